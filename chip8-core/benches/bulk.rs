@@ -0,0 +1,68 @@
+//! Compares running 500 instructions with `bulk_do_instructions` against calling
+//! `do_next_instruction` for each
+//! Run with `cargo bench --bench bulk`.
+
+use chip8_core::chip8::{Chip8, Chip8Builder, EmulationMode};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+// the number of instructions run in each batch, as the emulator might in a turbo frame
+const CYCLES: u32 = 500;
+
+// the number of batches timed for each way of running them
+const BATCHES: u32 = 20_000;
+
+// count and add up registers forever, as most of a game's instructions do
+const ARITHMETIC: [u8; 10] = [0x60, 0x00, 0x70, 0x01, 0x81, 0x04, 0x82, 0x12, 0x12, 0x02];
+
+// clear the display, then draw a digit at a random point forever
+const DRAWING: [u8; 12] = [
+    0x00, 0xE0, 0x70, 0x01, 0xF0, 0x29, 0xD1, 0x15, 0xC1, 0x0F, 0x12, 0x02,
+];
+
+/// Make a machine with a ROM loaded, with a fixed seed
+fn load(rom: &[u8]) -> Chip8 {
+    Chip8Builder::default()
+        .mode(EmulationMode::Chip8)
+        .seed(1)
+        .rom_bytes(rom)
+        .build()
+        .expect("the benchmark ROM should load")
+}
+
+/// Time running every batch of instructions
+fn time(rom: &[u8], run: impl Fn(&mut Chip8)) -> Duration {
+    let mut chip8 = load(rom);
+    let start = Instant::now();
+    for _ in 0..BATCHES {
+        run(&mut chip8);
+    }
+    black_box(&chip8);
+    start.elapsed()
+}
+
+/// Time both ways of running a ROM and print how they compare
+fn compare(name: &str, rom: &[u8]) {
+    let stepped = time(rom, |chip8| {
+        for _ in 0..CYCLES {
+            black_box(chip8.do_next_instruction()).expect("the instruction should run");
+        }
+    });
+    let bulk = time(rom, |chip8| {
+        let result = black_box(chip8.bulk_do_instructions(CYCLES));
+        assert!(result.errors.is_empty());
+    });
+
+    println!("{}:", name);
+    println!("  do_next_instruction x {}: {:?}", CYCLES, stepped / BATCHES);
+    println!("  bulk_do_instructions({}): {:?}", CYCLES, bulk / BATCHES);
+    println!(
+        "  speedup: {:.1}%",
+        (stepped.as_secs_f64() / bulk.as_secs_f64() - 1.0) * 100.0
+    );
+}
+
+fn main() {
+    compare("arithmetic", &ARITHMETIC);
+    compare("drawing", &DRAWING);
+}
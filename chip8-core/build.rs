@@ -0,0 +1,48 @@
+//! Fetching the test ROMs used by the integration tests
+//!
+//! With the `integration-tests` feature on, any ROM missing from `tests/roms` is downloaded with
+//! `curl`. A failed download is only a warning, so building never depends on the network.
+
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+// the test ROMs, and where to download them from
+const TEST_ROMS: [(&str, &str); 2] = [
+    (
+        "chip8-test-suite.ch8",
+        "https://github.com/Timendus/chip8-test-suite/raw/v2.1/bin/chip8-test-suite.ch8",
+    ),
+    (
+        "test_opcode.ch8",
+        "https://github.com/corax89/chip8-test-rom/raw/master/test_opcode.ch8",
+    ),
+];
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+    if env::var_os("CARGO_FEATURE_INTEGRATION_TESTS").is_none() {
+        return;
+    }
+
+    let dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").expect("cargo sets the manifest dir"))
+        .join("tests")
+        .join("roms");
+    for (name, url) in TEST_ROMS {
+        let path = dir.join(name);
+        println!("cargo:rerun-if-changed={}", path.display());
+        if path.exists() {
+            continue;
+        }
+
+        let downloaded = Command::new("curl")
+            .args(["--fail", "--silent", "--location", "--create-dirs", "--output"])
+            .arg(&path)
+            .arg(url)
+            .status()
+            .is_ok_and(|status| status.success());
+        if !downloaded {
+            println!("cargo:warning=could not download {} from {}", name, url);
+        }
+    }
+}
@@ -0,0 +1,76 @@
+//! Counting the instructions in a ROM without running it
+
+use crate::chip8::EmulationMode;
+use crate::disasm::disassemble;
+use alloc::collections::BTreeMap;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+/// How often one kind of instruction appears in a ROM
+#[derive(Debug, Clone, PartialEq)]
+pub struct FrequencyEntry {
+    /// The name of the instruction, such as `Draw`
+    pub name: &'static str,
+
+    /// The number of times the instruction appears
+    pub count: usize,
+
+    /// The share of all instructions in the ROM that are this one, as a percentage
+    pub percent: f32,
+
+    /// The least capable instruction set that has the instruction
+    pub mode: EmulationMode,
+}
+
+/// The instructions that appear in a ROM, with how often each does
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct OpcodeFrequencyReport {
+    /// Each kind of instruction that appears, most frequent first
+    pub entries: Vec<FrequencyEntry>,
+
+    /// The number of instructions in the ROM
+    pub total: usize,
+}
+
+impl OpcodeFrequencyReport {
+    /// Get the least capable instruction set that has every instruction in the ROM
+    pub fn required_mode(&self) -> EmulationMode {
+        self.entries
+            .iter()
+            .map(|entry| entry.mode)
+            .max()
+            .unwrap_or_default()
+    }
+}
+
+/// Count how often each kind of instruction appears in a ROM
+/// Every two bytes are taken as an instruction, as they are by `disassemble`, so sprites and
+/// other data that happen to look like instructions are counted too.
+pub fn analyze_frequency(rom: &[u8]) -> OpcodeFrequencyReport {
+    let lines = disassemble(rom);
+
+    let mut counts: BTreeMap<&'static str, (usize, EmulationMode)> = BTreeMap::new();
+    for line in &lines {
+        let instruction = &line.instruction;
+        counts
+            .entry(instruction.name())
+            .or_insert((0, instruction.required_mode()))
+            .0 += 1;
+    }
+
+    let total = lines.len();
+    let mut entries: Vec<FrequencyEntry> = counts
+        .into_iter()
+        .map(|(name, (count, mode))| FrequencyEntry {
+            name,
+            count,
+            percent: count as f32 * 100.0 / total as f32,
+            mode,
+        })
+        .collect();
+
+    // the counts are already in order of name, so ties stay in that order
+    entries.sort_by_key(|entry| Reverse(entry.count));
+
+    OpcodeFrequencyReport { entries, total }
+}
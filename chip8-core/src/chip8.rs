@@ -0,0 +1,2093 @@
+//! The virtual machine, and the constants that describe it
+//!
+//! The PC, I and SP registers are 16 bits wide on the hardware, so they are kept as `u16`, as
+//! save states and the debugging tools see them. They are only widened to `usize` where they
+//! index memory or the stack.
+
+#[cfg(feature = "std")]
+mod audio;
+mod breakpoint;
+mod builder;
+mod coverage;
+mod dispatch;
+#[cfg(feature = "std")]
+mod dump;
+mod font_set;
+mod history;
+mod inspector;
+mod memory_map;
+mod opcode_profiler;
+mod run;
+mod save_state;
+mod trace;
+mod watchpoint;
+
+#[cfg(feature = "std")]
+pub use crate::chip8::audio::{AudioSink, BUZZER_FREQUENCY};
+pub use crate::chip8::breakpoint::{Breakpoint, BreakpointManager};
+pub use crate::chip8::builder::Chip8Builder;
+pub use crate::chip8::coverage::InstructionCoverage;
+pub use crate::chip8::font_set::FontSet;
+pub use crate::chip8::history::DEFAULT_HISTORY_DEPTH;
+pub use crate::chip8::inspector::Chip8Inspector;
+pub use crate::chip8::memory_map::{MemoryMap, MemoryRegion};
+pub use crate::chip8::opcode_profiler::OpcodeProfiler;
+pub use crate::chip8::run::{BulkResult, FrameLog};
+pub use crate::chip8::save_state::SaveStateError;
+pub use crate::chip8::trace::{TraceEntry, DEFAULT_TRACE_DEPTH};
+pub use crate::chip8::watchpoint::{WatchMode, WatchpointEvent, MAX_WATCHPOINTS};
+#[cfg(feature = "std")]
+use crate::chip8::audio::AudioOutput;
+use crate::chip8::history::History;
+use crate::chip8::trace::ExecutionTrace;
+use crate::error::{Chip8Error, EmulatorError, ParseQuirksPresetError};
+use crate::input::Input;
+use crate::instructions::Instruction::*;
+use crate::instructions::{Instruction, Register};
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::mem;
+use core::ops::RangeInclusive;
+use core::str::FromStr;
+use log::{debug, info, trace, warn};
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "std")]
+use std::fs::File;
+#[cfg(feature = "std")]
+use std::io::{BufReader, ErrorKind, Read};
+#[cfg(feature = "std")]
+use std::path::Path;
+
+/// The size of RAM
+pub const MEM_SIZE: usize = 4096;
+
+/// The address that programs are loaded at
+pub const PROGMEM_START: u16 = 0x200;
+
+const STACK_SIZE: usize = 1024;
+const FONTMEM_START: u16 = 0x000;
+const LARGE_FONTMEM_START: u16 = 0x050;
+const PLANE_COUNT: usize = 4;
+const AUDIO_PATTERN_SIZE: usize = 16;
+
+/// The rate at which do_frame is called
+const DISPLAY_HZ: f32 = 60.0;
+
+/// The default rate at which the timers count down
+pub const DEFAULT_TIMER_HZ: u8 = 60;
+
+/// The default number of instructions run each frame
+pub const DEFAULT_CYCLES_PER_FRAME: u32 = 10;
+
+/// The font used by most modern interpreters
+const DEFAULT_FONT: [u8; 80] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+    0x20, 0x60, 0x20, 0x20, 0x70, // 1
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+    0x90, 0x90, 0xF0, 0x10, 0x10, // 4
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+    0xF0, 0x10, 0x20, 0x40, 0x40, // 7
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+    0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+    0xE0, 0x90, 0x90, 0x90, 0xE0, // D
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+];
+
+/// The SUPER-CHIP 8x10 font, only defined for the digits 0-9
+const LARGE_FONT: [u8; 100] = [
+    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+    0x3E, 0x7C, 0xE0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C, // 9
+];
+
+/// The display width in low resolution mode
+pub const LORES_WIDTH: usize = 64;
+
+/// The display height in low resolution mode
+pub const LORES_HEIGHT: usize = 32;
+
+/// The display width in high resolution mode (SUPER-CHIP only)
+pub const HIRES_WIDTH: usize = 128;
+
+/// The display height in high resolution mode (SUPER-CHIP only)
+pub const HIRES_HEIGHT: usize = 64;
+
+/// The display state
+/// VRAM is always large enough for high resolution mode, but only the top-left
+/// corner is used while in low resolution mode.
+pub type Vram = [[bool; HIRES_WIDTH]; HIRES_HEIGHT];
+
+/// The instruction set that the emulator understands
+/// Each instruction set is ordered after the ones it extends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default)]
+pub enum EmulationMode {
+    /// The original CHIP-8 instruction set
+    #[default]
+    Chip8,
+
+    /// The SUPER-CHIP 1.1 instruction set
+    SuperChip,
+
+    /// The XO-CHIP instruction set, a superset of SUPER-CHIP 1.1
+    XoChip,
+}
+
+impl FromStr for EmulationMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "chip8" | "chip-8" => Ok(EmulationMode::Chip8),
+            "schip" | "superchip" | "super-chip" => Ok(EmulationMode::SuperChip),
+            "xochip" | "xo-chip" => Ok(EmulationMode::XoChip),
+            _ => Err(format!("Unknown emulation mode '{}'", s)),
+        }
+    }
+}
+
+/// What happens to sprites drawn past the edges of the display
+/// Most games, such as Space Invaders, expect sprites to be clipped. Some demo ROMs draw sprites
+/// across the edges and expect them to wrap around to the other side.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WrapMode {
+    /// Pixels past the edges are not drawn
+    #[default]
+    Clip,
+
+    /// Pixels past the edges are drawn on the opposite side
+    Wrap,
+}
+
+impl FromStr for WrapMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "clip" => Ok(WrapMode::Clip),
+            "wrap" => Ok(WrapMode::Wrap),
+            _ => Err(format!("Unknown wrap mode '{}'", s)),
+        }
+    }
+}
+
+/// When the delay and sound timers count down
+/// Timers are meant to count down at a fixed rate, but some interpreters counted them down as
+/// instructions ran instead, which changes how fast their programs play.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimerDecrement {
+    /// The timers count down at the timer frequency, as frames pass
+    #[default]
+    OnFrame,
+
+    /// The timers count down once every N instructions, with 0 taken as 1
+    OnInstruction(u32),
+}
+
+/// A set of quirks, each of which is on if true
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Quirks {
+    /// Whether 8XY6 and 8XYE shift VX in place, ignoring VY
+    pub shift: bool,
+
+    /// Whether FX55 and FX65 leave I unchanged
+    pub load_store: bool,
+
+    /// Whether FX55 and FX65 add X to I instead of X + 1, if I isn't left unchanged
+    pub load_store_by_x: bool,
+
+    /// Whether BNNN jumps to NNN plus VX, where X is the high nibble of NNN, instead of V0
+    pub jump0: bool,
+
+    /// Whether DXYN waits for the next frame before drawing
+    pub vblank: bool,
+}
+
+/// The quirks of a well-known interpreter, so they don't have to be set one at a time
+///
+/// CHIP-48 and SUPER-CHIP 1.0 share their quirks, and differ only in the instructions they
+/// have. SUPER-CHIP 1.1 fixed FX55 and FX65 by leaving I alone, where the others added X to it.
+///
+/// | Preset          | shift | load_store | load_store_by_x | jump0 | vblank |
+/// |-----------------|-------|------------|-----------------|-------|--------|
+/// | `Chip8Original` | off   | off        | off             | off   | on     |
+/// | `Chip48`        | on    | off        | on              | on    | off    |
+/// | `SuperChip10`   | on    | off        | on              | on    | off    |
+/// | `SuperChip11`   | on    | on         | off             | on    | off    |
+/// | `XoChip`        | off   | off        | off             | off   | off    |
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuirksPreset {
+    /// The original COSMAC VIP interpreter
+    Chip8Original,
+
+    /// CHIP-48 on the HP-48 calculators
+    Chip48,
+
+    /// SUPER-CHIP 1.0
+    SuperChip10,
+
+    /// SUPER-CHIP 1.1
+    SuperChip11,
+
+    /// XO-CHIP, as run by Octo
+    XoChip,
+}
+
+impl QuirksPreset {
+    /// Get the quirks of the interpreter
+    pub fn quirks(self) -> Quirks {
+        match self {
+            QuirksPreset::Chip8Original => Quirks {
+                vblank: true,
+                ..Quirks::default()
+            },
+            QuirksPreset::Chip48 | QuirksPreset::SuperChip10 => Quirks {
+                shift: true,
+                load_store_by_x: true,
+                jump0: true,
+                ..Quirks::default()
+            },
+            QuirksPreset::SuperChip11 => Quirks {
+                shift: true,
+                load_store: true,
+                jump0: true,
+                ..Quirks::default()
+            },
+            QuirksPreset::XoChip => Quirks::default(),
+        }
+    }
+}
+
+impl FromStr for QuirksPreset {
+    type Err = ParseQuirksPresetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "chip8" | "chip-8" => Ok(QuirksPreset::Chip8Original),
+            "chip48" | "chip-48" => Ok(QuirksPreset::Chip48),
+            "schip10" => Ok(QuirksPreset::SuperChip10),
+            "schip11" | "schip" => Ok(QuirksPreset::SuperChip11),
+            "xochip" | "xo-chip" => Ok(QuirksPreset::XoChip),
+            _ => Err(ParseQuirksPresetError(s.into())),
+        }
+    }
+}
+
+/// Parse hex into bytes, taking the given number of digits at a time
+/// Whitespace is skipped, so tokens can be grouped however is easiest to read.
+fn parse_hex(hex: &str, digits: usize) -> Result<Vec<u8>, Chip8Error> {
+    let hex: Vec<char> = hex.chars().filter(|c| !c.is_whitespace()).collect();
+
+    let mut bytes = Vec::with_capacity(hex.len() / 2);
+    for (position, token) in hex.chunks(digits).enumerate() {
+        let token: String = token.iter().collect();
+        let value = match u16::from_str_radix(&token, 16) {
+            Ok(value) if token.len() == digits => value,
+            _ => return Err(Chip8Error::InvalidHexString { position, token }),
+        };
+        bytes.extend_from_slice(&value.to_be_bytes()[2 - digits / 2..]);
+    }
+
+    Ok(bytes)
+}
+
+/// CHIP-8 Registers
+#[derive(Clone, Copy, PartialEq)]
+struct Registers {
+    /// The 16 8-bit general-purpose registers
+    v: [u8; 16],
+
+    /// The 8-bit delay timer
+    /// Decrements at 60hz
+    dt: u8,
+
+    /// The 8-bit sound timer
+    /// Decrements at 60hz
+    st: u8,
+
+    /// The 16-bit index register
+    /// CHIP-8 addresses are only 12 bits wide, so only the lower 12 bits are used
+    i: u16,
+
+    /// The 16-bit program counter
+    pc: u16,
+
+    /// The stack pointer
+    /// In this implementation, the stack pointer is 16 bits.
+    sp: u16,
+
+    /// The RPL user flags (SUPER-CHIP only)
+    /// V registers can be saved here and read back later.
+    flags: [u8; 16],
+
+    /// The 8-bit audio pitch register (XO-CHIP only)
+    /// A pitch of 64 plays the audio pattern at 4000hz.
+    pitch: u8,
+}
+
+/// A copy of the CHIP-8 registers at a point in time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RegisterSnapshot {
+    /// The 16 8-bit general-purpose registers
+    pub v: [u8; 16],
+
+    /// The 8-bit delay timer
+    pub dt: u8,
+
+    /// The 8-bit sound timer
+    pub st: u8,
+
+    /// The 16-bit index register
+    pub i: u16,
+
+    /// The 16-bit program counter
+    pub pc: u16,
+
+    /// The stack pointer
+    pub sp: u16,
+
+    /// The RPL user flags (SUPER-CHIP only)
+    pub flags: [u8; 16],
+
+    /// The 8-bit audio pitch register (XO-CHIP only)
+    pub pitch: u8,
+
+    /// The number of instructions run since the program was loaded
+    pub cycles: u64,
+}
+
+/// CHIP-8 Memory
+#[derive(Clone, PartialEq)]
+pub struct Memory {
+    /// The main memory
+    /// The CHIP-8 has 4kB of RAM
+    ram: [u8; MEM_SIZE],
+
+    /// The stack
+    /// Used mostly for addresses for subroutine calls.
+    stack: [u16; STACK_SIZE],
+
+    /// The display state that instructions draw to, split into bit planes
+    /// For most modern implementations, the display is 64x32, or 128x64 in high resolution mode.
+    /// Only the first plane is used outside of XO-CHIP mode.
+    back_vram: [Vram; PLANE_COUNT],
+
+    /// The display planes as of the last frame, which is what is shown when double buffering
+    front_vram: [Vram; PLANE_COUNT],
+
+    /// The composite of all the display planes, as of the last frame
+    frame: Vram,
+
+    /// The 1-bit audio pattern buffer (XO-CHIP only)
+    audio_pattern: [u8; AUDIO_PATTERN_SIZE],
+}
+
+/// What happened in a frame, along with the display at the end of it
+#[derive(Debug, Clone, Copy)]
+pub struct FrameEvents<'a> {
+    /// The display state, with the planes composited together
+    pub vram: &'a Vram,
+
+    /// Whether the sound timer ran out this frame, ending the beep
+    pub sound_timer_expired: bool,
+
+    /// Whether the delay timer ran out this frame
+    pub delay_timer_expired: bool,
+
+    /// Whether any pixel changed since the last frame
+    pub display_updated: bool,
+}
+
+/// The state of the keypad
+#[derive(Clone, PartialEq)]
+pub struct InputState {
+    /// The keys that are currently pressed
+    curr: u16,
+
+    /// The register to store the next key in, while waiting for a keypress
+    waiting_for_key: Option<Register>,
+
+    /// The keys that have been pressed since waiting for a keypress started
+    pressed_while_waiting: u16,
+}
+
+/// A CHIP-8 virtual machine
+pub struct Chip8 {
+    /// The registers of the CHIP-8
+    registers: Registers,
+
+    /// Memory such as RAM, the stack, and VRAM
+    /// This is boxed so that moving a Chip8, as the builder methods do, stays cheap.
+    pub memory: Box<Memory>,
+
+    /// The current inputs, and the previous state of the input at the last cycle
+    input: InputState,
+
+    /// The instruction set being emulated
+    mode: EmulationMode,
+
+    /// What happens to sprites drawn past the edges of the display
+    wrap_mode: WrapMode,
+
+    /// Whether 8XY6 and 8XYE shift VX in place, ignoring VY
+    shift_quirk: bool,
+
+    /// Whether FX55 and FX65 leave I unchanged
+    load_store_quirk: bool,
+
+    /// Whether FX55 and FX65 add X to I instead of X + 1
+    load_store_by_x_quirk: bool,
+
+    /// Whether BNNN jumps to NNN plus VX, where X is the high nibble of NNN, instead of V0
+    jump0_quirk: bool,
+
+    /// Whether DXYN waits for the next frame before drawing
+    vblank_quirk: bool,
+
+    /// Whether FX1E sets VF when I goes past 0xFFF
+    add_index_overflow_quirk: bool,
+
+    /// Whether writes below program memory, where the fonts are, are blocked
+    write_protect_font: bool,
+
+    /// Whether the display is only shown once a frame ends, instead of as it is drawn
+    double_buffer: bool,
+
+    /// Whether anything has been drawn since the display was last shown
+    vram_dirty: bool,
+
+    /// Whether a sprite is being held back until the next frame
+    draw_pending: bool,
+
+    /// Whether the current frame has yet to end since the pending sprite was held back
+    waiting_for_vblank: bool,
+
+    /// The number of instructions that should be run each frame
+    cycles_per_frame: u32,
+
+    /// Whether or not the display is in high resolution mode
+    hires: bool,
+
+    /// A bitmask of the display planes that are drawn to
+    planes: u8,
+
+    /// Whether or not the program has asked to exit
+    exited: bool,
+
+    /// The address the program starts from, which is the start of program memory unless set
+    entry_point: u16,
+
+    /// The rate at which the delay and sound timers count down, in hz
+    timer_frequency: u8,
+
+    /// The time since the timers last counted down, in seconds
+    timer_accumulator: f32,
+
+    /// Whether the timers count down as frames pass or as instructions run
+    timer_decrement: TimerDecrement,
+
+    /// Whether the sound and delay timers ran out while counting down on instructions, since
+    /// the last frame
+    timer_expiry: (bool, bool),
+
+    /// The number of instructions run since the program was loaded
+    total_cycles: u64,
+
+    /// The number of frames run since the program was loaded
+    total_frames: u64,
+
+    /// The last instructions executed, if tracing is on
+    trace: Option<ExecutionTrace>,
+
+    /// The changes made by the last instructions executed, if history is on
+    history: Option<History>,
+
+    /// The number of times each opcode was executed, if profiling is on
+    profiler: Option<OpcodeProfiler>,
+
+    /// The addresses instructions were executed from, if coverage tracking is on
+    coverage: Option<InstructionCoverage>,
+
+    /// Where the sound is sent, if anywhere
+    #[cfg(feature = "std")]
+    audio: Option<AudioOutput>,
+
+    /// The watched addresses, and what kind of access they are watched for
+    /// This is a fixed size, so that checking every memory access doesn't allocate.
+    watchpoints: [Option<(u16, WatchMode)>; MAX_WATCHPOINTS],
+
+    /// The watched access made by the last instruction, if any
+    watchpoint_hit: Option<WatchpointEvent>,
+
+    /// The source of random numbers for CXNN
+    /// This is the generator behind `StdRng`, used directly so its position can be saved.
+    rng: ChaCha12Rng,
+
+    /// The regions of memory that the font and ROM were loaded into
+    memory_map: MemoryMap,
+}
+
+impl Default for Chip8 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for Chip8 {
+    /// Copy the whole machine, which then runs independently of the original
+    /// The audio sink is not copied, as sound can only be sent to one place.
+    fn clone(&self) -> Self {
+        Self {
+            registers: self.registers,
+            memory: self.memory.clone(),
+            input: self.input.clone(),
+            mode: self.mode,
+            wrap_mode: self.wrap_mode,
+            shift_quirk: self.shift_quirk,
+            load_store_quirk: self.load_store_quirk,
+            load_store_by_x_quirk: self.load_store_by_x_quirk,
+            jump0_quirk: self.jump0_quirk,
+            vblank_quirk: self.vblank_quirk,
+            add_index_overflow_quirk: self.add_index_overflow_quirk,
+            write_protect_font: self.write_protect_font,
+            double_buffer: self.double_buffer,
+            vram_dirty: self.vram_dirty,
+            draw_pending: self.draw_pending,
+            waiting_for_vblank: self.waiting_for_vblank,
+            cycles_per_frame: self.cycles_per_frame,
+            hires: self.hires,
+            planes: self.planes,
+            exited: self.exited,
+            entry_point: self.entry_point,
+            timer_frequency: self.timer_frequency,
+            timer_accumulator: self.timer_accumulator,
+            timer_decrement: self.timer_decrement,
+            timer_expiry: self.timer_expiry,
+            total_cycles: self.total_cycles,
+            total_frames: self.total_frames,
+            trace: self.trace.clone(),
+            history: self.history.clone(),
+            profiler: self.profiler.clone(),
+            coverage: self.coverage.clone(),
+            #[cfg(feature = "std")]
+            audio: None,
+            watchpoints: self.watchpoints,
+            watchpoint_hit: self.watchpoint_hit,
+            rng: self.rng.clone(),
+            memory_map: self.memory_map.clone(),
+        }
+    }
+}
+
+impl PartialEq for Chip8 {
+    /// Check if two machines are in the same state, with the same settings
+    /// The counters, trace, history, opcode profile, coverage and audio sink only record or pass
+    /// on what the machine does, so they are left out.
+    fn eq(&self, other: &Self) -> bool {
+        self.registers == other.registers
+            && self.memory == other.memory
+            && self.input == other.input
+            && self.mode == other.mode
+            && self.wrap_mode == other.wrap_mode
+            && self.shift_quirk == other.shift_quirk
+            && self.load_store_quirk == other.load_store_quirk
+            && self.load_store_by_x_quirk == other.load_store_by_x_quirk
+            && self.jump0_quirk == other.jump0_quirk
+            && self.vblank_quirk == other.vblank_quirk
+            && self.add_index_overflow_quirk == other.add_index_overflow_quirk
+            && self.write_protect_font == other.write_protect_font
+            && self.double_buffer == other.double_buffer
+            && self.vram_dirty == other.vram_dirty
+            && self.draw_pending == other.draw_pending
+            && self.waiting_for_vblank == other.waiting_for_vblank
+            && self.cycles_per_frame == other.cycles_per_frame
+            && self.hires == other.hires
+            && self.planes == other.planes
+            && self.exited == other.exited
+            && self.entry_point == other.entry_point
+            && self.timer_frequency == other.timer_frequency
+            && self.timer_accumulator == other.timer_accumulator
+            && self.timer_decrement == other.timer_decrement
+            && self.timer_expiry == other.timer_expiry
+            && self.watchpoints == other.watchpoints
+            && self.watchpoint_hit == other.watchpoint_hit
+            && self.rng == other.rng
+            && self.memory_map == other.memory_map
+    }
+}
+
+impl Chip8 {
+    /// Make a new Chip8
+    pub fn new() -> Self {
+        Self {
+            registers: Registers {
+                v: [0; 16],
+                dt: 0,
+                st: 0,
+                i: 0,
+                pc: 0x200,
+                sp: 0,
+                flags: [0; 16],
+                pitch: 64,
+            },
+            memory: Box::new(Memory {
+                ram: [0; MEM_SIZE],
+                stack: [0; STACK_SIZE],
+                back_vram: [[[false; HIRES_WIDTH]; HIRES_HEIGHT]; PLANE_COUNT],
+                front_vram: [[[false; HIRES_WIDTH]; HIRES_HEIGHT]; PLANE_COUNT],
+                frame: [[false; HIRES_WIDTH]; HIRES_HEIGHT],
+                audio_pattern: [0; AUDIO_PATTERN_SIZE],
+            }),
+            input: InputState {
+                curr: 0b0000_0000_0000_0000,
+                waiting_for_key: None,
+                pressed_while_waiting: 0b0000_0000_0000_0000,
+            },
+            mode: EmulationMode::Chip8,
+            wrap_mode: WrapMode::Clip,
+            shift_quirk: false,
+            load_store_quirk: false,
+            load_store_by_x_quirk: false,
+            jump0_quirk: false,
+            vblank_quirk: false,
+            add_index_overflow_quirk: false,
+            write_protect_font: true,
+            double_buffer: true,
+            vram_dirty: true,
+            draw_pending: false,
+            waiting_for_vblank: false,
+            cycles_per_frame: DEFAULT_CYCLES_PER_FRAME,
+            hires: false,
+            planes: 0b0001,
+            exited: false,
+            entry_point: PROGMEM_START,
+            timer_frequency: DEFAULT_TIMER_HZ,
+            timer_accumulator: 0.0,
+            timer_decrement: TimerDecrement::OnFrame,
+            timer_expiry: (false, false),
+            total_cycles: 0,
+            total_frames: 0,
+            trace: None,
+            history: None,
+            profiler: None,
+            coverage: None,
+            #[cfg(feature = "std")]
+            audio: None,
+            watchpoints: [None; MAX_WATCHPOINTS],
+            watchpoint_hit: None,
+            // without std there's no entropy to seed from, so runs repeat unless a seed is given
+            #[cfg(feature = "std")]
+            rng: ChaCha12Rng::from_entropy(),
+            #[cfg(not(feature = "std"))]
+            rng: ChaCha12Rng::seed_from_u64(0),
+            memory_map: MemoryMap::default(),
+        }
+    }
+
+    /// Set the instruction set to emulate
+    pub fn with_mode(mut self, mode: EmulationMode) -> Self {
+        self.mode = mode;
+
+        // the large font is only available to SUPER-CHIP and XO-CHIP programs
+        if mode != EmulationMode::Chip8 {
+            let start = LARGE_FONTMEM_START as usize;
+            self.memory.ram[start..start + LARGE_FONT.len()].copy_from_slice(&LARGE_FONT);
+            self.memory_map.set_large_font(LARGE_FONTMEM_START, LARGE_FONT.len());
+        }
+
+        self
+    }
+
+    /// Set what happens to sprites drawn past the edges of the display
+    pub fn with_wrap_mode(mut self, wrap_mode: WrapMode) -> Self {
+        self.wrap_mode = wrap_mode;
+        self
+    }
+
+    /// Set the rate at which the delay and sound timers count down
+    /// This is 60hz on most systems, but some programs were written for 50hz systems.
+    pub fn with_timer_frequency(mut self, hz: u8) -> Self {
+        self.timer_frequency = hz;
+        self
+    }
+
+    /// Set whether the delay and sound timers count down as frames pass, or as instructions run
+    /// The timer frequency is only used when they count down on frames.
+    pub fn with_timer_decrement(mut self, timer_decrement: TimerDecrement) -> Self {
+        self.timer_decrement = timer_decrement;
+        self
+    }
+
+    /// Set whether 8XY6 and 8XYE shift VX in place, as on SUPER-CHIP, instead of shifting VY
+    pub fn with_shift_quirk(mut self, enabled: bool) -> Self {
+        self.shift_quirk = enabled;
+        self
+    }
+
+    /// Set whether FX55 and FX65 leave I unchanged, as on SUPER-CHIP, instead of incrementing it
+    pub fn with_load_store_quirk(mut self, enabled: bool) -> Self {
+        self.load_store_quirk = enabled;
+        self
+    }
+
+    /// Set whether FX55 and FX65 add X to I, as on CHIP-48 and SUPER-CHIP 1.0, instead of X + 1
+    /// This has no effect when the load/store quirk leaves I unchanged.
+    pub fn with_load_store_by_x_quirk(mut self, enabled: bool) -> Self {
+        self.load_store_by_x_quirk = enabled;
+        self
+    }
+
+    /// Set whether BNNN jumps to NNN plus VX, as on CHIP-48, instead of NNN plus V0
+    /// X is the high nibble of NNN.
+    pub fn with_jump0_quirk(mut self, enabled: bool) -> Self {
+        self.jump0_quirk = enabled;
+        self
+    }
+
+    /// Set whether DXYN waits for the next frame before drawing, as on the original COSMAC VIP
+    /// This limits programs to drawing one sprite per frame.
+    pub fn with_vblank_quirk(mut self, enabled: bool) -> Self {
+        self.vblank_quirk = enabled;
+        self
+    }
+
+    /// Set whether FX1E sets VF to 1 when I goes past 0xFFF, and to 0 otherwise
+    /// This was done by the Amiga interpreter, and Spacefight 2091! depends on it.
+    pub fn with_add_index_overflow_quirk(mut self, enabled: bool) -> Self {
+        self.add_index_overflow_quirk = enabled;
+        self
+    }
+
+    /// Set whether writes below program memory are blocked, to keep the fonts intact
+    /// Blocked writes are logged and skipped, and the program carries on running.
+    pub fn with_write_protect_font(mut self, enabled: bool) -> Self {
+        self.write_protect_font = enabled;
+        self
+    }
+
+    /// Set whether the display is only shown once each frame ends, which is on by default
+    /// Without double buffering, a frame can show a display that is only partly drawn.
+    pub fn with_double_buffer(mut self, enabled: bool) -> Self {
+        self.double_buffer = enabled;
+        self
+    }
+
+    /// Seed the random numbers used by CXNN, so that runs with the same seed are the same
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.rng = ChaCha12Rng::seed_from_u64(seed);
+        self
+    }
+
+    /// Set the number of instructions that should be run each frame
+    pub fn with_cycles_per_frame(mut self, cycles_per_frame: u32) -> Self {
+        self.cycles_per_frame = cycles_per_frame;
+        self
+    }
+
+    /// Restart the program from the beginning
+    /// Registers, the stack, the display, timers and input are cleared, but RAM is left as it is,
+    /// so the ROM and font stay loaded. The emulator settings are kept.
+    pub fn reset(&mut self) {
+        self.registers = Registers {
+            v: [0; 16],
+            dt: 0,
+            st: 0,
+            i: 0,
+            pc: self.entry_point,
+            sp: 0,
+            flags: [0; 16],
+            pitch: 64,
+        };
+
+        self.memory.stack = [0; STACK_SIZE];
+        self.memory.back_vram = [[[false; HIRES_WIDTH]; HIRES_HEIGHT]; PLANE_COUNT];
+        self.memory.front_vram = [[[false; HIRES_WIDTH]; HIRES_HEIGHT]; PLANE_COUNT];
+        self.vram_dirty = true;
+        self.memory.frame = [[false; HIRES_WIDTH]; HIRES_HEIGHT];
+        self.memory.audio_pattern = [0; AUDIO_PATTERN_SIZE];
+
+        self.input = InputState {
+            curr: 0b0000_0000_0000_0000,
+            waiting_for_key: None,
+            pressed_while_waiting: 0b0000_0000_0000_0000,
+        };
+
+        self.hires = false;
+        self.planes = 0b0001;
+        self.exited = false;
+        self.draw_pending = false;
+        self.waiting_for_vblank = false;
+        self.timer_accumulator = 0.0;
+        self.timer_expiry = (false, false);
+        self.total_cycles = 0;
+        self.total_frames = 0;
+
+        if let Some(history) = &mut self.history {
+            history.clear();
+        }
+    }
+
+    /// Restart the program from the beginning, reseeding the random numbers used by CXNN
+    pub fn reset_with_seed(&mut self, seed: u64) {
+        self.reset();
+        self.rng = ChaCha12Rng::seed_from_u64(seed);
+    }
+
+    /// Load a rom into memory
+    #[cfg(feature = "std")]
+    pub fn load_rom(self, path: impl AsRef<Path>) -> Result<Self, Chip8Error> {
+        let path = path.as_ref();
+
+        // open the file
+        let file: File = File::open(path).map_err(|e| match e.kind() {
+            ErrorKind::NotFound => Chip8Error::RomNotFound(path.to_path_buf()),
+            _ => Chip8Error::RomReadError(e),
+        })?;
+
+        self.load_rom_from_reader(BufReader::new(file))
+    }
+
+    /// Load a rom into memory from anything that can be read, such as a stream or a cursor
+    /// The reader is read until it runs out.
+    #[cfg(feature = "std")]
+    pub fn load_rom_from_reader(self, mut reader: impl Read) -> Result<Self, Chip8Error> {
+        let mut bytes: Vec<u8> = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(Chip8Error::RomReadError)?;
+
+        self.load_rom_from_bytes(&bytes)
+    }
+
+    /// Load a rom into memory from a slice
+    pub fn load_rom_from_bytes(mut self, bytes: &[u8]) -> Result<Self, Chip8Error> {
+        // make sure the rom fits in program memory
+        let max = MEM_SIZE - PROGMEM_START as usize;
+        if bytes.len() > max {
+            return Err(Chip8Error::RomTooLarge {
+                size: bytes.len(),
+                max,
+            });
+        }
+
+        let start = PROGMEM_START as usize;
+        self.memory.ram[start..start + bytes.len()].copy_from_slice(bytes);
+        self.memory_map.set_program(PROGMEM_START, bytes.len());
+        info!("Loaded a {} byte ROM", bytes.len());
+
+        Ok(self)
+    }
+
+    /// Load a rom written as hex opcodes, such as "00E0 6000 6100"
+    /// Whitespace is ignored, and every four digits make an opcode.
+    pub fn load_rom_from_hex_string(self, hex: &str) -> Result<Self, Chip8Error> {
+        let bytes = parse_hex(hex, 4)?;
+        self.load_rom_from_bytes(&bytes)
+    }
+
+    /// Load a rom written as hex bytes, such as "00 E0 60 00"
+    /// Whitespace is ignored, and every two digits make a byte.
+    pub fn load_rom_from_hex_bytes(self, hex: &str) -> Result<Self, Chip8Error> {
+        let bytes = parse_hex(hex, 2)?;
+        self.load_rom_from_bytes(&bytes)
+    }
+
+    /// Load a rom into memory from a slice, starting it from the given address instead of 0x200
+    /// This is for ROMs with a self-test elsewhere, or several programs one after another.
+    pub fn load_rom_with_entry(self, bytes: &[u8], entry: u16) -> Result<Self, Chip8Error> {
+        self.load_rom_from_bytes(bytes)?.with_entry_point(entry)
+    }
+
+    /// Start the loaded ROM from the given address, which must be even and inside the ROM
+    /// Resetting the program also starts it again from here.
+    pub fn with_entry_point(mut self, entry: u16) -> Result<Self, Chip8Error> {
+        let in_rom = self.memory_map.region_at(entry) == MemoryRegion::ProgramCode;
+        if !entry.is_multiple_of(2) || !in_rom {
+            return Err(Chip8Error::InvalidEntryPoint(entry));
+        }
+
+        self.entry_point = entry;
+        self.registers.pc = entry;
+        Ok(self)
+    }
+
+    /// Load a font into memory from a file
+    #[cfg(feature = "std")]
+    pub fn load_font_file(self, path: impl AsRef<Path>) -> Result<Self, Chip8Error> {
+        let path = path.as_ref();
+
+        // open the file
+        let file = File::open(path).map_err(|_| Chip8Error::FontNotFound(path.to_path_buf()))?;
+
+        self.load_font_from_reader(BufReader::new(file))
+    }
+
+    /// Load a font into memory from anything that can be read, such as a stream or a cursor
+    /// The reader is read until it runs out.
+    #[cfg(feature = "std")]
+    pub fn load_font_from_reader(self, mut reader: impl Read) -> Result<Self, Chip8Error> {
+        let mut bytes: Vec<u8> = Vec::new();
+        reader
+            .read_to_end(&mut bytes)
+            .map_err(Chip8Error::FontReadError)?;
+
+        self.load_font_from_bytes(&bytes)
+    }
+
+    /// Load a font into memory from a slice
+    pub fn load_font_from_bytes(mut self, bytes: &[u8]) -> Result<Self, Chip8Error> {
+        // make sure the font doesn't run into program memory
+        if bytes.len() > (PROGMEM_START - FONTMEM_START) as usize {
+            return Err(Chip8Error::FontTooLarge);
+        }
+
+        // load the font into memory
+        let start = FONTMEM_START as usize;
+        self.memory.ram[start..start + bytes.len()].copy_from_slice(bytes);
+        self.memory_map.set_font(FONTMEM_START, bytes.len());
+        info!("Loaded a {} byte font", bytes.len());
+
+        Ok(self)
+    }
+
+    /// Get the program as it is in memory now, including any changes it made to its own code
+    pub fn export_rom(&self) -> Vec<u8> {
+        let start = PROGMEM_START as usize;
+        self.memory.ram[start..start + self.memory_map.program_len()].to_vec()
+    }
+
+    /// Get the small font as it is in memory now
+    pub fn export_font(&self) -> Vec<u8> {
+        let start = FONTMEM_START as usize;
+        self.memory.ram[start..start + self.memory_map.font_len()].to_vec()
+    }
+
+    /// Get the number of instructions that should be run each frame
+    pub fn cycles_per_frame(&self) -> u32 {
+        self.cycles_per_frame
+    }
+
+    /// Check if a tone is playing
+    pub fn get_tone(&self) -> bool {
+        self.registers.st > 0
+    }
+
+    /// Get the rate in hz at which the bits of the audio pattern should be played
+    #[cfg(feature = "std")]
+    pub fn get_audio_frequency(&self) -> f32 {
+        4000.0 * 2.0_f32.powf((self.registers.pitch as f32 - 64.0) / 48.0)
+    }
+
+    /// Get the audio pattern buffer
+    /// Outside of XO-CHIP mode, this is never written to.
+    pub fn get_audio_pattern(&self) -> &[u8; AUDIO_PATTERN_SIZE] {
+        &self.memory.audio_pattern
+    }
+
+    /// Get the color of a pixel, as it is shown
+    /// The color is a bitmask of the display planes that the pixel is set in.
+    pub fn get_pixel_color(&self, x: usize, y: usize) -> u8 {
+        let vram = self.shown_vram();
+        (0..PLANE_COUNT)
+            .filter(|&plane| vram[plane][y][x])
+            .fold(0, |color, plane| color | (0x1 << plane))
+    }
+
+    /// Get a copy of the registers
+    pub fn snapshot_registers(&self) -> RegisterSnapshot {
+        RegisterSnapshot {
+            v: self.registers.v,
+            dt: self.registers.dt,
+            st: self.registers.st,
+            i: self.registers.i,
+            pc: self.registers.pc,
+            sp: self.registers.sp,
+            flags: self.registers.flags,
+            pitch: self.registers.pitch,
+            cycles: self.total_cycles,
+        }
+    }
+
+    /// Get the number of instructions run since the program was loaded or reset
+    pub fn total_cycles(&self) -> u64 {
+        self.total_cycles
+    }
+
+    /// Get the number of frames run since the program was loaded or reset
+    pub fn total_frames(&self) -> u64 {
+        self.total_frames
+    }
+
+    /// Set one of the registers V0 to VF, such as from the debugger
+    pub fn set_register(&mut self, reg: u8, value: u8) -> Result<(), Chip8Error> {
+        let register = self
+            .registers
+            .v
+            .get_mut(reg as usize)
+            .ok_or(Chip8Error::InvalidRegister(reg))?;
+        *register = value;
+        Ok(())
+    }
+
+    /// Set the index register
+    pub fn set_i(&mut self, value: u16) {
+        self.registers.i = value;
+    }
+
+    /// Set the address of the next instruction, which must be even and inside memory
+    pub fn set_pc(&mut self, value: u16) -> Result<(), Chip8Error> {
+        if !value.is_multiple_of(2) || value as usize >= MEM_SIZE {
+            return Err(Chip8Error::InvalidProgramCounter(value));
+        }
+
+        self.registers.pc = value;
+        Ok(())
+    }
+
+    /// Set the delay timer
+    pub fn set_delay_timer(&mut self, value: u8) {
+        self.registers.dt = value;
+    }
+
+    /// Set the sound timer
+    pub fn set_sound_timer(&mut self, value: u8) {
+        self.registers.st = value;
+    }
+
+    /// Read a byte of RAM, or None if the address is out of range
+    pub fn peek_ram(&self, addr: u16) -> Option<u8> {
+        self.memory.ram.get(addr as usize).copied()
+    }
+
+    /// Copy bytes into RAM, such as to patch a program while it runs
+    /// Fails if the bytes run past the end of memory, or would overwrite the font while it is
+    /// write protected. The bytes are written as an instruction would write them, so watchpoints
+    /// see the patch and `step_back` undoes it.
+    pub fn inject_ram(&mut self, addr: u16, bytes: &[u8]) -> Result<(), Chip8Error> {
+        let start = addr as usize;
+        let end = start + bytes.len();
+        if end > MEM_SIZE {
+            return Err(Chip8Error::OutOfBoundsMemoryAccess {
+                addr,
+                len: bytes.len(),
+            });
+        }
+        if self.write_protect_font && !bytes.is_empty() && start < PROGMEM_START as usize {
+            return Err(Chip8Error::WriteProtectedRegion(addr));
+        }
+
+        self.watchpoint_hit = None;
+        let delta = self.begin_delta();
+        for (addr, &byte) in (start..end).zip(bytes) {
+            // the range and write protection were checked above, so the write can't fail
+            let _ = self.write_byte(addr, byte);
+        }
+        self.end_delta(delta);
+        Ok(())
+    }
+
+    /// Get the return addresses on the stack, from the outermost call to the innermost
+    pub fn peek_stack(&self) -> &[u16] {
+        &self.memory.stack[..(self.registers.sp as usize).min(STACK_SIZE)]
+    }
+
+    /// Get the number of subroutine calls that have yet to return
+    pub fn stack_depth(&self) -> usize {
+        self.registers.sp as usize
+    }
+
+    /// Check if a pixel is set in any display plane, or None if it is off the display
+    /// When double buffering, this is the display as of the last frame.
+    pub fn peek_vram(&self, x: usize, y: usize) -> Option<bool> {
+        let (width, height) = self.display_size();
+        if x >= width || y >= height {
+            return None;
+        }
+
+        Some(self.shown_vram().iter().any(|plane| plane[y][x]))
+    }
+
+    /// Get the display planes that are shown, which are only updated each frame when double
+    /// buffering
+    fn shown_vram(&self) -> &[Vram; PLANE_COUNT] {
+        if self.double_buffer {
+            &self.memory.front_vram
+        } else {
+            &self.memory.back_vram
+        }
+    }
+
+    /// Get the size of the display in the current resolution mode
+    pub fn display_size(&self) -> (usize, usize) {
+        if self.hires {
+            (HIRES_WIDTH, HIRES_HEIGHT)
+        } else {
+            (LORES_WIDTH, LORES_HEIGHT)
+        }
+    }
+
+    /// Check if the program has asked to exit
+    pub fn has_exited(&self) -> bool {
+        self.exited
+    }
+
+    /// Count down the timers and get the display state, along with what happened in the frame
+    /// It is assumed that this is called 60 times a second
+    pub fn do_frame(&mut self) -> FrameEvents<'_> {
+        self.total_frames += 1;
+
+        // let any pending sprite be drawn
+        self.waiting_for_vblank = false;
+
+        // play the sound for this frame before the sound timer counts down
+        #[cfg(feature = "std")]
+        self.output_audio();
+
+        // timers counted down by instructions may have run out since the last frame
+        let (mut sound_timer_expired, mut delay_timer_expired) =
+            mem::take(&mut self.timer_expiry);
+
+        // count down the timers as many times as they would have in a frame,
+        // keeping any leftover time for the next frame
+        if self.timer_decrement == TimerDecrement::OnFrame {
+            let timer_period: f32 = 1.0 / self.timer_frequency as f32;
+            self.timer_accumulator += 1.0 / DISPLAY_HZ;
+            while self.timer_accumulator >= timer_period {
+                self.timer_accumulator -= timer_period;
+
+                let (sound, delay) = self.count_down_timers();
+                sound_timer_expired |= sound;
+                delay_timer_expired |= delay;
+            }
+        }
+
+        let display_updated = self.swap_vram();
+
+        FrameEvents {
+            vram: &self.memory.frame,
+            sound_timer_expired,
+            delay_timer_expired,
+            display_updated,
+        }
+    }
+
+    /// Count the timers down by one, returning whether the sound and delay timers ran out
+    fn count_down_timers(&mut self) -> (bool, bool) {
+        let mut sound_timer_expired = false;
+        let mut delay_timer_expired = false;
+
+        // decrement ST if needed
+        if self.registers.st > 0 {
+            self.registers.st -= 1;
+            sound_timer_expired = self.registers.st == 0;
+            debug!("Sound timer decremented to {}", self.registers.st);
+        }
+
+        // decrement DT if needed
+        if self.registers.dt > 0 {
+            self.registers.dt -= 1;
+            delay_timer_expired = self.registers.dt == 0;
+            debug!("Delay timer decremented to {}", self.registers.dt);
+        }
+
+        (sound_timer_expired, delay_timer_expired)
+    }
+
+    /// Count the timers down once an instruction has run, if they count down every N
+    /// instructions and this was the Nth
+    fn count_down_on_instruction(&mut self) {
+        if let TimerDecrement::OnInstruction(n) = self.timer_decrement {
+            if self.total_cycles.is_multiple_of(n.max(1) as u64) {
+                let (sound, delay) = self.count_down_timers();
+                self.timer_expiry.0 |= sound;
+                self.timer_expiry.1 |= delay;
+            }
+        }
+    }
+
+    /// Show everything drawn so far, without waiting for the frame to end
+    /// This is for when the display can change without a frame passing, such as while stepping
+    /// through a paused program. Returns whether anything changed since it was last shown.
+    pub fn present_display(&mut self) -> bool {
+        self.swap_vram()
+    }
+
+    /// Show what was drawn in the frame, returning whether anything changed since the last one
+    fn swap_vram(&mut self) -> bool {
+        // nothing can have changed if nothing was drawn
+        if !core::mem::take(&mut self.vram_dirty) {
+            return false;
+        }
+
+        // the planes are small enough that copying them is cheaper than juggling two buffers
+        // they are copied even without double buffering, to tell what changed next time
+        let display_updated = self.memory.front_vram != self.memory.back_vram;
+        self.memory.front_vram = self.memory.back_vram;
+
+        // composite the display planes
+        for y in 0..HIRES_HEIGHT {
+            for x in 0..HIRES_WIDTH {
+                self.memory.frame[y][x] = self.memory.front_vram.iter().any(|plane| plane[y][x]);
+            }
+        }
+
+        display_updated
+    }
+
+    /// Update the inputs
+    /// Fails if the key isn't one of the 16 on the keypad.
+    pub fn change_input(&mut self, input: Input) -> Result<(), Chip8Error> {
+        let (Input::Pressed(key) | Input::Unpressed(key)) = input;
+        if key > 0xF {
+            return Err(Chip8Error::InvalidKey(key));
+        }
+
+        // update the current input
+        match input {
+            Input::Pressed(key) => {
+                debug!("Key {:X} pressed", key);
+                self.input.curr |= 0x1 << key; // set the n-th bit to 1
+
+                // remember the keypress if waiting for one
+                if self.input.waiting_for_key.is_some() {
+                    self.input.pressed_while_waiting |= 0x1 << key;
+                }
+            }
+            Input::Unpressed(key) => {
+                debug!("Key {:X} released", key);
+                self.input.curr &= !(0x1 << key); // set the n-th bit to 0
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the keys that are currently pressed, with key N in bit N
+    pub fn pressed_keys(&self) -> u16 {
+        self.input.curr
+    }
+
+    /// Run a single opcode as if it were the next instruction
+    /// The opcode is written into memory at PC, and both the program's bytes there and PC are put
+    /// back afterwards, so jumps and skips have no lasting effect and the next step runs the
+    /// program's own instruction.
+    #[cfg(feature = "debug")]
+    pub fn inject_opcode(&mut self, opcode: u16) -> Result<u16, EmulatorError> {
+        let pc = self.registers.pc;
+        let addr = pc as usize;
+        let original = [self.safe_read_byte(addr)?, self.safe_read_byte(addr + 1)?];
+        let [high, low] = opcode.to_be_bytes();
+        self.safe_write_byte(addr, high)?;
+        self.safe_write_byte(addr + 1, low)?;
+
+        let result = self.do_next_instruction();
+        self.memory.ram[addr..addr + 2].copy_from_slice(&original);
+        self.registers.pc = pc;
+        result
+    }
+
+    /// Do the next instruction and return the result, containing the opcode that was just dealt with
+    /// This should be called about 500 times a second
+    /// Or, a little under 9 times per call to do_frame()
+    pub fn do_next_instruction(&mut self) -> Result<u16, EmulatorError> {
+        // keep what's needed to undo the instruction, even if it fails partway through
+        let delta = self.begin_delta();
+        let result = self.execute_next_instruction();
+        self.end_delta(delta);
+
+        if result.is_ok() {
+            self.total_cycles += 1;
+            self.count_down_on_instruction();
+        }
+        result
+    }
+
+    /// Do the next instruction, without recording it in the history
+    fn execute_next_instruction(&mut self) -> Result<u16, EmulatorError> {
+        // only accesses made by this instruction are reported
+        self.watchpoint_hit = None;
+
+        // get the current opcode for returning results
+        let current_opcode: u16 = self.get_current_opcode()?;
+
+        // keep a record of the instruction for debugging
+        self.record_trace(current_opcode);
+        self.record_opcode(current_opcode);
+        self.record_coverage();
+
+        // the log message is only formatted when trace logging is on
+        trace!(
+            "[{}] [PC={:#05x}] {:#06X} {}",
+            self.total_cycles,
+            self.registers.pc,
+            current_opcode,
+            self.get_current_instruction().unwrap_or(Unknown)
+        );
+
+        // run the instruction with the handler for its opcode
+        let result = self.dispatch(current_opcode);
+        self.finish_trace();
+        result?;
+
+        // point the PC to the next instruction
+        // this wraps back around after a jump to 0x000
+        self.registers.pc = self.registers.pc.wrapping_add(2);
+
+        Ok(current_opcode)
+    }
+
+    /// Report an opcode that isn't valid in the current emulation mode
+    fn unknown_opcode(&self, opcode: u16) -> EmulatorError {
+        warn!("Unknown opcode {:#06x} at {:#05x}", opcode, self.registers.pc);
+        EmulatorError::UnknownOpcode {
+            opcode,
+            pc: self.registers.pc,
+        }
+    }
+
+    /// Check whether a sprite should be held back until the next frame, with the vblank quirk
+    /// The first time a sprite is drawn it is held back, and it is drawn once a frame has passed.
+    fn hold_for_vblank(&mut self) -> bool {
+        if !self.vblank_quirk {
+            return false;
+        }
+
+        if !self.draw_pending {
+            // start waiting for the end of the frame
+            self.draw_pending = true;
+            self.waiting_for_vblank = true;
+        }
+
+        if self.waiting_for_vblank {
+            return true;
+        }
+
+        // the frame has ended, so the sprite can be drawn
+        self.draw_pending = false;
+        false
+    }
+
+    /// Draw a sprite from memory starting at I at the coordinates in VX and VY
+    /// Each row of the sprite is `width` pixels (8 or 16) wide.
+    /// With more than one display plane selected, each plane's sprite follows the last in memory.
+    fn draw_sprite(
+        &mut self,
+        regx: Register,
+        regy: Register,
+        height: usize,
+        width: usize,
+    ) -> Result<(), EmulatorError> {
+        // get the size of the display
+        let (display_width, display_height) = self.display_size();
+
+        // get x and y to start drawing the sprite
+        let start_x: usize = self.registers.v[regx] as usize % display_width;
+        let start_y: usize = self.registers.v[regy] as usize % display_height;
+
+        // get the number of bytes in each row of the sprite
+        let row_bytes: usize = width / 8;
+
+        // get the location of the sprite for the first plane
+        let mut sprite_start: usize = self.registers.i as usize;
+
+        // whether any pixel was turned off
+        // VF isn't set until the whole sprite is drawn, as VX or VY could be VF
+        let mut collision: bool = false;
+
+        for plane in self.selected_planes() {
+            for row in 0..height {
+                for col in 0..width {
+                    // get this pixel in the sprite
+                    let byte: u8 = self.read_byte(sprite_start + row * row_bytes + col / 8)?;
+                    let pixel_state: bool = (byte & (0x1 << (7 - col % 8))) > 0;
+
+                    // only attempt to change this sprite if this bit is set
+                    if pixel_state {
+                        // get the x and y for this pixel
+                        let mut x = start_x + col;
+                        let mut y = start_y + row;
+
+                        // deal with pixels that go off the side of the screen
+                        if x >= display_width || y >= display_height {
+                            match self.wrap_mode {
+                                WrapMode::Clip => continue,
+                                WrapMode::Wrap => {
+                                    x %= display_width;
+                                    y %= display_height;
+                                }
+                            }
+                        }
+
+                        // a collision happens if this coord is already set
+                        let old = self.memory.back_vram[plane][y][x];
+                        collision |= old;
+
+                        // write vram
+                        self.set_pixel(plane, y, x, !old);
+                    }
+                }
+            }
+
+            // the sprite for the next plane follows this one
+            sprite_start += height * row_bytes;
+        }
+
+        // set the collision flag
+        self.registers.v[0xF] = collision as u8;
+        self.vram_dirty = true;
+
+        Ok(())
+    }
+
+    /// Scroll the selected display planes by the given number of pixels
+    /// Pixels scrolled in from off the display are blank.
+    fn scroll_display(&mut self, dx: isize, dy: isize) {
+        let (width, height) = self.display_size();
+        self.vram_dirty = true;
+
+        for plane in self.selected_planes() {
+            let old: Vram = self.memory.back_vram[plane];
+
+            for y in 0..height {
+                for x in 0..width {
+                    // get the pixel that moves into this one
+                    let src_x = x as isize - dx;
+                    let src_y = y as isize - dy;
+
+                    let on = (0..width as isize).contains(&src_x)
+                        && (0..height as isize).contains(&src_y)
+                        && old[src_y as usize][src_x as usize];
+                    self.set_pixel(plane, y, x, on);
+                }
+            }
+        }
+    }
+
+    /// Clear a display plane
+    fn clear_plane(&mut self, plane: usize) {
+        // without history, there is no need to go through each pixel
+        if self.history.is_none() {
+            self.memory.back_vram[plane] = [[false; HIRES_WIDTH]; HIRES_HEIGHT];
+            return;
+        }
+
+        for y in 0..HIRES_HEIGHT {
+            for x in 0..HIRES_WIDTH {
+                self.set_pixel(plane, y, x, false);
+            }
+        }
+    }
+
+    /// Turn a pixel of a display plane on or off, noting it in the history if it changes
+    fn set_pixel(&mut self, plane: usize, y: usize, x: usize, on: bool) {
+        let pixel = &mut self.memory.back_vram[plane][y][x];
+        if *pixel == on {
+            return;
+        }
+
+        if let Some(history) = &mut self.history {
+            history.record_pixel_flip(plane, y, x);
+        }
+        *pixel = on;
+    }
+
+    /// Get the indices of the display planes that are drawn to
+    fn selected_planes(&self) -> impl Iterator<Item = usize> {
+        let planes = self.planes;
+        (0..PLANE_COUNT).filter(move |plane| planes & (0x1 << plane) > 0)
+    }
+
+    /// Get the registers between X and Y in ascending order, and whether they are in memory in
+    /// reverse order, as they are if Y is less than X
+    fn register_range(regx: Register, regy: Register) -> (RangeInclusive<Register>, bool) {
+        if regx <= regy {
+            (regx..=regy, false)
+        } else {
+            (regy..=regx, true)
+        }
+    }
+
+    /// Point the PC at an address, for jumps and calls
+    /// The PC is moved on by 2 after every instruction, so it's set 2 before the address, wrapping
+    /// around so that jumping to 0x000 doesn't underflow.
+    fn jump_to(&mut self, addr: u16) {
+        self.registers.pc = addr.wrapping_sub(2);
+    }
+
+    /// Point the PC past the next instruction
+    /// In XO-CHIP mode, the long load instruction is 4 bytes long, so it is skipped entirely.
+    /// The next instruction is only read in XO-CHIP mode, so skipping past the end of memory
+    /// doesn't fail until the PC gets there.
+    fn skip_next_instruction(&mut self) {
+        let next = self.registers.pc as usize + 2;
+        let is_long_load = self.mode == EmulationMode::XoChip
+            && self.memory.ram.get(next..next + 2) == Some(&[0xF0, 0x00]);
+
+        self.registers.pc += 2;
+        if is_long_load {
+            self.registers.pc += 2;
+        }
+    }
+
+    /// Get the opcode at the PC
+    fn get_current_opcode(&self) -> Result<u16, EmulatorError> {
+        let pc = self.registers.pc as usize;
+        Ok(((self.safe_read_byte(pc)? as u16) << 8) | (self.safe_read_byte(pc + 1)? as u16))
+    }
+
+    /// Identify the instruction at the PC
+    fn get_current_instruction(&self) -> Result<Instruction, EmulatorError> {
+        match Instruction::decode(self.get_current_opcode()?) {
+            LoadLongAddress(_) => {
+                // XO-CHIP long load, with the address in the next two bytes
+                let pc = self.registers.pc as usize;
+                let high = self.safe_read_byte(pc + 2)? as usize;
+                let low = self.safe_read_byte(pc + 3)? as usize;
+                Ok(LoadLongAddress((high << 8) | low))
+            }
+            instruction => Ok(instruction),
+        }
+    }
+
+    /// Read a byte from memory, or fail if the address is past the end of memory
+    fn safe_read_byte(&self, addr: usize) -> Result<u8, EmulatorError> {
+        match self.memory.ram.get(addr) {
+            Some(byte) => Ok(*byte),
+            None => Err(EmulatorError::OutOfBoundsMemoryAccess {
+                addr,
+                pc: self.registers.pc,
+            }),
+        }
+    }
+
+    /// Read a byte from memory for an instruction, noting it if the address is watched
+    fn read_byte(&mut self, addr: usize) -> Result<u8, EmulatorError> {
+        let val = self.safe_read_byte(addr)?;
+        self.check_watchpoint(addr, val, val, WatchMode::Read);
+        Ok(val)
+    }
+
+    /// Write a byte to memory for an instruction, noting it if the address is watched
+    /// Writes to the write protected font region are logged and skipped, rather than halting.
+    fn write_byte(&mut self, addr: usize, val: u8) -> Result<(), EmulatorError> {
+        let old = self.memory.ram.get(addr).copied().unwrap_or_default();
+        match self.safe_write_byte(addr, val) {
+            Ok(()) => {
+                self.check_watchpoint(addr, old, val, WatchMode::Write);
+                Ok(())
+            }
+            Err(EmulatorError::WriteProtectedRegion { addr }) => {
+                warn!(
+                    "Blocked a write to the font region at {:#05x} (PC={:#05x})",
+                    addr, self.registers.pc
+                );
+                Ok(())
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Write a byte to memory
+    /// Fails if the address is past the end of memory, or below program memory while the font
+    /// region is write protected.
+    fn safe_write_byte(&mut self, addr: usize, val: u8) -> Result<(), EmulatorError> {
+        if self.write_protect_font && addr < PROGMEM_START as usize {
+            return Err(EmulatorError::WriteProtectedRegion { addr });
+        }
+
+        match self.memory.ram.get_mut(addr) {
+            Some(byte) => {
+                if let Some(history) = &mut self.history {
+                    history.record_ram_write(addr, *byte);
+                }
+                *byte = val;
+                Ok(())
+            }
+            None => Err(EmulatorError::OutOfBoundsMemoryAccess {
+                addr,
+                pc: self.registers.pc,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chip8::{
+        Chip8, Chip8Builder, EmulationMode, Quirks, QuirksPreset, TimerDecrement, WatchMode,
+        WrapMode, DEFAULT_HISTORY_DEPTH,
+    };
+    use crate::error::{Chip8Error, ParseQuirksPresetError};
+    use crate::input::{Input, MockInputSequence};
+    use alloc::format;
+    use alloc::string::String;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    /// Make a machine with a ROM loaded, in an instruction set
+    fn load(rom: &[u8], mode: EmulationMode) -> Chip8 {
+        Chip8Builder::default()
+            .mode(mode)
+            .rom_bytes(rom)
+            .build()
+            .expect("the test ROM should load")
+    }
+
+    /// Run a number of instructions, failing the test if any of them fail
+    fn run(chip8: &mut Chip8, cycles: u32) {
+        for _ in 0..cycles {
+            chip8.do_next_instruction().expect("the instruction should run");
+        }
+    }
+
+    /// Get a row of the display as it is shown
+    fn row(chip8: &Chip8, y: usize) -> Vec<bool> {
+        (0..64).map(|x| chip8.peek_vram(x, y) == Some(true)).collect()
+    }
+
+    #[test]
+    fn scroll_down_moves_rows_down_and_blanks_the_top() {
+        // I = 0x208, draw a one row sprite at (0, 0), scroll down 2, then loop forever
+        let rom = [0xA2, 0x08, 0xD0, 0x01, 0x00, 0xC2, 0x12, 0x06, 0xA5];
+        for mode in [EmulationMode::SuperChip, EmulationMode::XoChip] {
+            let mut chip8 = load(&rom, mode);
+            run(&mut chip8, 2);
+            chip8.do_frame();
+            let top = row(&chip8, 0);
+            assert_eq!(top.iter().filter(|&&pixel| pixel).count(), 4, "{:?}", mode);
+
+            run(&mut chip8, 1);
+            chip8.do_frame();
+            assert_eq!(row(&chip8, 0), [false; 64], "{:?}", mode);
+            assert_eq!(row(&chip8, 1), [false; 64], "{:?}", mode);
+            assert_eq!(row(&chip8, 2), top, "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn logic_operations_reset_vf_even_when_it_is_vx() {
+        // VF = the old value, V1 = 0x0F, then OR, AND, or XOR VF with V1
+        for op in [0x1, 0x2, 0x3] {
+            for old in [0x00, 0x01, 0xF0, 0xFF] {
+                let rom = [0x6F, old, 0x61, 0x0F, 0x8F, 0x10 | op];
+                let mut chip8 = load(&rom, EmulationMode::Chip8);
+                run(&mut chip8, 3);
+                let vf = chip8.snapshot_registers().v[0xF];
+                assert_eq!(vf, 0x00, "8F1{:X} with VF = {:#04x}", op, old);
+            }
+        }
+    }
+
+    #[test]
+    fn logic_operations_reset_vf_after_setting_vx() {
+        // VF = 0xFF, V1 = 0x0F, V2 = 0x3C, then OR, AND, or XOR V2 with V1
+        for (op, result) in [(0x1, 0x3F), (0x2, 0x0C), (0x3, 0x33)] {
+            let rom = [0x6F, 0xFF, 0x61, 0x0F, 0x62, 0x3C, 0x82, 0x10 | op];
+            let mut chip8 = load(&rom, EmulationMode::Chip8);
+            run(&mut chip8, 4);
+            let registers = chip8.snapshot_registers();
+            assert_eq!(registers.v[0x2], result, "821{:X}", op);
+            assert_eq!(registers.v[0xF], 0x00, "821{:X}", op);
+        }
+    }
+
+    #[test]
+    fn parse_quirks_presets() {
+        let names = [
+            ("chip8", QuirksPreset::Chip8Original),
+            ("chip-8", QuirksPreset::Chip8Original),
+            ("chip48", QuirksPreset::Chip48),
+            ("chip-48", QuirksPreset::Chip48),
+            ("schip10", QuirksPreset::SuperChip10),
+            ("schip11", QuirksPreset::SuperChip11),
+            ("schip", QuirksPreset::SuperChip11),
+            ("xochip", QuirksPreset::XoChip),
+            ("xo-chip", QuirksPreset::XoChip),
+            ("XOCHIP", QuirksPreset::XoChip),
+        ];
+        for (name, preset) in names {
+            assert_eq!(name.parse(), Ok(preset), "{}", name);
+        }
+    }
+
+    #[test]
+    fn parse_unknown_quirks_preset() {
+        assert_eq!(
+            "schip12".parse::<QuirksPreset>(),
+            Err(ParseQuirksPresetError("schip12".into()))
+        );
+    }
+
+    #[test]
+    fn quirks_preset_bundles() {
+        // as listed for each interpreter by the CHIP-8 Research Facility
+        let cosmac_vip = Quirks {
+            vblank: true,
+            ..Quirks::default()
+        };
+        let hp48 = Quirks {
+            shift: true,
+            load_store_by_x: true,
+            jump0: true,
+            ..Quirks::default()
+        };
+        let schip11 = Quirks {
+            shift: true,
+            load_store: true,
+            jump0: true,
+            ..Quirks::default()
+        };
+        let octo = Quirks::default();
+
+        let bundles = [
+            ("chip8", cosmac_vip),
+            ("chip48", hp48),
+            ("schip10", hp48),
+            ("schip11", schip11),
+            ("schip", schip11),
+            ("xochip", octo),
+        ];
+        for (name, quirks) in bundles {
+            let preset: QuirksPreset = name.parse().unwrap();
+            assert_eq!(preset.quirks(), quirks, "{}", name);
+        }
+    }
+
+    #[test]
+    fn superchip_presets_differ_in_where_i_ends_up() {
+        // I = 0x300, store V0 to V3
+        let rom = [0xA3, 0x00, 0xF3, 0x55];
+        for (preset, i) in [
+            (QuirksPreset::Chip8Original, 0x304),
+            (QuirksPreset::Chip48, 0x303),
+            (QuirksPreset::SuperChip10, 0x303),
+            (QuirksPreset::SuperChip11, 0x300),
+        ] {
+            let mut chip8 = Chip8Builder::default()
+                .quirks_preset(preset)
+                .rom_bytes(&rom)
+                .build()
+                .expect("the test ROM should load");
+            run(&mut chip8, 2);
+            assert_eq!(chip8.snapshot_registers().i, i, "{}", preset_name(preset));
+        }
+    }
+
+    // every preset, in the order they are listed in the docs
+    const PRESETS: [QuirksPreset; 5] = [
+        QuirksPreset::Chip8Original,
+        QuirksPreset::Chip48,
+        QuirksPreset::SuperChip10,
+        QuirksPreset::SuperChip11,
+        QuirksPreset::XoChip,
+    ];
+
+    /// Get the name of a preset as it is written in the docs
+    /// The match has no catch-all, so a new preset can't be added without a name here, which
+    /// should be a reminder to add it to `PRESETS` as well.
+    fn preset_name(preset: QuirksPreset) -> &'static str {
+        match preset {
+            QuirksPreset::Chip8Original => "Chip8Original",
+            QuirksPreset::Chip48 => "Chip48",
+            QuirksPreset::SuperChip10 => "SuperChip10",
+            QuirksPreset::SuperChip11 => "SuperChip11",
+            QuirksPreset::XoChip => "XoChip",
+        }
+    }
+
+    #[test]
+    fn quirks_preset_docs_table() {
+        let on_off = |on: bool| if on { "on" } else { "off" };
+
+        // build the table from the presets, as it should appear in the docs
+        let mut table = String::from(
+            "/// | Preset          | shift | load_store | load_store_by_x | jump0 | vblank |\n\
+             /// |-----------------|-------|------------|-----------------|-------|--------|\n",
+        );
+        for preset in PRESETS {
+            let quirks = preset.quirks();
+            table += &format!(
+                "/// | {:<15} | {:<5} | {:<10} | {:<15} | {:<5} | {:<6} |\n",
+                format!("`{}`", preset_name(preset)),
+                on_off(quirks.shift),
+                on_off(quirks.load_store),
+                on_off(quirks.load_store_by_x),
+                on_off(quirks.jump0),
+                on_off(quirks.vblank),
+            );
+        }
+
+        assert!(
+            include_str!("chip8.rs").contains(&table),
+            "The table in the QuirksPreset docs should be:\n{}",
+            table
+        );
+    }
+
+    #[test]
+    fn count_each_cycle_run() {
+        // jump back to the start forever
+        let mut chip8 = Chip8Builder::default()
+            .rom_bytes(&[0x12, 0x00])
+            .build()
+            .expect("the test ROM should load");
+        assert_eq!(chip8.total_cycles(), 0);
+
+        for _ in 0..50 {
+            chip8.do_next_instruction().expect("the jump should run");
+        }
+        assert_eq!(chip8.total_cycles(), 50);
+        assert_eq!(chip8.snapshot_registers().cycles, 50);
+    }
+
+    /// Run frames of 10 instructions until the delay timer runs out, getting how many it took
+    fn frames_until_delay_timer_expires(mut chip8: Chip8) -> u32 {
+        for frame in 1..=60 {
+            for _ in 0..10 {
+                chip8.do_next_instruction().expect("the instruction should run");
+            }
+            if chip8.do_frame().delay_timer_expired {
+                return frame;
+            }
+        }
+        panic!("the delay timer should run out within a second");
+    }
+
+    #[test]
+    fn timers_count_down_faster_on_each_instruction() {
+        // V0 = 10, DT = V0, then loop forever
+        let rom = [0x60, 0x0A, 0xF0, 0x15, 0x12, 0x04];
+        let build = |timer_decrement| {
+            Chip8Builder::default()
+                .rom_bytes(&rom)
+                .build()
+                .expect("the test ROM should load")
+                .with_timer_decrement(timer_decrement)
+        };
+
+        // the timer counts down once a frame, or once an instruction
+        let on_frame = frames_until_delay_timer_expires(build(TimerDecrement::OnFrame));
+        let on_instruction =
+            frames_until_delay_timer_expires(build(TimerDecrement::OnInstruction(1)));
+        assert_eq!(on_frame, 10);
+        assert_eq!(on_instruction, 2);
+    }
+
+    #[test]
+    fn same_seed_gives_the_same_random_numbers() {
+        // set V0 to VF to random numbers, then loop forever
+        let mut rom: Vec<u8> = (0x0..=0xF).flat_map(|x| [0xC0 | x, 0xFF]).collect();
+        rom.extend([0x12, 0x20]);
+
+        let run = |seed| {
+            let mut chip8 = Chip8Builder::default()
+                .rom_bytes(&rom)
+                .build()
+                .expect("the test ROM should load")
+                .with_seed(seed);
+            for _ in 0..16 {
+                chip8.do_next_instruction().expect("the instruction should run");
+            }
+            chip8.snapshot_registers().v
+        };
+
+        assert_eq!(run(1234), run(1234));
+        assert_ne!(run(1234), run(5678));
+    }
+
+    #[test]
+    fn reset_restarts_the_program() {
+        // count up V0 to V3, calling a subroutine that adds to I, and loop forever
+        let rom = [
+            0x70, 0x01, 0x71, 0x02, 0x72, 0x03, 0x73, 0x04, 0x22, 0x0C, 0x12, 0x00, 0xF0, 0x1E,
+            0x00, 0xEE,
+        ];
+        let mut chip8 = Chip8Builder::default()
+            .rom_bytes(&rom)
+            .build()
+            .expect("the test ROM should load");
+        for _ in 0..100 {
+            chip8.do_next_instruction().expect("the instruction should run");
+        }
+        assert_ne!(chip8.snapshot_registers().v, [0; 16]);
+
+        chip8.reset();
+        let registers = chip8.snapshot_registers();
+        assert_eq!(registers.pc, 0x200);
+        assert_eq!(registers.v, [0; 16]);
+        assert_eq!(registers.i, 0);
+        assert_eq!(chip8.stack_depth(), 0);
+        assert_eq!(chip8.total_cycles(), 0);
+
+        // the ROM is still loaded
+        assert_eq!(chip8.peek_ram(0x200), Some(0x70));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn load_rom_from_a_reader() {
+        let rom: &[u8] = &[0x00, 0xE0, 0x12, 0x02];
+        let chip8 = Chip8::new()
+            .load_rom_from_reader(rom)
+            .expect("the ROM should load from a slice");
+
+        let loaded: Vec<Option<u8>> = (0x200..0x204).map(|addr| chip8.peek_ram(addr)).collect();
+        assert_eq!(loaded, [Some(0x00), Some(0xE0), Some(0x12), Some(0x02)]);
+        assert_eq!(chip8.peek_ram(0x204), Some(0x00));
+        assert_eq!(chip8.snapshot_registers().pc, 0x200);
+    }
+
+    #[cfg(feature = "debug")]
+    #[test]
+    fn step_after_an_injected_opcode_runs_the_program() {
+        // V0 = 0x05, then V0 = 0x06
+        let mut chip8 = load(&[0x60, 0x05, 0x60, 0x06], EmulationMode::Chip8);
+        // V1 = 0x07
+        assert_eq!(chip8.inject_opcode(0x6107), Ok(0x6107));
+        assert_eq!(chip8.snapshot_registers().pc, 0x200);
+        assert_eq!(chip8.peek_ram(0x200), Some(0x60));
+        assert_eq!(chip8.peek_ram(0x201), Some(0x05));
+
+        assert_eq!(chip8.do_next_instruction(), Ok(0x6005));
+        let registers = chip8.snapshot_registers();
+        assert_eq!(registers.v[0x0], 0x05);
+        assert_eq!(registers.v[0x1], 0x07);
+        assert_eq!(registers.pc, 0x202);
+    }
+
+
+    #[test]
+    fn sprites_past_the_right_edge_are_clipped_or_wrapped() {
+        // draw a row of 8 pixels at (60, 0), then loop forever
+        let rom = [0xA2, 0x08, 0x60, 0x3C, 0xD0, 0x11, 0x12, 0x06, 0xFF];
+        for (wrap_mode, wrapped) in [(WrapMode::Clip, false), (WrapMode::Wrap, true)] {
+            let mut chip8 = Chip8Builder::default()
+                .wrap_mode(wrap_mode)
+                .rom_bytes(&rom)
+                .build()
+                .expect("the test ROM should load");
+            run(&mut chip8, 3);
+            chip8.do_frame();
+
+            let top = row(&chip8, 0);
+            assert!(top[60..].iter().all(|&pixel| pixel), "{:?}", wrap_mode);
+            assert!(top[..4].iter().all(|&pixel| pixel == wrapped), "{:?}", wrap_mode);
+            assert!(top[4..60].iter().all(|&pixel| !pixel), "{:?}", wrap_mode);
+        }
+    }
+
+
+    #[test]
+    fn entry_point_selects_the_second_of_two_programs() {
+        // V0 = 1 and loop at 0x200, then V0 = 2 and loop at 0x300
+        let mut rom = vec![0x00; 0x104];
+        rom[..4].copy_from_slice(&[0x60, 0x01, 0x12, 0x02]);
+        rom[0x100..].copy_from_slice(&[0x60, 0x02, 0x13, 0x02]);
+
+        let mut first = load(&rom, EmulationMode::Chip8);
+        run(&mut first, 2);
+        assert_eq!(first.snapshot_registers().v[0], 1);
+
+        let mut second = Chip8Builder::default()
+            .rom_bytes(&rom)
+            .entry_point(0x300)
+            .build()
+            .expect("0x300 should be a valid entry point");
+        run(&mut second, 2);
+        assert_eq!(second.snapshot_registers().v[0], 2);
+        assert_eq!(second.snapshot_registers().pc, 0x302);
+
+        // resetting starts the second program again
+        second.reset();
+        assert_eq!(second.snapshot_registers().pc, 0x300);
+
+        let load_with_entry = |entry| Chip8::new().load_rom_with_entry(&rom, entry);
+        assert!(load_with_entry(0x300).is_ok());
+        for entry in [0x301, 0x304, 0x1FE] {
+            assert!(matches!(
+                load_with_entry(entry),
+                Err(Chip8Error::InvalidEntryPoint(e)) if e == entry
+            ));
+        }
+    }
+
+
+    #[test]
+    fn clones_diverge_when_different_keys_are_pressed() {
+        // wait for a key and store it in V0, then loop forever
+        let mut original = load(&[0xF0, 0x0A, 0x12, 0x02], EmulationMode::Chip8);
+        run(&mut original, 1);
+        let mut clone = original.clone();
+        assert!(clone == original);
+
+        let press = |key| MockInputSequence::new().press(key).step().release(key).step();
+        press(0x1).run_on(&mut original).expect("the instructions should run");
+        press(0x2).run_on(&mut clone).expect("the instructions should run");
+        assert_eq!(original.snapshot_registers().v[0], 0x1);
+        assert_eq!(clone.snapshot_registers().v[0], 0x2);
+        assert!(clone != original);
+    }
+
+    #[test]
+    fn keys_off_the_keypad_are_rejected() {
+        let mut chip8 = load(&[0x12, 0x00], EmulationMode::Chip8);
+        chip8.change_input(Input::Pressed(0xF)).expect("0xF should be on the keypad");
+        assert!(matches!(
+            chip8.change_input(Input::Pressed(0x10)),
+            Err(Chip8Error::InvalidKey(0x10))
+        ));
+        assert!(matches!(
+            chip8.change_input(Input::Unpressed(0xFF)),
+            Err(Chip8Error::InvalidKey(0xFF))
+        ));
+        assert_eq!(chip8.pressed_keys(), 0x8000);
+    }
+
+    #[test]
+    fn patching_the_rom_changes_the_path_it_takes() {
+        // V0 = 0, skip setting V1 to 1 if V0 is 0, then loop forever
+        let rom = [0x60, 0x00, 0x30, 0x00, 0x61, 0x01, 0x12, 0x06];
+        let mut unpatched = load(&rom, EmulationMode::Chip8);
+        run(&mut unpatched, 3);
+        assert_eq!(unpatched.snapshot_registers().v[1], 0);
+
+        // skip if V0 isn't 0 instead, so V1 is set
+        let mut patched = load(&rom, EmulationMode::Chip8);
+        patched.inject_ram(0x202, &[0x40]).expect("the patch should fit");
+        run(&mut patched, 3);
+        assert_eq!(patched.snapshot_registers().v[1], 1);
+    }
+
+    #[test]
+    fn patches_are_watched_and_can_be_stepped_back() {
+        let mut chip8 = load(&[0x12, 0x00], EmulationMode::Chip8);
+        chip8.enable_history(DEFAULT_HISTORY_DEPTH);
+        chip8
+            .add_watchpoint(0x301, WatchMode::Write)
+            .expect("there should be room for a watchpoint");
+
+        chip8.inject_ram(0x300, &[0xAB, 0xCD]).expect("the patch should fit");
+        let hit = chip8.watchpoint_hit().expect("the patch should hit the watchpoint");
+        assert_eq!((hit.addr, hit.old_value, hit.new_value), (0x301, 0x00, 0xCD));
+
+        chip8.step_back().expect("the patch should be undone");
+        assert_eq!(chip8.peek_ram(0x300), Some(0x00));
+        assert_eq!(chip8.peek_ram(0x301), Some(0x00));
+    }
+}
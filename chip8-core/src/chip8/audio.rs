@@ -0,0 +1,176 @@
+use crate::chip8::{Chip8, EmulationMode, AUDIO_PATTERN_SIZE, DISPLAY_HZ};
+use alloc::boxed::Box;
+
+/// The pitch of the beep outside of XO-CHIP mode, in hz
+pub const BUZZER_FREQUENCY: f32 = 440.0;
+
+// the number of bits in the XO-CHIP audio pattern
+const PATTERN_BITS: f32 = (AUDIO_PATTERN_SIZE * 8) as f32;
+
+/// Somewhere to send the sound the emulator makes, such as an audio device
+/// Sinks must be Sync so that the emulator can still be shared between threads.
+pub trait AudioSink: Send + Sync + 'static {
+    /// Get the number of samples the sink plays each second
+    fn sample_rate(&self) -> u32;
+
+    /// Play a single sample, from -1.0 to 1.0
+    fn output_sample(&mut self, sample: f32);
+}
+
+/// An audio sink, and how far through the sound it is
+pub(super) struct AudioOutput {
+    sink: Box<dyn AudioSink>,
+
+    /// The fraction of a sample left over from the last frame
+    leftover: f32,
+
+    /// The position within the current period of the beep or pattern, from 0 to 1
+    phase: f32,
+}
+
+impl Chip8 {
+    /// Send the sound to a sink, a frame's worth of samples at a time
+    /// The sound timer and, in XO-CHIP mode, the audio pattern and pitch decide what is played.
+    pub fn set_audio_sink(&mut self, sink: Box<dyn AudioSink>) {
+        self.audio = Some(AudioOutput {
+            sink,
+            leftover: 0.0,
+            phase: 0.0,
+        });
+    }
+
+    /// Stop sending sound to the sink, and get it back
+    pub fn take_audio_sink(&mut self) -> Option<Box<dyn AudioSink>> {
+        self.audio.take().map(|audio| audio.sink)
+    }
+
+    /// Send a frame of samples to the audio sink, if there is one
+    pub(super) fn output_audio(&mut self) {
+        let playing = self.registers.st > 0;
+        let xochip = self.mode == EmulationMode::XoChip;
+
+        // the pattern is played a bit at a time, so a whole period is every bit in it
+        let frequency = if xochip {
+            self.get_audio_frequency() / PATTERN_BITS
+        } else {
+            BUZZER_FREQUENCY
+        };
+        let pattern = self.memory.audio_pattern;
+
+        let Some(audio) = &mut self.audio else {
+            return;
+        };
+
+        // keep any fraction of a sample, so the sink gets exactly its sample rate over time
+        let sample_rate = audio.sink.sample_rate() as f32;
+        let samples = audio.leftover + sample_rate / DISPLAY_HZ;
+        audio.leftover = samples.fract();
+
+        for _ in 0..samples as u32 {
+            let on = if xochip {
+                let bit = ((audio.phase * PATTERN_BITS) as usize).min(AUDIO_PATTERN_SIZE * 8 - 1);
+                pattern[bit / 8] & (0x80 >> (bit % 8)) != 0
+            } else {
+                audio.phase < 0.5
+            };
+
+            let sample = match (playing, on) {
+                (false, _) => 0.0,
+                (true, true) => 1.0,
+                (true, false) => -1.0,
+            };
+            audio.sink.output_sample(sample);
+
+            audio.phase = (audio.phase + frequency / sample_rate) % 1.0;
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::AudioSink;
+    use crate::chip8::{Chip8, Chip8Builder, EmulationMode};
+    use alloc::boxed::Box;
+    use alloc::vec::Vec;
+    use std::sync::{Arc, Mutex};
+
+    /// A sink that keeps every sample it is sent, to be checked once the emulator is done
+    struct RecordingAudioSink {
+        sample_rate: u32,
+        samples: Arc<Mutex<Vec<f32>>>,
+    }
+
+    impl AudioSink for RecordingAudioSink {
+        fn sample_rate(&self) -> u32 {
+            self.sample_rate
+        }
+
+        fn output_sample(&mut self, sample: f32) {
+            self.samples.lock().unwrap().push(sample);
+        }
+    }
+
+    /// Make a machine with a ROM loaded, sending its sound to a recording sink
+    fn record(rom: &[u8], mode: EmulationMode, sample_rate: u32) -> (Chip8, Arc<Mutex<Vec<f32>>>) {
+        let mut chip8 = Chip8Builder::default()
+            .mode(mode)
+            .rom_bytes(rom)
+            .build()
+            .expect("the test ROM should load");
+        let samples = Arc::new(Mutex::new(Vec::new()));
+        chip8.set_audio_sink(Box::new(RecordingAudioSink {
+            sample_rate,
+            samples: Arc::clone(&samples),
+        }));
+        (chip8, samples)
+    }
+
+    #[test]
+    fn silent_while_the_sound_timer_is_zero() {
+        let (mut chip8, samples) = record(&[0x12, 0x00], EmulationMode::Chip8, 6000);
+        chip8.do_frame();
+        assert_eq!(*samples.lock().unwrap(), [0.0; 100]);
+    }
+
+    #[test]
+    fn beep_while_the_sound_timer_is_set() {
+        // V0 = 60, ST = V0, then loop forever
+        let rom = [0x60, 0x3C, 0xF0, 0x18, 0x12, 0x04];
+        let (mut chip8, samples) = record(&rom, EmulationMode::Chip8, 6000);
+        chip8.do_next_instruction().expect("the load should run");
+        chip8.do_next_instruction().expect("the sound timer should be set");
+        chip8.do_frame();
+
+        // a 440hz square wave, high for the first half of each period
+        let samples = samples.lock().unwrap();
+        assert_eq!(samples.len(), 100);
+        assert_eq!(&samples[..7], [1.0; 7]);
+        assert_eq!(&samples[7..14], [-1.0; 7]);
+        assert!(samples.iter().all(|&sample| sample == 1.0 || sample == -1.0));
+    }
+
+    #[test]
+    fn send_the_sample_rate_over_several_frames() {
+        // at 90hz, a frame is one and a half samples
+        let (mut chip8, samples) = record(&[0x12, 0x00], EmulationMode::Chip8, 90);
+        let counts: Vec<usize> = (0..4)
+            .map(|_| {
+                chip8.do_frame();
+                samples.lock().unwrap().len()
+            })
+            .collect();
+        assert_eq!(counts, [1, 3, 4, 6]);
+    }
+
+    #[test]
+    fn audio_frequency_follows_the_pitch() {
+        // V0 = 112, pitch = V0
+        let rom = [0x60, 0x70, 0xF0, 0x3A];
+        let (mut chip8, _) = record(&rom, EmulationMode::XoChip, 6000);
+        assert_eq!(chip8.get_audio_frequency(), 4000.0);
+
+        chip8.do_next_instruction().expect("the load should run");
+        chip8.do_next_instruction().expect("the pitch should be set");
+        assert_eq!(chip8.get_audio_frequency(), 8000.0);
+    }
+}
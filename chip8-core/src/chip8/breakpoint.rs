@@ -0,0 +1,141 @@
+use crate::chip8::RegisterSnapshot;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+use core::str::FromStr;
+
+/// A condition that pauses the emulator when it is met
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Breakpoint {
+    /// The PC reaches an address
+    AtAddress(u16),
+
+    /// A register V0 to VF holds a value
+    WhenRegisterEquals {
+        /// The register to check
+        reg: u8,
+
+        /// The value that triggers the breakpoint
+        value: u8,
+    },
+
+    /// The PC is anywhere in a range of addresses
+    WhenPcInRange(Range<u16>),
+
+    /// A number of instructions have been run
+    AfterNCycles(u64),
+}
+
+impl Breakpoint {
+    /// Check if the breakpoint is triggered by the registers and the instructions run so far
+    pub fn is_hit(&self, snapshot: &RegisterSnapshot, cycles: u64) -> bool {
+        let pc = snapshot.pc;
+        match self {
+            Breakpoint::AtAddress(addr) => pc == *addr,
+            Breakpoint::WhenRegisterEquals { reg, value } => {
+                snapshot.v.get(*reg as usize) == Some(value)
+            }
+            Breakpoint::WhenPcInRange(range) => range.contains(&pc),
+            Breakpoint::AfterNCycles(n) => cycles == *n,
+        }
+    }
+}
+
+impl FromStr for Breakpoint {
+    type Err = String;
+
+    /// Parse a breakpoint, which is an address like "2A0", a register value like "V3=10", a range
+    /// of addresses like "200..240", or a number of instructions like "cycle=1000"
+    /// Addresses and values are in hex, and the number of instructions is in decimal.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex = |s: &str| {
+            u16::from_str_radix(s.trim().trim_start_matches("0x"), 16)
+                .map_err(|_| format!("'{}' is not a hex number", s))
+        };
+
+        let lower = s.to_lowercase();
+        if let Some(cycles) = lower.strip_prefix("cycle=") {
+            let cycles = cycles
+                .trim()
+                .parse()
+                .map_err(|_| format!("'{}' is not a number of instructions", cycles))?;
+            Ok(Breakpoint::AfterNCycles(cycles))
+        } else if let Some((reg, value)) = lower.split_once('=') {
+            let reg = reg
+                .trim()
+                .strip_prefix('v')
+                .and_then(|reg| u8::from_str_radix(reg, 16).ok())
+                .filter(|&reg| reg <= 0xF)
+                .ok_or_else(|| format!("'{}' is not a register from V0 to VF", reg))?;
+            let value = u8::try_from(hex(value)?)
+                .map_err(|_| format!("'{}' doesn't fit in a register", value))?;
+            Ok(Breakpoint::WhenRegisterEquals { reg, value })
+        } else if let Some((start, end)) = lower.split_once("..") {
+            Ok(Breakpoint::WhenPcInRange(hex(start)?..hex(end)?))
+        } else {
+            Ok(Breakpoint::AtAddress(hex(&lower)?))
+        }
+    }
+}
+
+impl fmt::Display for Breakpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Breakpoint::AtAddress(addr) => write!(f, "at {:#05x}", addr),
+            Breakpoint::WhenRegisterEquals { reg, value } => {
+                write!(f, "when V{:X} is {:#04x}", reg, value)
+            }
+            Breakpoint::WhenPcInRange(range) => {
+                write!(f, "in {:#05x}..{:#05x}", range.start, range.end)
+            }
+            Breakpoint::AfterNCycles(n) => write!(f, "after {} instructions", n),
+        }
+    }
+}
+
+/// The breakpoints set in a debugger
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BreakpointManager {
+    breakpoints: Vec<Breakpoint>,
+}
+
+impl BreakpointManager {
+    /// Add a breakpoint, unless the same one is already set
+    pub fn add(&mut self, breakpoint: Breakpoint) {
+        if !self.breakpoints.contains(&breakpoint) {
+            self.breakpoints.push(breakpoint);
+        }
+    }
+
+    /// Remove a breakpoint, if it is set
+    pub fn remove(&mut self, breakpoint: &Breakpoint) {
+        self.breakpoints.retain(|b| b != breakpoint);
+    }
+
+    /// Replace the breakpoints at addresses, leaving the conditional ones alone
+    pub fn set_addresses(&mut self, addrs: &[u16]) {
+        self.breakpoints.retain(|b| !matches!(b, Breakpoint::AtAddress(_)));
+        for &addr in addrs {
+            self.add(Breakpoint::AtAddress(addr));
+        }
+    }
+
+    /// Get the breakpoints, in the order they were added
+    pub fn breakpoints(&self) -> &[Breakpoint] {
+        &self.breakpoints
+    }
+
+    /// Get the first breakpoint that is triggered by the registers and the instructions run so
+    /// far, if any is
+    pub fn check_breakpoints(
+        &self,
+        snapshot: &RegisterSnapshot,
+        cycles: u64,
+    ) -> Option<&Breakpoint> {
+        self.breakpoints
+            .iter()
+            .find(|breakpoint| breakpoint.is_hit(snapshot, cycles))
+    }
+}
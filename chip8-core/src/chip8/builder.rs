@@ -0,0 +1,187 @@
+use crate::chip8::{
+    Chip8, EmulationMode, FontSet, QuirksPreset, WrapMode, DEFAULT_CYCLES_PER_FRAME,
+};
+use crate::error::Chip8Error;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::path::{Path, PathBuf};
+
+/// Where to load a ROM or font from
+#[derive(Debug, Clone)]
+enum Source {
+    Bytes(Vec<u8>),
+    #[cfg(feature = "std")]
+    Path(PathBuf),
+}
+
+/// Configures and makes a Chip8
+/// Anything not set is left at its default, and the default font is used if no font is given.
+#[derive(Debug, Clone, Default)]
+pub struct Chip8Builder {
+    mode: EmulationMode,
+    rom: Option<Source>,
+    font: Option<Source>,
+    wrap_mode: WrapMode,
+    cycles_per_frame: Option<u32>,
+    shift_quirk: bool,
+    load_store_quirk: bool,
+    load_store_by_x_quirk: bool,
+    jump0_quirk: bool,
+    vblank_quirk: bool,
+    add_index_overflow_quirk: bool,
+    write_protect_font: Option<bool>,
+    seed: Option<u64>,
+    entry_point: Option<u16>,
+}
+
+impl Chip8Builder {
+    /// Set the instruction set to emulate
+    pub fn mode(mut self, mode: EmulationMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Load the ROM from a slice
+    pub fn rom_bytes(mut self, bytes: &[u8]) -> Self {
+        self.rom = Some(Source::Bytes(bytes.to_vec()));
+        self
+    }
+
+    /// Load the ROM from a file
+    #[cfg(feature = "std")]
+    pub fn rom_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.rom = Some(Source::Path(path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Load the font from a slice
+    pub fn font_bytes(mut self, bytes: &[u8]) -> Self {
+        self.font = Some(Source::Bytes(bytes.to_vec()));
+        self
+    }
+
+    /// Load the font from a file
+    #[cfg(feature = "std")]
+    pub fn font_path(mut self, path: impl AsRef<Path>) -> Self {
+        self.font = Some(Source::Path(path.as_ref().to_path_buf()));
+        self
+    }
+
+    /// Use one of the font sets, which is the original font unless another is given
+    pub fn font_set(self, font: FontSet) -> Self {
+        self.font_bytes(font.bytes())
+    }
+
+    /// Set what happens to sprites drawn past the edges of the display
+    pub fn wrap_mode(mut self, wrap_mode: WrapMode) -> Self {
+        self.wrap_mode = wrap_mode;
+        self
+    }
+
+    /// Set the number of instructions that should be run each frame
+    pub fn cycles_per_frame(mut self, cycles_per_frame: u32) -> Self {
+        self.cycles_per_frame = Some(cycles_per_frame);
+        self
+    }
+
+    /// Set whether 8XY6 and 8XYE shift VX in place, ignoring VY
+    pub fn shift_quirk(mut self, enabled: bool) -> Self {
+        self.shift_quirk = enabled;
+        self
+    }
+
+    /// Set whether FX55 and FX65 leave I unchanged
+    pub fn load_store_quirk(mut self, enabled: bool) -> Self {
+        self.load_store_quirk = enabled;
+        self
+    }
+
+    /// Set whether FX55 and FX65 add X to I instead of X + 1
+    pub fn load_store_by_x_quirk(mut self, enabled: bool) -> Self {
+        self.load_store_by_x_quirk = enabled;
+        self
+    }
+
+    /// Set whether BNNN jumps to NNN plus VX instead of V0
+    pub fn jump0_quirk(mut self, enabled: bool) -> Self {
+        self.jump0_quirk = enabled;
+        self
+    }
+
+    /// Set whether DXYN waits for the next frame before drawing
+    pub fn vblank_quirk(mut self, enabled: bool) -> Self {
+        self.vblank_quirk = enabled;
+        self
+    }
+
+    /// Set whether FX1E sets VF when I goes past 0xFFF
+    pub fn add_index_overflow_quirk(mut self, enabled: bool) -> Self {
+        self.add_index_overflow_quirk = enabled;
+        self
+    }
+
+    /// Set the quirks to those of a well-known interpreter
+    pub fn quirks_preset(self, preset: QuirksPreset) -> Self {
+        let quirks = preset.quirks();
+        self.shift_quirk(quirks.shift)
+            .load_store_quirk(quirks.load_store)
+            .load_store_by_x_quirk(quirks.load_store_by_x)
+            .jump0_quirk(quirks.jump0)
+            .vblank_quirk(quirks.vblank)
+    }
+
+    /// Set whether writes below program memory are blocked, which they are by default
+    pub fn write_protect_font(mut self, enabled: bool) -> Self {
+        self.write_protect_font = Some(enabled);
+        self
+    }
+
+    /// Seed the random numbers used by CXNN
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Start the ROM from an address other than the start of program memory
+    pub fn entry_point(mut self, entry: u16) -> Self {
+        self.entry_point = Some(entry);
+        self
+    }
+
+    /// Make the Chip8, loading the font and ROM
+    pub fn build(self) -> Result<Chip8, Chip8Error> {
+        let mut chip8 = Chip8::new()
+            .with_mode(self.mode)
+            .with_wrap_mode(self.wrap_mode)
+            .with_cycles_per_frame(self.cycles_per_frame.unwrap_or(DEFAULT_CYCLES_PER_FRAME))
+            .with_shift_quirk(self.shift_quirk)
+            .with_load_store_quirk(self.load_store_quirk)
+            .with_load_store_by_x_quirk(self.load_store_by_x_quirk)
+            .with_jump0_quirk(self.jump0_quirk)
+            .with_vblank_quirk(self.vblank_quirk)
+            .with_add_index_overflow_quirk(self.add_index_overflow_quirk)
+            .with_write_protect_font(self.write_protect_font.unwrap_or(true));
+        if let Some(seed) = self.seed {
+            chip8 = chip8.with_seed(seed);
+        }
+
+        chip8 = match self.font {
+            None => chip8.load_font(FontSet::Original)?,
+            Some(Source::Bytes(bytes)) => chip8.load_font_from_bytes(&bytes)?,
+            #[cfg(feature = "std")]
+            Some(Source::Path(path)) => chip8.load_font_file(path)?,
+        };
+
+        chip8 = match self.rom {
+            None => chip8,
+            Some(Source::Bytes(bytes)) => chip8.load_rom_from_bytes(&bytes)?,
+            #[cfg(feature = "std")]
+            Some(Source::Path(path)) => chip8.load_rom(path)?,
+        };
+        if let Some(entry) = self.entry_point {
+            chip8 = chip8.with_entry_point(entry)?;
+        }
+
+        Ok(chip8)
+    }
+}
@@ -0,0 +1,107 @@
+use crate::chip8::{Chip8, MEM_SIZE, PROGMEM_START};
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+// the number of addresses marked in each word of the coverage bitmap
+const BITS_PER_WORD: usize = u64::BITS as usize;
+
+/// Marks which addresses an instruction has been executed from
+/// Used to find the parts of a ROM that a run never reached.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstructionCoverage {
+    /// One bit for each address in memory, set once an instruction is fetched from it
+    covered: [u64; MEM_SIZE / BITS_PER_WORD],
+}
+
+impl Default for InstructionCoverage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl InstructionCoverage {
+    /// Make a tracker with no addresses covered
+    pub fn new() -> Self {
+        Self {
+            covered: [0; MEM_SIZE / BITS_PER_WORD],
+        }
+    }
+
+    /// Mark an instruction as executed from an address
+    pub fn record(&mut self, addr: u16) {
+        let addr = addr as usize % MEM_SIZE;
+        self.covered[addr / BITS_PER_WORD] |= 1 << (addr % BITS_PER_WORD);
+    }
+
+    /// Check if an instruction has been executed from an address
+    pub fn is_covered(&self, addr: u16) -> bool {
+        let addr = addr as usize;
+        addr < MEM_SIZE && self.covered[addr / BITS_PER_WORD] & (1 << (addr % BITS_PER_WORD)) != 0
+    }
+
+    /// Get the number of addresses that an instruction has been executed from
+    pub fn covered_count(&self) -> usize {
+        self.covered.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    /// Get the share of a ROM's instructions that have been executed, as a percentage
+    /// The ROM is taken as two byte instructions from the start of the program, so code that
+    /// is only reached at odd addresses isn't counted.
+    pub fn coverage_percent(&self, rom_size: usize) -> f32 {
+        let instructions = Self::rom_instructions(rom_size);
+        let total = instructions.len();
+        if total == 0 {
+            return 0.0;
+        }
+
+        let covered = instructions.filter(|&addr| self.is_covered(addr)).count();
+        covered as f32 * 100.0 / total as f32
+    }
+
+    /// Write the coverage of a ROM as an LCOV tracefile, for tools like genhtml
+    /// Each two byte instruction of the ROM is a line, numbered from 1 at the start of the
+    /// program, and `source` is the name given for the file they come from.
+    #[cfg(feature = "std")]
+    pub fn save_lcov(&self, w: &mut impl Write, source: &str, rom_size: usize) -> io::Result<()> {
+        writeln!(w, "TN:")?;
+        writeln!(w, "SF:{}", source)?;
+
+        let mut hit = 0;
+        for (line, addr) in Self::rom_instructions(rom_size).enumerate() {
+            let covered = self.is_covered(addr);
+            hit += covered as usize;
+            writeln!(w, "DA:{},{}", line + 1, covered as u8)?;
+        }
+
+        writeln!(w, "LF:{}", Self::rom_instructions(rom_size).len())?;
+        writeln!(w, "LH:{}", hit)?;
+        writeln!(w, "end_of_record")
+    }
+
+    /// Get the address of each instruction in a ROM of the given size
+    fn rom_instructions(rom_size: usize) -> impl ExactSizeIterator<Item = u16> {
+        let end = (PROGMEM_START as usize + rom_size).min(MEM_SIZE);
+        (PROGMEM_START as usize..end).step_by(2).map(|addr| addr as u16)
+    }
+}
+
+impl Chip8 {
+    /// Start marking which addresses instructions are executed from
+    /// Anything already marked is cleared.
+    pub fn enable_coverage_tracking(&mut self) {
+        self.coverage = Some(InstructionCoverage::new());
+    }
+
+    /// Get the addresses executed from, if tracking was turned on with
+    /// `enable_coverage_tracking`
+    pub fn coverage(&self) -> Option<&InstructionCoverage> {
+        self.coverage.as_ref()
+    }
+
+    /// Mark the instruction at the PC as executed, if coverage tracking is on
+    pub(super) fn record_coverage(&mut self) {
+        if let Some(coverage) = &mut self.coverage {
+            coverage.record(self.registers.pc);
+        }
+    }
+}
@@ -0,0 +1,1046 @@
+use crate::chip8::{
+    Chip8, EmulationMode, AUDIO_PATTERN_SIZE, LARGE_FONTMEM_START, PLANE_COUNT, STACK_SIZE,
+};
+use crate::error::EmulatorError;
+use crate::instructions::{Immediate, Register};
+use rand::Rng;
+
+/// Runs one instruction, given its opcode
+/// The PC still points at the instruction, and is moved on to the next one afterwards.
+type Handler = fn(&mut Chip8, u16) -> Result<(), EmulatorError>;
+
+// handlers for each instruction group, by the high nibble of the opcode
+const OPCODE_TABLE: [Handler; 16] = [
+    Chip8::handle_system,
+    Chip8::handle_jump,
+    Chip8::handle_call,
+    Chip8::handle_skip_equal_imm,
+    Chip8::handle_skip_not_equal_imm,
+    Chip8::handle_register_range,
+    Chip8::handle_load_imm,
+    Chip8::handle_add_imm,
+    Chip8::handle_arithmetic,
+    Chip8::handle_skip_not_equal_reg,
+    Chip8::handle_load_address,
+    Chip8::handle_jump_with_offset,
+    Chip8::handle_rand,
+    Chip8::handle_draw,
+    Chip8::handle_keys,
+    Chip8::handle_misc,
+];
+
+// handlers for the 8XYN register arithmetic instructions, by the low nibble
+const ARITHMETIC_TABLE: [Handler; 16] = [
+    Chip8::handle_load_reg,
+    Chip8::handle_or_reg,
+    Chip8::handle_and_reg,
+    Chip8::handle_xor_reg,
+    Chip8::handle_add_reg,
+    Chip8::handle_sub_reg,
+    Chip8::handle_shift_right_reg,
+    Chip8::handle_subn_reg,
+    Chip8::handle_unknown,
+    Chip8::handle_unknown,
+    Chip8::handle_unknown,
+    Chip8::handle_unknown,
+    Chip8::handle_unknown,
+    Chip8::handle_unknown,
+    Chip8::handle_shift_left_reg,
+    Chip8::handle_unknown,
+];
+
+// handlers for the EXNN key instructions, by the low byte
+const KEY_TABLE: [Handler; 256] = {
+    let mut table: [Handler; 256] = [Chip8::handle_unknown; 256];
+    table[0x9E] = Chip8::handle_skip_if_key_pressed;
+    table[0xA1] = Chip8::handle_skip_if_key_not_pressed;
+    table
+};
+
+// handlers for the FXNN timer, memory and other instructions, by the low byte
+const MISC_TABLE: [Handler; 256] = {
+    let mut table: [Handler; 256] = [Chip8::handle_unknown; 256];
+    table[0x00] = Chip8::handle_load_long_address;
+    table[0x01] = Chip8::handle_set_planes;
+    table[0x02] = Chip8::handle_load_audio;
+    table[0x07] = Chip8::handle_read_delay_timer;
+    table[0x0A] = Chip8::handle_store_keypress;
+    table[0x15] = Chip8::handle_write_delay_timer;
+    table[0x18] = Chip8::handle_write_sound_timer;
+    table[0x1E] = Chip8::handle_add_index;
+    table[0x29] = Chip8::handle_set_sprite_loc;
+    table[0x30] = Chip8::handle_set_large_sprite_loc;
+    table[0x33] = Chip8::handle_store_bcd;
+    table[0x3A] = Chip8::handle_set_pitch;
+    table[0x55] = Chip8::handle_store_registers;
+    table[0x65] = Chip8::handle_read_registers;
+    table[0x75] = Chip8::handle_store_flags;
+    table[0x85] = Chip8::handle_read_flags;
+    table
+};
+
+/// Get the X register of an opcode
+fn x(opcode: u16) -> Register {
+    ((opcode & 0x0F00) >> 8) as Register
+}
+
+/// Get the Y register of an opcode
+fn y(opcode: u16) -> Register {
+    ((opcode & 0x00F0) >> 4) as Register
+}
+
+/// Get the low nibble of an opcode
+fn n(opcode: u16) -> Immediate {
+    (opcode & 0x000F) as Immediate
+}
+
+/// Get the low byte of an opcode
+fn nn(opcode: u16) -> Immediate {
+    (opcode & 0x00FF) as Immediate
+}
+
+/// Get the address in an opcode
+fn nnn(opcode: u16) -> u16 {
+    opcode & 0x0FFF
+}
+
+impl Chip8 {
+    /// Run the instruction for an opcode, leaving the PC to be moved on afterwards
+    pub(super) fn dispatch(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        OPCODE_TABLE[(opcode >> 12) as usize](self, opcode)
+    }
+
+    /// Fail unless SUPER-CHIP instructions are available, as they are not valid CHIP-8 ones
+    fn require_super_chip(&self, opcode: u16) -> Result<(), EmulatorError> {
+        match self.mode {
+            EmulationMode::Chip8 => Err(self.unknown_opcode(opcode)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Fail unless XO-CHIP instructions are available
+    fn require_xo_chip(&self, opcode: u16) -> Result<(), EmulatorError> {
+        match self.mode {
+            EmulationMode::XoChip => Ok(()),
+            _ => Err(self.unknown_opcode(opcode)),
+        }
+    }
+
+    /// An opcode that isn't a valid instruction
+    fn handle_unknown(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        Err(self.unknown_opcode(opcode))
+    }
+
+    /// 0NNN: SYS, CLS, RET, and the SUPER-CHIP and XO-CHIP display instructions
+    fn handle_system(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        match nnn(opcode) {
+            0x0C0..=0x0CF => self.handle_scroll_down(opcode),
+            0x0D0..=0x0DF => self.handle_scroll_up(opcode),
+            0x0E0 => self.handle_cls(opcode),
+            0x0EE => self.handle_ret(opcode),
+            0x0FB => self.handle_scroll_right(opcode),
+            0x0FC => self.handle_scroll_left(opcode),
+            0x0FD => self.handle_exit_interpreter(opcode),
+            0x0FE => self.handle_low_res(opcode),
+            0x0FF => self.handle_high_res(opcode),
+            // machine code routines are intentionally ignored
+            _ => Ok(()),
+        }
+    }
+
+    /// 00E0: clear the display
+    fn handle_cls(&mut self, _opcode: u16) -> Result<(), EmulatorError> {
+        // set all spaces in the selected planes to false
+        for plane in self.selected_planes() {
+            self.clear_plane(plane);
+        }
+        self.vram_dirty = true;
+        Ok(())
+    }
+
+    /// 00EE: return from a subroutine
+    fn handle_ret(&mut self, _opcode: u16) -> Result<(), EmulatorError> {
+        // there must be something on the stack to return to
+        if self.registers.sp == 0 {
+            return Err(EmulatorError::StackUnderflow);
+        }
+
+        // decrement the stack pointer
+        self.registers.sp -= 1;
+
+        // set the program counter to be the newly popped address
+        self.registers.pc = self.memory.stack[self.registers.sp as usize];
+        Ok(())
+    }
+
+    /// 1NNN: jump to NNN
+    fn handle_jump(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.jump_to(nnn(opcode));
+        Ok(())
+    }
+
+    /// BNNN: jump to NNN plus V0, or plus VX where X is the high nibble of NNN with the jump0
+    /// quirk
+    fn handle_jump_with_offset(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        let offset_reg = if self.jump0_quirk { x(opcode) } else { 0x0 };
+        self.jump_to(nnn(opcode) + self.registers.v[offset_reg] as u16);
+        Ok(())
+    }
+
+    /// 2NNN: call the subroutine at NNN
+    fn handle_call(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        // there must be room on the stack for the return address
+        if self.registers.sp as usize >= STACK_SIZE {
+            return Err(EmulatorError::StackOverflow {
+                depth: self.registers.sp as usize,
+            });
+        }
+
+        // put the current PC at the top of the stack
+        self.memory.stack[self.registers.sp as usize] = self.registers.pc;
+        self.registers.sp += 1;
+
+        // replace the current PC with the given address
+        self.jump_to(nnn(opcode));
+        Ok(())
+    }
+
+    /// 3XNN: skip the next instruction if VX equals NN
+    fn handle_skip_equal_imm(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        if self.registers.v[x(opcode)] == nn(opcode) {
+            self.skip_next_instruction();
+        }
+        Ok(())
+    }
+
+    /// 4XNN: skip the next instruction if VX doesn't equal NN
+    fn handle_skip_not_equal_imm(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        if self.registers.v[x(opcode)] != nn(opcode) {
+            self.skip_next_instruction();
+        }
+        Ok(())
+    }
+
+    /// 5XYN: skip if VX equals VY, or the XO-CHIP register range store and load
+    fn handle_register_range(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        match n(opcode) {
+            0x0 => self.handle_skip_equal_reg(opcode),
+            0x2 => self.handle_store_range(opcode),
+            0x3 => self.handle_load_range(opcode),
+            _ => self.handle_unknown(opcode),
+        }
+    }
+
+    /// 5XY0: skip the next instruction if VX equals VY
+    fn handle_skip_equal_reg(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        if self.registers.v[x(opcode)] == self.registers.v[y(opcode)] {
+            self.skip_next_instruction();
+        }
+        Ok(())
+    }
+
+    /// 9XY0: skip the next instruction if VX doesn't equal VY
+    fn handle_skip_not_equal_reg(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        if self.registers.v[x(opcode)] != self.registers.v[y(opcode)] {
+            self.skip_next_instruction();
+        }
+        Ok(())
+    }
+
+    /// 6XNN: set VX to NN
+    fn handle_load_imm(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.registers.v[x(opcode)] = nn(opcode);
+        Ok(())
+    }
+
+    /// 8XYN: register arithmetic and logic
+    fn handle_arithmetic(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        ARITHMETIC_TABLE[n(opcode) as usize](self, opcode)
+    }
+
+    /// 8XY0: set VX to VY
+    fn handle_load_reg(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.registers.v[x(opcode)] = self.registers.v[y(opcode)];
+        Ok(())
+    }
+
+    /// ANNN: set I to NNN
+    fn handle_load_address(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.registers.i = nnn(opcode);
+        Ok(())
+    }
+
+    /// FX07: set VX to the delay timer
+    fn handle_read_delay_timer(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.registers.v[x(opcode)] = self.registers.dt;
+        Ok(())
+    }
+
+    /// FX15: set the delay timer to VX
+    fn handle_write_delay_timer(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.registers.dt = self.registers.v[x(opcode)];
+        Ok(())
+    }
+
+    /// FX18: set the sound timer to VX
+    fn handle_write_sound_timer(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.registers.st = self.registers.v[x(opcode)];
+        Ok(())
+    }
+
+    /// 7XNN: add NN to VX, without touching VF
+    fn handle_add_imm(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        let reg = x(opcode);
+        self.registers.v[reg] = self.registers.v[reg].wrapping_add(nn(opcode));
+        Ok(())
+    }
+
+    /// 8XY4: add VY to VX, setting VF on carry
+    fn handle_add_reg(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        let (regx, regy) = (x(opcode), y(opcode));
+        let (result, overflow): (u8, bool) =
+            self.registers.v[regx].overflowing_add(self.registers.v[regy]);
+
+        // set VF with whether there was an overflow, and then VX with the result
+        self.registers.v[0xF] = overflow as u8;
+        self.registers.v[regx] = result;
+        Ok(())
+    }
+
+    /// FX1E: add VX to I
+    fn handle_add_index(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        let sum = self.registers.i.wrapping_add(self.registers.v[x(opcode)] as u16);
+
+        // set VF if I went past the end of addressable memory
+        if self.add_index_overflow_quirk {
+            self.registers.v[0xF] = if sum > 0xFFF { 0x1 } else { 0x0 };
+        }
+
+        // I is a 16-bit register, so it wraps around past 0xFFFF
+        self.registers.i = sum;
+        Ok(())
+    }
+
+    /// 8XY5: subtract VY from VX, clearing VF on borrow
+    fn handle_sub_reg(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        let (regx, regy) = (x(opcode), y(opcode));
+        let (result, borrow): (u8, bool) =
+            self.registers.v[regx].overflowing_sub(self.registers.v[regy]);
+
+        // set VF with whether there wasn't a borrow, and then VX with the result
+        self.registers.v[0xF] = !borrow as u8;
+        self.registers.v[regx] = result;
+        Ok(())
+    }
+
+    /// 8XY7: set VX to VY minus VX, clearing VF on borrow
+    fn handle_subn_reg(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        let (regx, regy) = (x(opcode), y(opcode));
+        let (result, borrow): (u8, bool) =
+            self.registers.v[regy].overflowing_sub(self.registers.v[regx]);
+
+        // set VF with whether there wasn't a borrow, and then VX with the result
+        self.registers.v[0xF] = !borrow as u8;
+        self.registers.v[regx] = result;
+        Ok(())
+    }
+
+    /// 8XY6: set VX to VY shifted right by one, with the old low bit in VF
+    fn handle_shift_right_reg(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        let regx = x(opcode);
+
+        // put the value of VY into VX, unless shifting VX in place
+        if !self.shift_quirk {
+            self.registers.v[regx] = self.registers.v[y(opcode)];
+        }
+
+        let lsb = self.registers.v[regx] & 0x01;
+        self.registers.v[regx] >>= 1;
+        self.registers.v[0xF] = lsb;
+        Ok(())
+    }
+
+    /// 8XYE: set VX to VY shifted left by one, with the old high bit in VF
+    fn handle_shift_left_reg(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        let regx = x(opcode);
+
+        // put the value of VY into VX, unless shifting VX in place
+        if !self.shift_quirk {
+            self.registers.v[regx] = self.registers.v[y(opcode)];
+        }
+
+        let msb = (self.registers.v[regx] & 0x80) >> 7;
+        self.registers.v[regx] <<= 1;
+        self.registers.v[0xF] = msb;
+        Ok(())
+    }
+
+    /// 8XY1: set VX to VX OR VY, resetting VF
+    fn handle_or_reg(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.registers.v[x(opcode)] |= self.registers.v[y(opcode)];
+        self.registers.v[0xF] = 0x00;
+        Ok(())
+    }
+
+    /// 8XY2: set VX to VX AND VY, resetting VF
+    fn handle_and_reg(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.registers.v[x(opcode)] &= self.registers.v[y(opcode)];
+        self.registers.v[0xF] = 0x00;
+        Ok(())
+    }
+
+    /// 8XY3: set VX to VX XOR VY, resetting VF
+    fn handle_xor_reg(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.registers.v[x(opcode)] ^= self.registers.v[y(opcode)];
+        self.registers.v[0xF] = 0x00;
+        Ok(())
+    }
+
+    /// CXNN: set VX to a random number AND NN, resetting VF
+    fn handle_rand(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.registers.v[x(opcode)] = self.rng.gen::<u8>() & nn(opcode);
+        self.registers.v[0xF] = 0x00;
+        Ok(())
+    }
+
+    /// DXYN: draw an N byte sprite from I at (VX, VY), or a 16x16 one if N is 0
+    fn handle_draw(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        if self.hold_for_vblank() {
+            // stay on this instruction until the next frame
+            self.registers.pc -= 2;
+            return Ok(());
+        }
+
+        let (regx, regy) = (x(opcode), y(opcode));
+        match n(opcode) {
+            // draw a 16x16 sprite, which XO-CHIP keeps from SUPER-CHIP
+            0x0 if self.mode >= EmulationMode::SuperChip => self.draw_sprite(regx, regy, 16, 16),
+            // the original CHIP-8 draws a sprite with no rows
+            0x0 => self.draw_sprite(regx, regy, 0, 8),
+            // draw an 8 pixel wide sprite, N rows tall
+            rows => self.draw_sprite(regx, regy, rows as usize, 8),
+        }
+    }
+
+    /// 00CN: scroll the display down by N pixels, filling the top with blank rows
+    fn handle_scroll_down(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.require_super_chip(opcode)?;
+        self.scroll_display(0, n(opcode) as isize);
+        Ok(())
+    }
+
+    /// 00FB: scroll the display right by 4 pixels
+    fn handle_scroll_right(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.require_super_chip(opcode)?;
+        self.scroll_display(4, 0);
+        Ok(())
+    }
+
+    /// 00FC: scroll the display left by 4 pixels
+    fn handle_scroll_left(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.require_super_chip(opcode)?;
+        self.scroll_display(-4, 0);
+        Ok(())
+    }
+
+    /// 00FD: exit the interpreter
+    fn handle_exit_interpreter(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.require_super_chip(opcode)?;
+        self.exited = true;
+
+        // stay on this instruction
+        self.registers.pc -= 2;
+        Ok(())
+    }
+
+    /// 00FE: switch to low resolution mode and clear the display
+    fn handle_low_res(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.require_super_chip(opcode)?;
+        self.hires = false;
+        for plane in 0..PLANE_COUNT {
+            self.clear_plane(plane);
+        }
+        self.vram_dirty = true;
+        Ok(())
+    }
+
+    /// 00FF: switch to high resolution mode and clear the display
+    fn handle_high_res(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.require_super_chip(opcode)?;
+        self.hires = true;
+        for plane in 0..PLANE_COUNT {
+            self.clear_plane(plane);
+        }
+        self.vram_dirty = true;
+        Ok(())
+    }
+
+    /// FX30: point I at the large font sprite for the digit in VX
+    fn handle_set_large_sprite_loc(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.require_super_chip(opcode)?;
+
+        // each large sprite is 10 bytes long
+        self.registers.i = LARGE_FONTMEM_START + (self.registers.v[x(opcode)] as u16 % 10) * 0x0A;
+        Ok(())
+    }
+
+    /// FX75: store V0 to VX in the RPL user flags
+    fn handle_store_flags(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.require_super_chip(opcode)?;
+        let reg = x(opcode);
+        self.registers.flags[0..=reg].copy_from_slice(&self.registers.v[0..=reg]);
+        Ok(())
+    }
+
+    /// FX85: read V0 to VX from the RPL user flags
+    fn handle_read_flags(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.require_super_chip(opcode)?;
+        let reg = x(opcode);
+        self.registers.v[0..=reg].copy_from_slice(&self.registers.flags[0..=reg]);
+        Ok(())
+    }
+
+    /// 00DN: scroll the display up by N pixels, filling the bottom with blank rows
+    fn handle_scroll_up(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.require_xo_chip(opcode)?;
+        self.scroll_display(0, -(n(opcode) as isize));
+        Ok(())
+    }
+
+    /// 5XY2: store VX to VY in memory starting at I, without changing I
+    /// The registers are stored in reverse order if Y is less than X.
+    fn handle_store_range(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.require_xo_chip(opcode)?;
+        let (registers, reverse) = Self::register_range(x(opcode), y(opcode));
+        let (first, last) = (*registers.start(), *registers.end());
+        for r in registers {
+            let offset = if reverse { last - r } else { r - first };
+            self.write_byte(self.registers.i as usize + offset, self.registers.v[r])?;
+        }
+        Ok(())
+    }
+
+    /// 5XY3: read VX to VY from memory starting at I, without changing I
+    /// The registers are loaded in reverse order if Y is less than X.
+    fn handle_load_range(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.require_xo_chip(opcode)?;
+        let (registers, reverse) = Self::register_range(x(opcode), y(opcode));
+        let (first, last) = (*registers.start(), *registers.end());
+        for r in registers {
+            let offset = if reverse { last - r } else { r - first };
+            self.registers.v[r] = self.read_byte(self.registers.i as usize + offset)?;
+        }
+        Ok(())
+    }
+
+    /// F000 NNNN: set I to the 16-bit address after the opcode
+    fn handle_load_long_address(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        if x(opcode) != 0x0 {
+            return self.handle_unknown(opcode);
+        }
+        self.require_xo_chip(opcode)?;
+
+        let pc = self.registers.pc as usize;
+        let high = self.safe_read_byte(pc + 2)? as u16;
+        let low = self.safe_read_byte(pc + 3)? as u16;
+        self.registers.i = (high << 8) | low;
+
+        // skip over the address
+        self.registers.pc += 2;
+        Ok(())
+    }
+
+    /// FN01: select the display planes to draw to
+    fn handle_set_planes(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.require_xo_chip(opcode)?;
+        self.planes = x(opcode) as u8;
+        Ok(())
+    }
+
+    /// F002: load the audio pattern from memory starting at I
+    fn handle_load_audio(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        if x(opcode) != 0x0 {
+            return self.handle_unknown(opcode);
+        }
+        self.require_xo_chip(opcode)?;
+
+        for offset in 0..AUDIO_PATTERN_SIZE {
+            self.memory.audio_pattern[offset] = self.read_byte(self.registers.i as usize + offset)?;
+        }
+        Ok(())
+    }
+
+    /// FX3A: set the audio pitch to VX
+    fn handle_set_pitch(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        self.require_xo_chip(opcode)?;
+        self.registers.pitch = self.registers.v[x(opcode)];
+        Ok(())
+    }
+
+    /// FX29: point I at the small font sprite for the digit in VX
+    fn handle_set_sprite_loc(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        // each sprite is 5 bytes long
+        self.registers.i = self.registers.v[x(opcode)] as u16 * 0x05;
+        Ok(())
+    }
+
+    /// EXNN: the key instructions
+    fn handle_keys(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        KEY_TABLE[nn(opcode) as usize](self, opcode)
+    }
+
+    /// EX9E: skip the next instruction if the key in VX is pressed
+    fn handle_skip_if_key_pressed(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        if self.input.curr & (0x1 << self.registers.v[x(opcode)]) > 0 {
+            self.skip_next_instruction();
+        }
+        Ok(())
+    }
+
+    /// EXA1: skip the next instruction if the key in VX isn't pressed
+    fn handle_skip_if_key_not_pressed(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        if self.input.curr & (0x1 << self.registers.v[x(opcode)]) == 0 {
+            self.skip_next_instruction();
+        }
+        Ok(())
+    }
+
+    /// FXNN: the timer, memory and other instructions
+    fn handle_misc(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        MISC_TABLE[nn(opcode) as usize](self, opcode)
+    }
+
+    /// FX33: store the decimal digits of VX at I, I+1 and I+2
+    fn handle_store_bcd(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        let value = self.registers.v[x(opcode)];
+
+        let i = self.registers.i as usize;
+        self.write_byte(i, value / 100)?;
+        self.write_byte(i + 1, (value % 100) / 10)?;
+        self.write_byte(i + 2, value % 10)?;
+        Ok(())
+    }
+
+    /// FX55: store V0 to VX in memory starting at I
+    fn handle_store_registers(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        let reg = x(opcode);
+        for r in 0..=reg {
+            self.write_byte(self.registers.i as usize + r, self.registers.v[r])?;
+        }
+
+        self.increment_index_after_load_store(reg);
+        Ok(())
+    }
+
+    /// FX65: read V0 to VX from memory starting at I
+    fn handle_read_registers(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        let reg = x(opcode);
+        for r in 0..=reg {
+            self.registers.v[r] = self.read_byte(self.registers.i as usize + r)?;
+        }
+
+        self.increment_index_after_load_store(reg);
+        Ok(())
+    }
+
+    /// Move I on past the registers stored or read by FX55 and FX65, as the quirks say to
+    fn increment_index_after_load_store(&mut self, reg: usize) {
+        if self.load_store_quirk {
+            return;
+        }
+        let by = if self.load_store_by_x_quirk { reg } else { reg + 1 };
+        self.registers.i = self.registers.i.wrapping_add(by as u16);
+    }
+
+    /// FX0A: wait for a key to be pressed and then released, and store it in VX
+    fn handle_store_keypress(&mut self, opcode: u16) -> Result<(), EmulatorError> {
+        match self.input.waiting_for_key {
+            None => {
+                // start waiting, only counting keys pressed from now on
+                self.input.waiting_for_key = Some(x(opcode));
+                self.input.pressed_while_waiting = 0;
+            }
+            Some(_) => {
+                // get the keys that were pressed while waiting and have been released
+                let released_inputs: u16 = self.input.pressed_while_waiting & !self.input.curr;
+
+                // store the lowest key that was released and stop waiting
+                if released_inputs != 0 {
+                    self.registers.v[x(opcode)] = released_inputs.trailing_zeros() as u8;
+                    self.input.waiting_for_key = None;
+                }
+            }
+        }
+
+        if self.input.waiting_for_key.is_some() {
+            // counteract the PC increment that comes later
+            self.registers.pc -= 2;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chip8::{Chip8, Chip8Builder, EmulationMode, STACK_SIZE};
+    use crate::error::EmulatorError;
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    /// Make a machine with a ROM loaded, in an instruction set
+    fn load(rom: &[u8], mode: EmulationMode) -> Chip8 {
+        Chip8Builder::default()
+            .mode(mode)
+            .rom_bytes(rom)
+            .build()
+            .expect("the test ROM should load")
+    }
+
+    /// Run a number of instructions, failing the test if any of them fail
+    fn run(chip8: &mut Chip8, cycles: u32) {
+        for _ in 0..cycles {
+            chip8.do_next_instruction().expect("the instruction should run");
+        }
+    }
+
+    /// Count the pixels that are on in a corner of the display, once the frame is shown
+    fn lit_pixels(chip8: &mut Chip8, width: usize, height: usize) -> usize {
+        chip8.do_frame();
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| (x, y)))
+            .filter(|&(x, y)| chip8.peek_vram(x, y) == Some(true))
+            .count()
+    }
+
+    #[test]
+    fn draw_large_sprite_in_xo_chip() {
+        // V0 = 0, V1 = 0, I = 0x20A, draw 16x16 at (V0, V1), then a 32 byte solid sprite
+        let mut rom = vec![0x60, 0x00, 0x61, 0x00, 0xA2, 0x0A, 0xD0, 0x10, 0x12, 0x08];
+        rom.extend([0xFF; 32]);
+
+        for mode in [EmulationMode::SuperChip, EmulationMode::XoChip] {
+            let mut chip8 = load(&rom, mode);
+            run(&mut chip8, 4);
+            assert_eq!(lit_pixels(&mut chip8, 16, 16), 256, "{:?}", mode);
+            assert_eq!(chip8.peek_vram(16, 0), Some(false), "{:?}", mode);
+        }
+    }
+
+    #[test]
+    fn draw_sets_vf_only_when_a_pixel_is_turned_off() {
+        // V0 = 0, V1 = 0, I = 0x210, draw twice, clear, draw again, spin, then the sprite
+        let rom = [
+            0x60, 0x00, 0x61, 0x00, 0xA2, 0x10, 0xD0, 0x11, 0xD0, 0x11, 0x00, 0xE0, 0xD0, 0x11,
+            0x12, 0x0E, 0xF0, 0x00,
+        ];
+        let mut chip8 = load(&rom, EmulationMode::Chip8);
+
+        run(&mut chip8, 4);
+        assert_eq!(chip8.snapshot_registers().v[0xF], 0);
+        assert_eq!(lit_pixels(&mut chip8, 8, 1), 4);
+
+        run(&mut chip8, 1);
+        assert_eq!(chip8.snapshot_registers().v[0xF], 1);
+        assert_eq!(lit_pixels(&mut chip8, 8, 1), 0);
+
+        run(&mut chip8, 2);
+        assert_eq!(chip8.snapshot_registers().v[0xF], 0);
+        assert_eq!(lit_pixels(&mut chip8, 8, 1), 4);
+    }
+
+    #[test]
+    fn draw_reads_vf_as_x_before_setting_it() {
+        // VF = 2, V1 = 0, I = 0x20C, draw at (VF, V1) twice, spin, then a two row sprite
+        let rom = [
+            0x6F, 0x02, 0x61, 0x00, 0xA2, 0x0C, 0xDF, 0x12, 0xDF, 0x12, 0x12, 0x0A, 0xF0, 0xF0,
+        ];
+        let mut chip8 = load(&rom, EmulationMode::Chip8);
+
+        // the first draw is at x = 2, and leaves VF at 0
+        run(&mut chip8, 4);
+        assert_eq!(chip8.snapshot_registers().v[0xF], 0);
+
+        // the second is at x = 0, turning off the pixels at 2 and 3 on both rows
+        run(&mut chip8, 1);
+        assert_eq!(chip8.snapshot_registers().v[0xF], 1);
+        chip8.do_frame();
+        for y in 0..2 {
+            let row: Vec<bool> = (0..8).map(|x| chip8.peek_vram(x, y) == Some(true)).collect();
+            assert_eq!(row, [true, true, false, false, true, true, false, false]);
+        }
+    }
+
+    #[test]
+    fn draw_reads_vf_as_y_before_setting_it() {
+        // V0 = 0, VF = 1, I = 0x20C, draw at (V0, VF) twice, spin, then a two row sprite
+        let rom = [
+            0x60, 0x00, 0x6F, 0x01, 0xA2, 0x0C, 0xD0, 0xF2, 0xD0, 0xF2, 0x12, 0x0A, 0xF0, 0xF0,
+        ];
+        let mut chip8 = load(&rom, EmulationMode::Chip8);
+
+        // the first draw covers rows 1 and 2, and leaves VF at 0
+        run(&mut chip8, 4);
+        assert_eq!(chip8.snapshot_registers().v[0xF], 0);
+
+        // the second covers rows 0 and 1, turning off row 1
+        run(&mut chip8, 1);
+        assert_eq!(chip8.snapshot_registers().v[0xF], 1);
+        chip8.do_frame();
+        let rows: Vec<bool> = (0..3).map(|y| chip8.peek_vram(0, y) == Some(true)).collect();
+        assert_eq!(rows, [true, false, true]);
+    }
+
+    #[test]
+    fn call_past_the_top_of_the_stack_overflows() {
+        // call 0x200 from 0x200, forever
+        let mut chip8 = load(&[0x22, 0x00], EmulationMode::Chip8);
+        run(&mut chip8, STACK_SIZE as u32);
+        assert_eq!(chip8.stack_depth(), STACK_SIZE);
+
+        assert_eq!(
+            chip8.do_next_instruction(),
+            Err(EmulatorError::StackOverflow { depth: STACK_SIZE })
+        );
+        assert_eq!(chip8.stack_depth(), STACK_SIZE);
+    }
+
+    #[test]
+    fn return_with_an_empty_stack_underflows() {
+        let mut chip8 = load(&[0x00, 0xEE], EmulationMode::Chip8);
+        assert_eq!(chip8.do_next_instruction(), Err(EmulatorError::StackUnderflow));
+        assert_eq!(chip8.stack_depth(), 0);
+    }
+
+    #[test]
+    fn nested_calls_push_and_pop_the_stack_in_order() {
+        // call 0x206, which calls 0x20A, which calls 0x20E, and each returns
+        let rom = [
+            0x22, 0x06, 0x12, 0x02, 0x00, 0x00, 0x22, 0x0A, 0x00, 0xEE, 0x22, 0x0E, 0x00, 0xEE,
+            0x00, 0xEE,
+        ];
+        let mut chip8 = load(&rom, EmulationMode::Chip8);
+        // each call pushes its own address, and returning moves past it
+        run(&mut chip8, 3);
+        assert_eq!(chip8.stack_depth(), 3);
+        assert_eq!(chip8.peek_stack(), [0x200, 0x206, 0x20A]);
+
+        run(&mut chip8, 1);
+        assert_eq!(chip8.snapshot_registers().pc, 0x20C);
+        assert_eq!(chip8.peek_stack(), [0x200, 0x206]);
+
+        run(&mut chip8, 1);
+        assert_eq!(chip8.snapshot_registers().pc, 0x208);
+        assert_eq!(chip8.peek_stack(), [0x200]);
+
+        run(&mut chip8, 1);
+        assert_eq!(chip8.snapshot_registers().pc, 0x202);
+        assert!(chip8.peek_stack().is_empty());
+        assert_eq!(chip8.stack_depth(), 0);
+    }
+
+    #[test]
+    fn jump_with_offset_lands_on_the_address_plus_the_offset() {
+        // V0 = 4, V3 = 4, jump to 0x300 plus the offset
+        let rom = [0x60, 0x04, 0x63, 0x04, 0xB3, 0x00];
+        for jump0 in [false, true] {
+            let mut chip8 = Chip8Builder::default()
+                .jump0_quirk(jump0)
+                .rom_bytes(&rom)
+                .build()
+                .unwrap();
+            run(&mut chip8, 3);
+            assert_eq!(chip8.snapshot_registers().pc, 0x304, "jump0 quirk {}", jump0);
+        }
+    }
+
+    #[test]
+    fn jump_with_offset_uses_vx_with_the_jump0_quirk() {
+        // V0 = 4, V3 = 8, jump to 0x300 plus V0, or V3 with the quirk
+        let rom = [0x60, 0x04, 0x63, 0x08, 0xB3, 0x00];
+        for (jump0, pc) in [(false, 0x304), (true, 0x308)] {
+            let mut chip8 = Chip8Builder::default()
+                .jump0_quirk(jump0)
+                .rom_bytes(&rom)
+                .build()
+                .unwrap();
+            run(&mut chip8, 3);
+            assert_eq!(chip8.snapshot_registers().pc, pc, "jump0 quirk {}", jump0);
+        }
+    }
+
+    #[test]
+    fn shift_right_shifts_vy_or_vx_in_place() {
+        // V1 = 0x03, V2 = 0x40, V1 = V2 >> 1, or V1 >> 1 with the quirk
+        let rom = [0x61, 0x03, 0x62, 0x40, 0x81, 0x26];
+        for (shift, v1, vf) in [(false, 0x20, 0), (true, 0x01, 1)] {
+            let mut chip8 = Chip8Builder::default()
+                .shift_quirk(shift)
+                .rom_bytes(&rom)
+                .build()
+                .unwrap();
+            run(&mut chip8, 3);
+
+            let registers = chip8.snapshot_registers();
+            assert_eq!(registers.v[1], v1, "shift quirk {}", shift);
+            assert_eq!(registers.v[2], 0x40, "shift quirk {}", shift);
+            assert_eq!(registers.v[0xF], vf, "shift quirk {}", shift);
+        }
+    }
+
+    #[test]
+    fn shift_left_shifts_vy_or_vx_in_place() {
+        // V1 = 0x81, V2 = 0x40, V1 = V2 << 1, or V1 << 1 with the quirk
+        let rom = [0x61, 0x81, 0x62, 0x40, 0x81, 0x2E];
+        for (shift, v1, vf) in [(false, 0x80, 0), (true, 0x02, 1)] {
+            let mut chip8 = Chip8Builder::default()
+                .shift_quirk(shift)
+                .rom_bytes(&rom)
+                .build()
+                .unwrap();
+            run(&mut chip8, 3);
+
+            let registers = chip8.snapshot_registers();
+            assert_eq!(registers.v[1], v1, "shift quirk {}", shift);
+            assert_eq!(registers.v[2], 0x40, "shift quirk {}", shift);
+            assert_eq!(registers.v[0xF], vf, "shift quirk {}", shift);
+        }
+    }
+
+    #[test]
+    fn store_and_read_registers_with_and_without_the_load_store_quirks() {
+        // set V0 to V3, store them at 0x300, clear them, then read them back from 0x300
+        let rom = [
+            0x60, 0x11, 0x61, 0x22, 0x62, 0x33, 0x63, 0x44, 0xA3, 0x00, 0xF3, 0x55, 0x60, 0x00,
+            0x61, 0x00, 0x62, 0x00, 0x63, 0x00, 0xA3, 0x00, 0xF3, 0x65,
+        ];
+        let cases = [
+            (false, false, 0x304),
+            (true, false, 0x300),
+            (false, true, 0x303),
+            (true, true, 0x300),
+        ];
+        for (load_store, by_x, i) in cases {
+            let mut chip8 = Chip8Builder::default()
+                .load_store_quirk(load_store)
+                .load_store_by_x_quirk(by_x)
+                .rom_bytes(&rom)
+                .build()
+                .unwrap();
+
+            run(&mut chip8, 6);
+            let stored: Vec<Option<u8>> = (0x300..0x305).map(|a| chip8.peek_ram(a)).collect();
+            assert_eq!(stored, [Some(0x11), Some(0x22), Some(0x33), Some(0x44), Some(0x00)]);
+            let quirks = (load_store, by_x);
+            assert_eq!(chip8.snapshot_registers().i, i, "load/store quirks {:?}", quirks);
+
+            run(&mut chip8, 6);
+            let registers = chip8.snapshot_registers();
+            assert_eq!(registers.v[..5], [0x11, 0x22, 0x33, 0x44, 0x00]);
+            assert_eq!(registers.i, i, "load/store quirks {:?}", quirks);
+        }
+    }
+
+    #[test]
+    fn add_index_sets_vf_past_0xfff_only_with_the_quirk() {
+        // VF = 0x55, V1 = 0x10, I = 0xFF0, I += V1
+        let rom = [0x6F, 0x55, 0x61, 0x10, 0xAF, 0xF0, 0xF1, 0x1E];
+        for (quirk, vf) in [(false, 0x55), (true, 0x01)] {
+            let mut chip8 = Chip8Builder::default()
+                .add_index_overflow_quirk(quirk)
+                .rom_bytes(&rom)
+                .build()
+                .unwrap();
+            run(&mut chip8, 4);
+
+            let registers = chip8.snapshot_registers();
+            assert_eq!(registers.i, 0x1000, "add index overflow quirk {}", quirk);
+            assert_eq!(registers.v[0xF], vf, "add index overflow quirk {}", quirk);
+        }
+    }
+
+    #[test]
+    fn add_index_clears_vf_up_to_0xfff_with_the_quirk() {
+        // VF = 0x55, V1 = 0x10, I = 0xFEF, I += V1
+        let rom = [0x6F, 0x55, 0x61, 0x10, 0xAF, 0xEF, 0xF1, 0x1E];
+        let mut chip8 = Chip8Builder::default()
+            .add_index_overflow_quirk(true)
+            .rom_bytes(&rom)
+            .build()
+            .unwrap();
+        run(&mut chip8, 4);
+
+        let registers = chip8.snapshot_registers();
+        assert_eq!(registers.i, 0xFFF);
+        assert_eq!(registers.v[0xF], 0x00);
+    }
+
+    #[test]
+    fn add_index_wraps_i_at_16_bits() {
+        // V1 = 0xFF, I += V1
+        let mut chip8 = load(&[0x61, 0xFF, 0xF1, 0x1E], EmulationMode::Chip8);
+        run(&mut chip8, 1);
+        chip8.set_i(0xFFF0);
+        run(&mut chip8, 1);
+        assert_eq!(chip8.snapshot_registers().i, 0x00EF);
+    }
+
+    #[test]
+    fn store_registers_over_the_font_only_without_write_protection() {
+        // V0 to V3 = 0xAA, I = 0x000, store V0 to V3
+        let rom = [0x60, 0xAA, 0x61, 0xAA, 0x62, 0xAA, 0x63, 0xAA, 0xA0, 0x00, 0xF3, 0x55];
+        let first_bytes = |chip8: &Chip8| -> Vec<Option<u8>> {
+            (0x000..0x004).map(|addr| chip8.peek_ram(addr)).collect()
+        };
+
+        for protect in [true, false] {
+            let mut chip8 = Chip8Builder::default()
+                .write_protect_font(protect)
+                .rom_bytes(&rom)
+                .build()
+                .expect("the test ROM should load");
+            let font = first_bytes(&chip8);
+
+            // blocked writes are skipped without stopping the program
+            run(&mut chip8, 6);
+            if protect {
+                assert_eq!(first_bytes(&chip8), font);
+            } else {
+                assert_eq!(first_bytes(&chip8), [Some(0xAA); 4]);
+            }
+        }
+    }
+
+
+    #[test]
+    fn skip_at_the_end_of_memory_fails_only_when_the_pc_gets_there() {
+        for mode in [EmulationMode::Chip8, EmulationMode::XoChip] {
+            // jump to the last instruction in memory, which skips the next one
+            let mut chip8 = load(&[0x1F, 0xFE], mode);
+            chip8.inject_ram(0xFFE, &[0x30, 0x00]).expect("the skip should fit");
+            run(&mut chip8, 2);
+            assert_eq!(chip8.snapshot_registers().pc, 0x1002, "{:?}", mode);
+
+            assert!(matches!(
+                chip8.do_next_instruction(),
+                Err(EmulatorError::OutOfBoundsMemoryAccess { addr: 0x1002, .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn store_and_load_register_ranges_in_either_order() {
+        // V1 to V3 = 1 to 3, store them forwards at 0x300 and backwards at 0x304, then load
+        // the backwards ones forwards
+        let rom = [
+            0x61, 0x01, 0x62, 0x02, 0x63, 0x03, 0xA3, 0x00, 0x51, 0x32, 0xA3, 0x04, 0x53, 0x12,
+            0x51, 0x33,
+        ];
+        let mut chip8 = load(&rom, EmulationMode::XoChip);
+        run(&mut chip8, 7);
+        let stored: Vec<Option<u8>> = (0x300..0x307).map(|addr| chip8.peek_ram(addr)).collect();
+        assert_eq!(stored, [1, 2, 3, 0, 3, 2, 1].map(Some));
+
+        run(&mut chip8, 1);
+        assert_eq!(chip8.snapshot_registers().v[1..4], [3, 2, 1]);
+    }
+}
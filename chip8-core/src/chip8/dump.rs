@@ -0,0 +1,96 @@
+use crate::chip8::{Chip8, PLANE_COUNT};
+use std::io::{self, Write};
+
+// the number of stack frames shown, from the innermost call outwards
+const DUMP_STACK_FRAMES: usize = 8;
+
+// the number of bytes on each line of the RAM dump
+const DUMP_BYTES_PER_LINE: usize = 16;
+
+impl Chip8 {
+    /// Write the registers, stack, RAM and display out as text, such as after a crash
+    /// RAM is dumped 16 bytes to a line with the printable bytes alongside, and the display is
+    /// drawn with `#` for pixels that are on, or the color in hex for XO-CHIP's other planes.
+    pub fn dump_state_to_writer(&self, w: &mut impl Write) -> io::Result<()> {
+        let registers = &self.registers;
+
+        // registers
+        let v = registers
+            .v
+            .iter()
+            .enumerate()
+            .map(|(reg, value)| format!("V{:X}={:#04x}", reg, value))
+            .collect::<Vec<String>>()
+            .join(" ");
+        writeln!(w, "{}", v)?;
+        writeln!(
+            w,
+            "I={:#05x} PC={:#05x} SP={} DT={:#04x} ST={:#04x}",
+            registers.i, registers.pc, registers.sp, registers.dt, registers.st
+        )?;
+
+        // stack
+        writeln!(w)?;
+        writeln!(w, "Stack:")?;
+        let stack = self.peek_stack();
+        if stack.is_empty() {
+            writeln!(w, "  (empty)")?;
+        }
+        for (depth, addr) in stack.iter().enumerate().rev().take(DUMP_STACK_FRAMES) {
+            writeln!(w, "  {:>4}: {:#05x}", depth, addr)?;
+        }
+        if stack.len() > DUMP_STACK_FRAMES {
+            writeln!(w, "  ...and {} more", stack.len() - DUMP_STACK_FRAMES)?;
+        }
+
+        // RAM
+        writeln!(w)?;
+        writeln!(w, "RAM:")?;
+        for (line, bytes) in self.memory.ram.chunks(DUMP_BYTES_PER_LINE).enumerate() {
+            let hex = bytes
+                .iter()
+                .map(|byte| format!("{:02X}", byte))
+                .collect::<Vec<String>>()
+                .join(" ");
+            let ascii: String = bytes
+                .iter()
+                .map(|&byte| match byte {
+                    b' ' => ' ',
+                    _ if byte.is_ascii_graphic() => byte as char,
+                    _ => '.',
+                })
+                .collect();
+            writeln!(w, "{:03X}: {} |{}|", line * DUMP_BYTES_PER_LINE, hex, ascii)?;
+        }
+
+        // display, as drawn so far rather than as last shown
+        let (width, height) = self.display_size();
+        writeln!(w)?;
+        writeln!(w, "VRAM ({}x{}):", width, height)?;
+        for y in 0..height {
+            let row: String = (0..width)
+                .map(|x| {
+                    let color = (0..PLANE_COUNT)
+                        .filter(|&plane| self.memory.back_vram[plane][y][x])
+                        .fold(0, |color, plane| color | (0x1 << plane));
+                    match color {
+                        0 => '.',
+                        1 => '#',
+                        _ => char::from_digit(color, 16).unwrap_or('?'),
+                    }
+                })
+                .collect();
+            writeln!(w, "{}", row)?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the registers, stack, RAM and display as text
+    /// This is the same as `dump_state_to_writer`, collected into a string.
+    pub fn dump_state_to_string(&self) -> String {
+        let mut dump = Vec::new();
+        self.dump_state_to_writer(&mut dump).expect("Writing to a Vec never fails");
+        String::from_utf8_lossy(&dump).into_owned()
+    }
+}
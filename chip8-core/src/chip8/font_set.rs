@@ -0,0 +1,210 @@
+use crate::chip8::{Chip8, DEFAULT_FONT};
+use crate::error::Chip8Error;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::str::FromStr;
+
+/// The font of the COSMAC VIP interpreter, where CHIP-8 started
+const COSMAC_FONT: [u8; 80] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+    0x60, 0x20, 0x20, 0x20, 0x70, // 1
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+    0xF0, 0x10, 0x70, 0x10, 0xF0, // 3
+    0xA0, 0xA0, 0xF0, 0x20, 0x20, // 4
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+    0xF0, 0x10, 0x10, 0x10, 0x10, // 7
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+    0xF0, 0x50, 0x70, 0x50, 0xF0, // B
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+    0xF0, 0x50, 0x50, 0x50, 0xF0, // D
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+    0xF0, 0x80, 0xF0, 0x80, 0x80, // F
+];
+
+/// The narrower font of the DREAM 6800
+const DREAM6800_FONT: [u8; 80] = [
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // 0
+    0x40, 0x40, 0x40, 0x40, 0x40, // 1
+    0xE0, 0x20, 0xE0, 0x80, 0xE0, // 2
+    0xE0, 0x20, 0xE0, 0x20, 0xE0, // 3
+    0x80, 0xA0, 0xA0, 0xE0, 0x20, // 4
+    0xE0, 0x80, 0xE0, 0x20, 0xE0, // 5
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0, // 6
+    0xE0, 0x20, 0x20, 0x20, 0x20, // 7
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // 8
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0, // 9
+    0xE0, 0xA0, 0xE0, 0xA0, 0xA0, // A
+    0xC0, 0xA0, 0xE0, 0xA0, 0xC0, // B
+    0xE0, 0x80, 0x80, 0x80, 0xE0, // C
+    0xC0, 0xA0, 0xA0, 0xA0, 0xC0, // D
+    0xE0, 0x80, 0xE0, 0x80, 0xE0, // E
+    0xE0, 0x80, 0xC0, 0x80, 0x80, // F
+];
+
+/// The font of the ETI 660, which is also three pixels wide
+const ETI660_FONT: [u8; 80] = [
+    0xE0, 0xA0, 0xA0, 0xA0, 0xE0, // 0
+    0x20, 0x20, 0x20, 0x20, 0x20, // 1
+    0xE0, 0x20, 0xE0, 0x80, 0xE0, // 2
+    0xE0, 0x20, 0xE0, 0x20, 0xE0, // 3
+    0xA0, 0xA0, 0xE0, 0x20, 0x20, // 4
+    0xE0, 0x80, 0xE0, 0x20, 0xE0, // 5
+    0xE0, 0x80, 0xE0, 0xA0, 0xE0, // 6
+    0xE0, 0x20, 0x20, 0x20, 0x20, // 7
+    0xE0, 0xA0, 0xE0, 0xA0, 0xE0, // 8
+    0xE0, 0xA0, 0xE0, 0x20, 0xE0, // 9
+    0xE0, 0xA0, 0xE0, 0xA0, 0xA0, // A
+    0x80, 0x80, 0xE0, 0xA0, 0xE0, // B
+    0xE0, 0x80, 0x80, 0x80, 0xE0, // C
+    0x20, 0x20, 0xE0, 0xA0, 0xE0, // D
+    0xE0, 0x80, 0xE0, 0x80, 0xE0, // E
+    0xE0, 0x80, 0xE0, 0x80, 0x80, // F
+];
+
+/// The small font for the digits 0-F, as drawn by FX29
+/// The fonts of historical interpreters differ slightly, which some programs depend on.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum FontSet {
+    /// The font used by most modern interpreters
+    #[default]
+    Original,
+
+    /// The font of the DREAM 6800
+    Dream6800,
+
+    /// The font of the ETI 660
+    Eti660,
+
+    /// The font of the COSMAC VIP
+    Cosmac,
+
+    /// A font of up to 512 bytes, such as one read from a file
+    Custom(Vec<u8>),
+}
+
+impl FontSet {
+    /// Get the bytes of the font, five for each digit
+    pub fn bytes(&self) -> &[u8] {
+        match self {
+            FontSet::Original => &DEFAULT_FONT,
+            FontSet::Dream6800 => &DREAM6800_FONT,
+            FontSet::Eti660 => &ETI660_FONT,
+            FontSet::Cosmac => &COSMAC_FONT,
+            FontSet::Custom(bytes) => bytes,
+        }
+    }
+}
+
+impl FromStr for FontSet {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "original" => Ok(FontSet::Original),
+            "dream" | "dream6800" => Ok(FontSet::Dream6800),
+            "eti" | "eti660" => Ok(FontSet::Eti660),
+            "cosmac" | "vip" => Ok(FontSet::Cosmac),
+            _ => Err(format!("Unknown font set '{}'", s)),
+        }
+    }
+}
+
+impl Chip8 {
+    /// Load one of the font sets into font memory
+    pub fn load_font(self, font: FontSet) -> Result<Self, Chip8Error> {
+        self.load_font_from_bytes(font.bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FontSet, COSMAC_FONT};
+    use crate::chip8::Chip8Builder;
+    use alloc::string::String;
+    use alloc::vec::Vec;
+
+    // how each font should look, with the digits 0 to F side by side
+    const ORIGINAL_PIXELS: [&str; 5] = [
+        "#### ..#. #### #### #..# #### #### #### #### #### #### ###. #### ###. #### ####",
+        "#..# .##. ...# ...# #..# #... #... ...# #..# #..# #..# #..# #... #..# #... #...",
+        "#..# ..#. #### #### #### #### #### ..#. #### #### #### ###. #... #..# #### ####",
+        "#..# ..#. #... ...# ...# ...# #..# .#.. #..# ...# #..# #..# #... #..# #... #...",
+        "#### .### #### #### ...# #### #### .#.. #### #### #..# ###. #### ###. #### #...",
+    ];
+    const DREAM6800_PIXELS: [&str; 5] = [
+        "###. .#.. ###. ###. #... ###. ###. ###. ###. ###. ###. ##.. ###. ##.. ###. ###.",
+        "#.#. .#.. ..#. ..#. #.#. #... #... ..#. #.#. #.#. #.#. #.#. #... #.#. #... #...",
+        "#.#. .#.. ###. ###. #.#. ###. ###. ..#. ###. ###. ###. ###. #... #.#. ###. ##..",
+        "#.#. .#.. #... ..#. ###. ..#. #.#. ..#. #.#. ..#. #.#. #.#. #... #.#. #... #...",
+        "###. .#.. ###. ###. ..#. ###. ###. ..#. ###. ###. #.#. ##.. ###. ##.. ###. #...",
+    ];
+    const ETI660_PIXELS: [&str; 5] = [
+        "###. ..#. ###. ###. #.#. ###. ###. ###. ###. ###. ###. #... ###. ..#. ###. ###.",
+        "#.#. ..#. ..#. ..#. #.#. #... #... ..#. #.#. #.#. #.#. #... #... ..#. #... #...",
+        "#.#. ..#. ###. ###. ###. ###. ###. ..#. ###. ###. ###. ###. #... ###. ###. ###.",
+        "#.#. ..#. #... ..#. ..#. ..#. #.#. ..#. #.#. ..#. #.#. #.#. #... #.#. #... #...",
+        "###. ..#. ###. ###. ..#. ###. ###. ..#. ###. ###. #.#. ###. ###. ###. ###. #...",
+    ];
+    const COSMAC_PIXELS: [&str; 5] = [
+        "#### .##. #### #### #.#. #### #### #### #### #### #### #### #### #### #### ####",
+        "#..# ..#. ...# ...# #.#. #... #... ...# #..# #..# #..# .#.# #... .#.# #... #...",
+        "#..# ..#. #### .### #### #### #### ...# #### #### #### .### #... .#.# #### ####",
+        "#..# ..#. #... ...# ..#. ...# #..# ...# #..# ...# #..# .#.# #... .#.# #... #...",
+        "#### .### #### #### ..#. #### #### ...# #### #### #..# #### #### #### #### #...",
+    ];
+
+    /// Draw each digit with FX29 and DXY5, and get the pixels drawn in the same layout as above
+    fn draw_digits(font: FontSet) -> Vec<String> {
+        let digits: Vec<Vec<String>> = (0x0..=0xF)
+            .map(|digit| {
+                // V0 = the digit, point I at it, draw it at (0, 0), then loop forever
+                let rom = [0x60, digit, 0xF0, 0x29, 0xD1, 0x15, 0x12, 0x06];
+                let mut chip8 = Chip8Builder::default()
+                    .font_set(font.clone())
+                    .rom_bytes(&rom)
+                    .build()
+                    .expect("the test ROM should load");
+                for _ in 0..3 {
+                    chip8.do_next_instruction().expect("the instruction should run");
+                }
+                chip8.do_frame();
+
+                (0..5)
+                    .map(|y| {
+                        (0..4)
+                            .map(|x| match chip8.peek_vram(x, y) {
+                                Some(true) => '#',
+                                _ => '.',
+                            })
+                            .collect()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        (0..5)
+            .map(|y| {
+                let row: Vec<&str> = digits.iter().map(|digit| digit[y].as_str()).collect();
+                row.join(" ")
+            })
+            .collect()
+    }
+
+    #[test]
+    fn draw_each_font_set() {
+        let fonts = [
+            (FontSet::Original, ORIGINAL_PIXELS),
+            (FontSet::Dream6800, DREAM6800_PIXELS),
+            (FontSet::Eti660, ETI660_PIXELS),
+            (FontSet::Cosmac, COSMAC_PIXELS),
+            (FontSet::Custom(COSMAC_FONT.to_vec()), COSMAC_PIXELS),
+        ];
+        for (font, pixels) in fonts {
+            assert_eq!(draw_digits(font.clone()), pixels, "{:?}", font);
+        }
+    }
+}
@@ -0,0 +1,413 @@
+use crate::chip8::{
+    Chip8, Registers, Vram, AUDIO_PATTERN_SIZE, HIRES_HEIGHT, HIRES_WIDTH, PLANE_COUNT,
+};
+use crate::error::Chip8Error;
+use crate::instructions::Instruction::*;
+use crate::instructions::Register;
+use alloc::boxed::Box;
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::mem;
+
+/// The default number of instructions that can be stepped back through
+pub const DEFAULT_HISTORY_DEPTH: usize = 256;
+
+// the number of pixels in one display plane and in all of them, and the bytes for a bit for each
+const PLANE_SIZE: usize = HIRES_HEIGHT * HIRES_WIDTH;
+const PIXEL_COUNT: usize = PLANE_COUNT * PLANE_SIZE;
+const FLIP_MASK_SIZE: usize = PIXEL_COUNT / 8;
+
+// the most flipped pixels listed one by one, before a bit for every pixel takes less memory
+const MAX_LISTED_FLIPS: usize = FLIP_MASK_SIZE / 2;
+
+/// The pixels flipped by a single instruction
+/// Most instructions flip a few pixels, which are listed. A clear or scroll can flip most of the
+/// display, so past a point a bit is kept for every pixel instead.
+#[derive(Clone)]
+enum PixelFlips {
+    /// The index of each pixel flipped, counting along the rows of each plane in turn
+    Listed(Vec<u16>),
+
+    /// A bit for each pixel, in the same order, set if the pixel was flipped
+    Mask(Box<[u8; FLIP_MASK_SIZE]>),
+}
+
+impl PixelFlips {
+    /// Note that a pixel was flipped
+    fn flip(&mut self, index: u16) {
+        match self {
+            PixelFlips::Listed(flips) if flips.len() < MAX_LISTED_FLIPS => flips.push(index),
+            PixelFlips::Listed(flips) => {
+                let mut mask = Box::new([0; FLIP_MASK_SIZE]);
+                for &flip in flips.iter() {
+                    mask[flip as usize / 8] ^= 0x1 << (flip % 8);
+                }
+                mask[index as usize / 8] ^= 0x1 << (index % 8);
+                *self = PixelFlips::Mask(mask);
+            }
+            PixelFlips::Mask(mask) => mask[index as usize / 8] ^= 0x1 << (index % 8),
+        }
+    }
+
+    /// Flip the pixels back, returning whether there were any
+    fn undo(&self, vram: &mut [Vram; PLANE_COUNT]) -> bool {
+        let mut undo_flip = |index: usize| {
+            let (plane, pixel) = (index / PLANE_SIZE, index % PLANE_SIZE);
+            let pixel = &mut vram[plane][pixel / HIRES_WIDTH][pixel % HIRES_WIDTH];
+            *pixel = !*pixel;
+        };
+
+        match self {
+            PixelFlips::Listed(flips) => {
+                flips.iter().for_each(|&flip| undo_flip(flip as usize));
+                !flips.is_empty()
+            }
+            PixelFlips::Mask(mask) => {
+                (0..PIXEL_COUNT)
+                    .filter(|&index| mask[index / 8] & (0x1 << (index % 8)) > 0)
+                    .for_each(undo_flip);
+                true
+            }
+        }
+    }
+}
+
+impl Default for PixelFlips {
+    fn default() -> Self {
+        PixelFlips::Listed(Vec::new())
+    }
+}
+
+/// The changes made by a single instruction
+/// Only what the instruction could have changed is kept, so most deltas are small.
+#[derive(Clone)]
+pub(super) struct StateDelta {
+    /// The registers just before the instruction was executed
+    registers: Registers,
+
+    /// The addresses of the bytes of RAM that were written, and what was there before
+    ram: Vec<(usize, u8)>,
+
+    /// The stack slot that was written by a call, and what was there before
+    stack: Option<(usize, u16)>,
+
+    /// The pixels that were flipped
+    vram: PixelFlips,
+
+    /// The audio pattern, if the instruction could have changed it
+    audio_pattern: Option<[u8; AUDIO_PATTERN_SIZE]>,
+
+    /// The register being waited on for a keypress, and the keys pressed while waiting
+    waiting_for_key: Option<Register>,
+    pressed_while_waiting: u16,
+
+    /// The display mode and the planes being drawn to
+    hires: bool,
+    planes: u8,
+
+    /// Whether the program had asked to exit
+    exited: bool,
+
+    /// Whether a sprite was being held back until the next frame
+    draw_pending: bool,
+    waiting_for_vblank: bool,
+
+    /// The number of instructions run before this one
+    total_cycles: u64,
+}
+
+/// A record of the changes made by the last few instructions, so that they can be undone
+#[derive(Clone)]
+pub(super) struct History {
+    deltas: VecDeque<StateDelta>,
+    depth: usize,
+
+    /// The RAM written by the instruction being executed, and what was there before
+    ram_writes: Vec<(usize, u8)>,
+
+    /// The pixels flipped by the instruction being executed
+    pixel_flips: PixelFlips,
+}
+
+impl History {
+    /// Make an empty history that keeps the last `depth` instructions
+    fn new(depth: usize) -> Self {
+        Self {
+            deltas: VecDeque::with_capacity(depth),
+            depth,
+            ram_writes: Vec::new(),
+            pixel_flips: PixelFlips::default(),
+        }
+    }
+
+    /// Note that a byte of RAM is about to be overwritten
+    pub(super) fn record_ram_write(&mut self, addr: usize, old: u8) {
+        self.ram_writes.push((addr, old));
+    }
+
+    /// Note that a pixel of the display is about to be flipped
+    pub(super) fn record_pixel_flip(&mut self, plane: usize, y: usize, x: usize) {
+        let index = plane * PLANE_SIZE + y * HIRES_WIDTH + x;
+        self.pixel_flips.flip(index as u16);
+    }
+
+    /// Forget every instruction recorded so far
+    pub(super) fn clear(&mut self) {
+        self.deltas.clear();
+        self.ram_writes.clear();
+        self.pixel_flips = PixelFlips::default();
+    }
+}
+
+impl Chip8 {
+    /// Record the changes made by every instruction, so the last `depth` of them can be undone
+    /// with `step_back`. Enabling history again clears it.
+    pub fn enable_history(&mut self, depth: usize) {
+        self.history = Some(History::new(depth));
+    }
+
+    /// Stop recording the changes made by instructions, and forget those already recorded
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    /// Get the number of instructions that can currently be stepped back through
+    pub fn history_len(&self) -> usize {
+        self.history
+            .as_ref()
+            .map_or(0, |history| history.deltas.len())
+    }
+
+    /// Undo the last instruction executed
+    /// Timers and RAM are put back as they were, but the random numbers used by CXNN are not, so
+    /// stepping forward again may give a different result. Fails if history is not enabled, or
+    /// there are no instructions left to undo.
+    pub fn step_back(&mut self) -> Result<(), Chip8Error> {
+        let delta = self
+            .history
+            .as_mut()
+            .and_then(|history| history.deltas.pop_back())
+            .ok_or(Chip8Error::NoHistory)?;
+
+        // undo the writes newest first, so a byte written twice ends up with its first value
+        for &(addr, old) in delta.ram.iter().rev() {
+            self.memory.ram[addr] = old;
+        }
+        if let Some((slot, old)) = delta.stack {
+            self.memory.stack[slot] = old;
+        }
+        self.vram_dirty |= delta.vram.undo(&mut self.memory.back_vram);
+        if let Some(audio_pattern) = delta.audio_pattern {
+            self.memory.audio_pattern = audio_pattern;
+        }
+
+        self.registers = delta.registers;
+        self.input.waiting_for_key = delta.waiting_for_key;
+        self.input.pressed_while_waiting = delta.pressed_while_waiting;
+        self.hires = delta.hires;
+        self.planes = delta.planes;
+        self.exited = delta.exited;
+        self.draw_pending = delta.draw_pending;
+        self.waiting_for_vblank = delta.waiting_for_vblank;
+        self.total_cycles = delta.total_cycles;
+
+        Ok(())
+    }
+
+    /// Keep what is needed to undo the next instruction, if history is enabled
+    pub(super) fn begin_delta(&mut self) -> Option<StateDelta> {
+        let history = self.history.as_mut()?;
+        history.ram_writes.clear();
+        history.pixel_flips = PixelFlips::default();
+
+        let instruction = self.get_current_instruction().ok();
+        let stack = match instruction {
+            Some(Call(_)) => self
+                .memory
+                .stack
+                .get(self.registers.sp as usize)
+                .map(|&old| (self.registers.sp as usize, old)),
+            _ => None,
+        };
+        let audio_pattern = match instruction {
+            Some(LoadAudio) => Some(self.memory.audio_pattern),
+            _ => None,
+        };
+
+        Some(StateDelta {
+            registers: self.registers,
+            ram: Vec::new(),
+            stack,
+            vram: PixelFlips::default(),
+            audio_pattern,
+            waiting_for_key: self.input.waiting_for_key,
+            pressed_while_waiting: self.input.pressed_while_waiting,
+            hires: self.hires,
+            planes: self.planes,
+            exited: self.exited,
+            draw_pending: self.draw_pending,
+            waiting_for_vblank: self.waiting_for_vblank,
+            total_cycles: self.total_cycles,
+        })
+    }
+
+    /// Add what the instruction changed to the history
+    pub(super) fn end_delta(&mut self, delta: Option<StateDelta>) {
+        let (Some(mut delta), Some(history)) = (delta, self.history.as_mut()) else {
+            return;
+        };
+        delta.ram = mem::take(&mut history.ram_writes);
+        delta.vram = mem::take(&mut history.pixel_flips);
+
+        if history.deltas.len() >= history.depth {
+            history.deltas.pop_front();
+        }
+        if history.depth > 0 {
+            history.deltas.push_back(delta);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chip8::history::{PixelFlips, StateDelta, DEFAULT_HISTORY_DEPTH};
+    use crate::chip8::{
+        Chip8, Chip8Builder, EmulationMode, RegisterSnapshot, Vram, MEM_SIZE, PLANE_COUNT,
+    };
+    use crate::error::Chip8Error;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::mem;
+
+    /// Make a machine with a ROM loaded and history enabled, in an instruction set
+    fn load(rom: &[u8], mode: EmulationMode) -> Chip8 {
+        let mut chip8 = Chip8Builder::default()
+            .mode(mode)
+            .rom_bytes(rom)
+            .build()
+            .expect("the test ROM should load");
+        chip8.enable_history(DEFAULT_HISTORY_DEPTH);
+        chip8
+    }
+
+    /// Get the registers, display planes and RAM
+    fn state(chip8: &Chip8) -> (RegisterSnapshot, [Vram; PLANE_COUNT], [u8; MEM_SIZE]) {
+        (
+            chip8.snapshot_registers(),
+            chip8.memory.back_vram,
+            chip8.memory.ram,
+        )
+    }
+
+    /// Run a number of instructions, then step back through each of them, checking that the
+    /// machine is put back as it was before each one
+    fn check_step_back(chip8: &mut Chip8, cycles: usize) {
+        let mut states = Vec::new();
+        for _ in 0..cycles {
+            states.push(state(chip8));
+            chip8.do_next_instruction().expect("the instruction should run");
+        }
+
+        for (step, before) in states.iter().enumerate().rev() {
+            chip8.step_back().expect("the instruction should be undone");
+            assert!(state(chip8) == *before, "instruction {} was not undone", step);
+        }
+        assert!(matches!(chip8.step_back(), Err(Chip8Error::NoHistory)));
+    }
+
+    /// Get the number of bytes kept by the history
+    fn history_size(chip8: &Chip8) -> usize {
+        let history = chip8.history.as_ref().expect("history should be enabled");
+        let delta_size = |delta: &StateDelta| {
+            let vram = match &delta.vram {
+                PixelFlips::Listed(flips) => flips.capacity() * mem::size_of::<u16>(),
+                PixelFlips::Mask(mask) => mem::size_of_val(&**mask),
+            };
+            let ram = delta.ram.capacity() * mem::size_of::<(usize, u8)>();
+            mem::size_of::<StateDelta>() + ram + vram
+        };
+        history.deltas.iter().map(delta_size).sum()
+    }
+
+    /// Make an XO-CHIP ROM that keeps drawing a checkerboard to two planes in high resolution and
+    /// scrolling it, so that most instructions flip many pixels
+    fn busy_display_rom() -> Vec<u8> {
+        let mut rom = vec![
+            0x00, 0xFF, // high resolution
+            0xF3, 0x01, // draw to planes 1 and 2
+            0xA3, 0x00, // I = 0x300
+            0xD1, 0x20, // draw a 16x16 sprite at (V1, V2)
+            0x71, 0x13, // V1 += 0x13
+            0x72, 0x07, // V2 += 0x07
+            0x00, 0xC1, // scroll down 1
+            0x00, 0xFB, // scroll right 4
+            0x12, 0x06, // jump back to the draw
+        ];
+        rom.resize(0x100, 0x00);
+
+        // a 16x16 checkerboard for each plane
+        rom.extend((0..0x40).map(|byte| if byte / 2 % 2 == 0 { 0xAA } else { 0x55 }));
+        rom
+    }
+
+    #[test]
+    fn step_back_without_history() {
+        let mut chip8 = Chip8Builder::default()
+            .rom_bytes(&[0x60, 0x01])
+            .build()
+            .expect("the test ROM should load");
+        chip8.do_next_instruction().expect("the instruction should run");
+        assert!(matches!(chip8.step_back(), Err(Chip8Error::NoHistory)));
+    }
+
+    #[test]
+    fn step_back_through_draws() {
+        // I = 0x20A, V0 = 0x3E, then draw a one row sprite at (V0, V1) twice, and loop forever
+        let rom = [0xA2, 0x0A, 0x60, 0x3E, 0xD0, 0x11, 0xD0, 0x11, 0x12, 0x08, 0xF0];
+        check_step_back(&mut load(&rom, EmulationMode::Chip8), 6);
+    }
+
+    #[test]
+    fn step_back_through_scrolls_and_clears() {
+        // I = 0x20E, draw a one row sprite at (0, 0), scroll down 2, right 4, clear, then switch
+        // to high resolution
+        let rom = [
+            0xA2, 0x0E, 0xD0, 0x11, 0x00, 0xC2, 0x00, 0xFB, 0x00, 0xE0, 0x00, 0xFF, 0x12, 0x00,
+            0xF0,
+        ];
+        check_step_back(&mut load(&rom, EmulationMode::SuperChip), 14);
+    }
+
+    #[test]
+    fn step_back_through_memory_and_stack_writes() {
+        // V0 = 0x12, I = 0x300, store V0 at I, call 0x20A, then return to loop forever
+        let rom = [0x60, 0x12, 0xA3, 0x00, 0xF0, 0x55, 0x22, 0x0A, 0x12, 0x08, 0x00, 0xEE];
+        check_step_back(&mut load(&rom, EmulationMode::Chip8), 8);
+    }
+
+    #[test]
+    fn step_back_through_a_busy_display() {
+        let mut chip8 = load(&busy_display_rom(), EmulationMode::XoChip);
+        for _ in 0..1000 {
+            chip8.do_next_instruction().expect("the instruction should run");
+        }
+        check_step_back(&mut chip8, DEFAULT_HISTORY_DEPTH);
+    }
+
+    #[test]
+    fn history_of_a_busy_display_stays_small() {
+        let mut chip8 = load(&busy_display_rom(), EmulationMode::XoChip);
+        for _ in 0..5000 {
+            chip8.do_next_instruction().expect("the instruction should run");
+        }
+
+        let history = chip8.history.as_ref().expect("history should be enabled");
+        assert_eq!(history.deltas.len(), DEFAULT_HISTORY_DEPTH);
+        assert!(history
+            .deltas
+            .iter()
+            .any(|delta| matches!(delta.vram, PixelFlips::Mask(_))));
+        let size = history_size(&chip8);
+        assert!(size < 5 * 1024 * 1024, "{} bytes", size);
+    }
+}
@@ -0,0 +1,50 @@
+use crate::chip8::{Chip8, FrameEvents, Vram};
+use crate::error::EmulatorError;
+use crate::instructions::Instruction;
+
+/// Watches the emulator as it runs, for front-ends that need to see each instruction or frame
+/// Every method does nothing by default, so only the ones that are needed have to be written.
+pub trait Chip8Inspector {
+    /// Called just before an instruction is executed
+    fn on_instruction(&mut self, _pc: u16, _opcode: u16, _instruction: &Instruction) {}
+
+    /// Called when an instruction fails
+    fn on_error(&mut self, _error: &EmulatorError) {}
+
+    /// Called with the display at the end of each frame
+    fn on_frame(&mut self, _vram: &Vram) {}
+}
+
+impl Chip8 {
+    /// Do the next instruction, telling the inspector about it
+    /// Behaves exactly like `do_next_instruction` otherwise.
+    pub fn step_with_inspector(
+        &mut self,
+        inspector: &mut impl Chip8Inspector,
+    ) -> Result<u16, EmulatorError> {
+        // an instruction that can't be read fails before it could be inspected
+        let opcode = self.get_current_opcode();
+        let instruction = self.get_current_instruction();
+        if let (Ok(opcode), Ok(instruction)) = (opcode, instruction) {
+            inspector.on_instruction(self.registers.pc, opcode, &instruction);
+        }
+
+        let result = self.do_next_instruction();
+        if let Err(e) = &result {
+            inspector.on_error(e);
+        }
+
+        result
+    }
+
+    /// Finish a frame, showing the display to the inspector
+    /// Behaves exactly like `do_frame` otherwise.
+    pub fn do_frame_with_inspector(
+        &mut self,
+        inspector: &mut impl Chip8Inspector,
+    ) -> FrameEvents<'_> {
+        let events = self.do_frame();
+        inspector.on_frame(events.vram);
+        events
+    }
+}
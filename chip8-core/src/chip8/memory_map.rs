@@ -0,0 +1,93 @@
+use crate::chip8::{Chip8, MEM_SIZE};
+use core::fmt;
+use core::ops::Range;
+
+/// What a part of memory is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryRegion {
+    /// Sprites for the hex digits, in the small or large font
+    FontData,
+
+    /// The ROM, as it was loaded
+    ProgramCode,
+
+    /// Memory after the ROM, which the program is free to use
+    ProgramData,
+
+    /// Memory that nothing was loaded into
+    Unmapped,
+}
+
+impl fmt::Display for MemoryRegion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MemoryRegion::FontData => write!(f, "font region"),
+            MemoryRegion::ProgramCode => write!(f, "program region"),
+            MemoryRegion::ProgramData => write!(f, "program data region"),
+            MemoryRegion::Unmapped => write!(f, "unmapped memory"),
+        }
+    }
+}
+
+/// The regions of memory that the font and ROM were loaded into
+/// The stack is kept outside of RAM, so it has no region.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MemoryMap {
+    /// The addresses of the small font
+    font: Range<u16>,
+
+    /// The addresses of the large font, if the emulation mode has one
+    large_font: Range<u16>,
+
+    /// The addresses of the ROM
+    program: Range<u16>,
+}
+
+impl MemoryMap {
+    /// Get the region an address is in
+    pub fn region_at(&self, addr: u16) -> MemoryRegion {
+        let after_program = !self.program.is_empty() && addr >= self.program.end;
+
+        if self.program.contains(&addr) {
+            MemoryRegion::ProgramCode
+        } else if self.font.contains(&addr) || self.large_font.contains(&addr) {
+            MemoryRegion::FontData
+        } else if after_program && (addr as usize) < MEM_SIZE {
+            MemoryRegion::ProgramData
+        } else {
+            MemoryRegion::Unmapped
+        }
+    }
+
+    /// Get the size of the small font
+    pub(super) fn font_len(&self) -> usize {
+        self.font.len()
+    }
+
+    /// Get the size of the ROM
+    pub(super) fn program_len(&self) -> usize {
+        self.program.len()
+    }
+
+    /// Record where the small font was loaded
+    pub(super) fn set_font(&mut self, start: u16, len: usize) {
+        self.font = start..start + len as u16;
+    }
+
+    /// Record where the large font was loaded
+    pub(super) fn set_large_font(&mut self, start: u16, len: usize) {
+        self.large_font = start..start + len as u16;
+    }
+
+    /// Record where the ROM was loaded
+    pub(super) fn set_program(&mut self, start: u16, len: usize) {
+        self.program = start..start + len as u16;
+    }
+}
+
+impl Chip8 {
+    /// Get the regions of memory that the font and ROM were loaded into
+    pub fn memory_map(&self) -> &MemoryMap {
+        &self.memory_map
+    }
+}
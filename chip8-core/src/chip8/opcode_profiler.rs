@@ -0,0 +1,106 @@
+use crate::chip8::Chip8;
+#[cfg(feature = "std")]
+use crate::instructions::Instruction;
+use alloc::boxed::Box;
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+// the number of possible opcodes
+const OPCODE_COUNT: usize = 0x10000;
+
+// the number of opcodes listed in a report
+#[cfg(feature = "std")]
+const REPORT_LENGTH: usize = 20;
+
+/// Counts how many times each opcode is executed
+/// Used to find the hot paths in a ROM.
+#[derive(Clone)]
+pub struct OpcodeProfiler {
+    /// The number of times each opcode was executed, indexed by opcode
+    /// This is boxed, as half a megabyte is too much to put on the stack on some targets.
+    hits: Box<[u64; OPCODE_COUNT]>,
+}
+
+impl Default for OpcodeProfiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OpcodeProfiler {
+    /// Make a profiler with every count at zero
+    pub fn new() -> Self {
+        let hits: Box<[u64]> = vec![0; OPCODE_COUNT].into_boxed_slice();
+        Self {
+            hits: hits.try_into().expect("hit counts should have one entry per opcode"),
+        }
+    }
+
+    /// Count an execution of an opcode
+    pub fn record(&mut self, opcode: u16) {
+        self.hits[opcode as usize] += 1;
+    }
+
+    /// Get the total number of opcodes executed
+    pub fn total(&self) -> u64 {
+        self.hits.iter().sum()
+    }
+
+    /// Get the `n` most executed opcodes with their counts, most executed first
+    /// Opcodes that were never executed are left out.
+    pub fn top_n(&self, n: usize) -> Vec<(u16, u64)> {
+        let mut counts: Vec<(u16, u64)> = self
+            .hits
+            .iter()
+            .enumerate()
+            .filter(|(_, &count)| count > 0)
+            .map(|(opcode, &count)| (opcode as u16, count))
+            .collect();
+
+        // ties are broken by opcode, so the report is the same every run
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+        counts.truncate(n);
+        counts
+    }
+
+    /// Write a table of the most executed opcodes, with their counts and share of all executions
+    #[cfg(feature = "std")]
+    pub fn report_to_writer(&self, w: &mut impl Write) -> io::Result<()> {
+        let total = self.total();
+        writeln!(w, "{:<8}{:<20}{:>12}{:>9}", "opcode", "instruction", "count", "%")?;
+        for (opcode, count) in self.top_n(REPORT_LENGTH) {
+            writeln!(
+                w,
+                "{:<8}{:<20}{:>12}{:>8.2}%",
+                format!("{:04X}", opcode),
+                Instruction::decode(opcode).to_string(),
+                count,
+                count as f64 * 100.0 / total as f64
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Chip8 {
+    /// Count how many times each opcode is executed
+    pub fn with_opcode_profiler(mut self) -> Self {
+        self.profiler = Some(OpcodeProfiler::new());
+        self
+    }
+
+    /// Get the opcode counts, if profiling was turned on with `with_opcode_profiler`
+    pub fn opcode_profiler(&self) -> Option<&OpcodeProfiler> {
+        self.profiler.as_ref()
+    }
+
+    /// Count an execution of an opcode, if profiling is on
+    pub(super) fn record_opcode(&mut self, opcode: u16) {
+        if let Some(profiler) = &mut self.profiler {
+            profiler.record(opcode);
+        }
+    }
+}
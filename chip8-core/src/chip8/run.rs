@@ -0,0 +1,272 @@
+use crate::chip8::{Chip8, Vram};
+use crate::error::{EmulatorError, RunUntilError};
+use alloc::vec::Vec;
+use log::{log_enabled, Level};
+
+// the width and height of the sprites looked for by `run_until_vram_contains`
+const PATTERN_WIDTH: usize = 8;
+const PATTERN_HEIGHT: usize = 5;
+
+/// What happened over a run of frames
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrameLog {
+    /// The result of every instruction run, in order
+    pub results: Vec<Result<u16, EmulatorError>>,
+
+    /// The number of frames run
+    pub frames: u32,
+
+    /// The number of frames in which the display changed
+    pub display_updates: u32,
+
+    /// The number of frames in which the sound timer ran out
+    pub sound_timer_expirations: u32,
+
+    /// The number of frames in which the delay timer ran out
+    pub delay_timer_expirations: u32,
+
+    /// The display at the end of the last frame, or as last shown if no frames were run
+    pub vram: Vram,
+}
+
+/// What happened over a batch of instructions run by `bulk_do_instructions`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BulkResult {
+    /// The number of instructions run without an error
+    pub executed: u32,
+
+    /// The error that stopped the batch, if any
+    /// This is empty in normal operation.
+    pub errors: Vec<EmulatorError>,
+
+    /// The number of instructions run since the program was loaded, as of the end of the batch
+    pub total_cycles: u64,
+}
+
+impl Chip8 {
+    /// Run a batch of instructions, stopping early if one fails or the program exits
+    /// When nothing is tracing, profiling or keeping history, the instructions are run straight
+    /// from their opcodes, which is much quicker than calling `do_next_instruction` for each.
+    pub fn bulk_do_instructions(&mut self, n: u32) -> BulkResult {
+        let mut result = BulkResult {
+            executed: 0,
+            errors: Vec::new(),
+            total_cycles: self.total_cycles,
+        };
+
+        // with nothing recording the instructions, they can be run straight from their opcodes
+        let recording = self.history.is_some()
+            || self.trace.is_some()
+            || self.profiler.is_some()
+            || log_enabled!(Level::Trace);
+        while result.executed < n && !self.exited {
+            let step = if recording {
+                self.do_next_instruction().map(|_| ())
+            } else {
+                self.execute_unrecorded_instruction()
+            };
+
+            if let Err(e) = step {
+                result.errors.push(e);
+                break;
+            }
+            result.executed += 1;
+        }
+
+        result.total_cycles = self.total_cycles;
+        result
+    }
+
+    /// Run the next instruction, without keeping anything to trace, profile or undo it
+    fn execute_unrecorded_instruction(&mut self) -> Result<(), EmulatorError> {
+        self.watchpoint_hit = None;
+        let opcode = self.get_current_opcode()?;
+        self.record_coverage();
+        self.dispatch(opcode)?;
+
+        // point the PC to the next instruction, as `execute_next_instruction` does
+        self.registers.pc = self.registers.pc.wrapping_add(2);
+        self.total_cycles += 1;
+        self.count_down_on_instruction();
+        Ok(())
+    }
+
+    /// Run a number of instructions, returning the result of each
+    /// Errors don't stop the run, so every instruction after a failure is tried as well.
+    pub fn run_for_cycles(&mut self, n: u32) -> Vec<Result<u16, EmulatorError>> {
+        (0..n).map(|_| self.do_next_instruction()).collect()
+    }
+
+    /// Run a number of frames, each of which runs the instructions for a frame and then counts
+    /// down the timers
+    /// Like `run_for_cycles`, errors don't stop the run.
+    pub fn run_for_frames(&mut self, n: u32) -> FrameLog {
+        let mut log = FrameLog {
+            results: Vec::with_capacity(n.saturating_mul(self.cycles_per_frame) as usize),
+            frames: n,
+            display_updates: 0,
+            sound_timer_expirations: 0,
+            delay_timer_expirations: 0,
+            vram: self.memory.frame,
+        };
+
+        for _ in 0..n {
+            let results = self.run_for_cycles(self.cycles_per_frame);
+            log.results.extend(results);
+
+            let events = self.do_frame();
+            log.display_updates += events.display_updated as u32;
+            log.sound_timer_expirations += events.sound_timer_expired as u32;
+            log.delay_timer_expirations += events.delay_timer_expired as u32;
+            log.vram = *events.vram;
+        }
+
+        log
+    }
+
+    /// Run instructions until the program counter reaches an address, returning the number of
+    /// instructions run
+    /// Only instructions are run, so the timers don't count down while waiting.
+    pub fn run_until_pc(&mut self, target: u16, max_cycles: u32) -> Result<u32, RunUntilError> {
+        self.run_until(max_cycles, |chip8| chip8.registers.pc == target)
+    }
+
+    /// Run instructions until a sprite has been drawn at a point on the display, returning the
+    /// number of instructions run
+    /// Each row of the pattern is 8 pixels, and the pixels under it must match it exactly.
+    /// Pixels past the edges of the display count as off.
+    pub fn run_until_vram_contains(
+        &mut self,
+        pattern: &[[bool; PATTERN_WIDTH]; PATTERN_HEIGHT],
+        x: usize,
+        y: usize,
+        max_cycles: u32,
+    ) -> Result<u32, RunUntilError> {
+        self.run_until(max_cycles, |chip8| chip8.vram_contains(pattern, x, y))
+    }
+
+    /// Run instructions until a condition holds, which is checked before each one
+    fn run_until(
+        &mut self,
+        max_cycles: u32,
+        done: impl Fn(&Chip8) -> bool,
+    ) -> Result<u32, RunUntilError> {
+        for cycles in 0..max_cycles {
+            if done(self) {
+                return Ok(cycles);
+            }
+            self.do_next_instruction()?;
+        }
+
+        if done(self) {
+            Ok(max_cycles)
+        } else {
+            Err(RunUntilError::MaxCyclesExceeded)
+        }
+    }
+
+    /// Check whether the drawn display matches a pattern at a point
+    fn vram_contains(
+        &self,
+        pattern: &[[bool; PATTERN_WIDTH]; PATTERN_HEIGHT],
+        x: usize,
+        y: usize,
+    ) -> bool {
+        let (width, height) = self.display_size();
+        pattern.iter().enumerate().all(|(dy, row)| {
+            row.iter().enumerate().all(|(dx, &on)| {
+                let (px, py) = (x + dx, y + dy);
+                let drawn = px < width
+                    && py < height
+                    && self.memory.back_vram.iter().any(|plane| plane[py][px]);
+                drawn == on
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chip8::{Chip8, Chip8Builder, EmulationMode, DEFAULT_HISTORY_DEPTH};
+    use crate::error::EmulatorError;
+
+    // clear the display, then draw a digit at a random point forever
+    const ROM: [u8; 12] = [
+        0x00, 0xE0, 0x70, 0x01, 0xF0, 0x29, 0xD1, 0x15, 0xC1, 0x0F, 0x12, 0x02,
+    ];
+
+    /// Make a machine with a ROM loaded, with a fixed seed
+    fn load(rom: &[u8]) -> Chip8 {
+        Chip8Builder::default()
+            .mode(EmulationMode::Chip8)
+            .seed(1)
+            .rom_bytes(rom)
+            .build()
+            .expect("the test ROM should load")
+    }
+
+    #[test]
+    fn bulk_matches_running_one_instruction_at_a_time() {
+        let mut bulk = load(&ROM);
+        let mut stepped = load(&ROM);
+
+        let result = bulk.bulk_do_instructions(500);
+        for _ in 0..500 {
+            stepped.do_next_instruction().expect("the instruction should run");
+        }
+
+        assert_eq!(result.executed, 500);
+        assert!(result.errors.is_empty());
+        assert_eq!(result.total_cycles, 500);
+        assert!(bulk == stepped);
+    }
+
+    #[test]
+    fn bulk_stops_at_an_error() {
+        // add to V0 twice, then an opcode that isn't an instruction
+        let mut chip8 = load(&[0x70, 0x01, 0x70, 0x01, 0xFF, 0xFF, 0x70, 0x01]);
+
+        let result = chip8.bulk_do_instructions(10);
+        assert_eq!(result.executed, 2);
+        assert_eq!(result.total_cycles, 2);
+        assert!(matches!(
+            result.errors[..],
+            [EmulatorError::UnknownOpcode { opcode: 0xFFFF, .. }]
+        ));
+        assert_eq!(chip8.snapshot_registers().v[0], 2);
+    }
+
+    #[test]
+    fn bulk_keeps_history_when_it_is_enabled() {
+        let mut chip8 = load(&ROM);
+        chip8.enable_history(DEFAULT_HISTORY_DEPTH);
+
+        let before = chip8.snapshot_registers();
+        chip8.bulk_do_instructions(5);
+        for _ in 0..5 {
+            chip8.step_back().expect("each instruction should be undone");
+        }
+        assert_eq!(chip8.snapshot_registers(), before);
+    }
+
+
+    #[test]
+    fn one_cycle_per_frame_runs_one_instruction_each_frame() {
+        let mut chip8 = Chip8Builder::default()
+            .cycles_per_frame(1)
+            .rom_bytes(&ROM)
+            .build()
+            .expect("the test ROM should load");
+
+        for frame in 1..=5 {
+            let log = chip8.run_for_frames(1);
+            assert_eq!(log.results.len(), 1);
+            assert_eq!(chip8.total_cycles(), frame);
+        }
+
+        // the emulator runs each frame's instructions as a batch
+        let result = chip8.bulk_do_instructions(chip8.cycles_per_frame());
+        assert_eq!(result.executed, 1);
+        assert_eq!(result.total_cycles, 6);
+    }
+}
@@ -0,0 +1,414 @@
+use crate::chip8::{
+    Chip8, EmulationMode, AUDIO_PATTERN_SIZE, FONTMEM_START, HIRES_HEIGHT, HIRES_WIDTH,
+    LARGE_FONT, LARGE_FONTMEM_START, MEM_SIZE, PLANE_COUNT, PROGMEM_START, STACK_SIZE,
+};
+use alloc::vec::Vec;
+use core::fmt;
+use rand::SeedableRng;
+use rand_chacha::ChaCha12Rng;
+
+/// The bytes at the start of every save state
+const MAGIC: &[u8] = b"CHIP8SAVE";
+
+/// The version of the save state format
+const VERSION: u8 = 5;
+
+/// Errors that can occur while loading a save state
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SaveStateError {
+    /// The data does not start with the save state header
+    BadMagic,
+
+    /// The data was saved in a format this version cannot read
+    UnsupportedVersion(u8),
+
+    /// The data ended before the whole state was read
+    TruncatedData,
+
+    /// A value in the data is too large for the machine, so the data must be corrupt
+    OutOfRange {
+        /// What the value is, such as `program length`
+        field: &'static str,
+
+        /// The value that was read
+        value: usize,
+    },
+}
+
+impl fmt::Display for SaveStateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveStateError::BadMagic => write!(f, "not a CHIP-8 save state"),
+            SaveStateError::UnsupportedVersion(version) => {
+                write!(f, "unsupported save state version {}", version)
+            }
+            SaveStateError::TruncatedData => write!(f, "save state is truncated"),
+            SaveStateError::OutOfRange { field, value } => {
+                write!(f, "save state has an out of range {} of {:#x}", field, value)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SaveStateError {}
+
+/// Reads values out of a save state in order
+struct StateReader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> StateReader<'a> {
+    /// Take the next n bytes
+    fn take(&mut self, n: usize) -> Result<&'a [u8], SaveStateError> {
+        if self.bytes.len() < n {
+            return Err(SaveStateError::TruncatedData);
+        }
+
+        let (taken, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    /// Take the next byte
+    fn u8(&mut self) -> Result<u8, SaveStateError> {
+        Ok(self.take(1)?[0])
+    }
+
+    /// Take the next two bytes as a big-endian value
+    fn u16(&mut self) -> Result<u16, SaveStateError> {
+        let bytes = self.take(2)?;
+        Ok(u16::from_be_bytes([bytes[0], bytes[1]]))
+    }
+
+    /// Take the next N bytes as an array
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], SaveStateError> {
+        let mut array = [0; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    /// Take the next byte as a flag
+    fn bool(&mut self) -> Result<bool, SaveStateError> {
+        Ok(self.u8()? != 0)
+    }
+}
+
+/// Check that a value read from a save state is no more than its maximum
+fn check_range(field: &'static str, value: usize, max: usize) -> Result<(), SaveStateError> {
+    if value > max {
+        return Err(SaveStateError::OutOfRange { field, value });
+    }
+    Ok(())
+}
+
+impl Chip8 {
+    /// Serialize the full state of the emulator
+    /// The format is the magic header and version, followed by the registers, RAM, stack,
+    /// bit-packed VRAM, input state, timing, quirks, random number generator, and the sizes of
+    /// the font and ROM.
+    pub fn save_state(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = Vec::new();
+
+        // header
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+
+        // emulator configuration
+        bytes.push(match self.mode {
+            EmulationMode::Chip8 => 0,
+            EmulationMode::SuperChip => 1,
+            EmulationMode::XoChip => 2,
+        });
+        bytes.push(self.hires as u8);
+        bytes.push(self.planes);
+        bytes.push(self.exited as u8);
+
+        // registers
+        bytes.extend_from_slice(&self.registers.v);
+        bytes.push(self.registers.dt);
+        bytes.push(self.registers.st);
+        bytes.extend_from_slice(&self.registers.i.to_be_bytes());
+        bytes.extend_from_slice(&self.registers.pc.to_be_bytes());
+        bytes.extend_from_slice(&self.registers.sp.to_be_bytes());
+        bytes.extend_from_slice(&self.registers.flags);
+        bytes.push(self.registers.pitch);
+
+        // memory
+        bytes.extend_from_slice(&self.memory.ram);
+        for addr in self.memory.stack {
+            bytes.extend_from_slice(&addr.to_be_bytes());
+        }
+        for plane in &self.memory.back_vram {
+            for row in plane {
+                // pack each row 8 pixels to a byte
+                for pixels in row.chunks(8) {
+                    bytes.push(
+                        pixels
+                            .iter()
+                            .fold(0, |byte, &pixel| (byte << 1) | pixel as u8),
+                    );
+                }
+            }
+        }
+        bytes.extend_from_slice(&self.memory.audio_pattern);
+
+        // input
+        bytes.extend_from_slice(&self.input.curr.to_be_bytes());
+        bytes.push(self.input.waiting_for_key.is_some() as u8);
+        bytes.push(self.input.waiting_for_key.unwrap_or(0) as u8);
+        bytes.extend_from_slice(&self.input.pressed_while_waiting.to_be_bytes());
+
+        // timing
+        bytes.extend_from_slice(&self.total_cycles.to_be_bytes());
+        bytes.extend_from_slice(&self.total_frames.to_be_bytes());
+        bytes.extend_from_slice(&self.timer_accumulator.to_be_bytes());
+
+        // quirks
+        bytes.push(self.shift_quirk as u8);
+        bytes.push(self.load_store_quirk as u8);
+        bytes.push(self.load_store_by_x_quirk as u8);
+        bytes.push(self.jump0_quirk as u8);
+        bytes.push(self.vblank_quirk as u8);
+        bytes.push(self.add_index_overflow_quirk as u8);
+
+        // the random number generator, so CXNN carries on from where it was
+        bytes.extend_from_slice(&self.rng.get_seed());
+        bytes.extend_from_slice(&self.rng.get_word_pos().to_be_bytes());
+
+        // sizes of the font and ROM, for the memory map
+        bytes.extend_from_slice(&(self.memory_map.font_len() as u16).to_be_bytes());
+        bytes.extend_from_slice(&(self.memory_map.program_len() as u16).to_be_bytes());
+
+        bytes
+    }
+
+    /// Make a new Chip8 from a serialized state
+    pub fn load_state(bytes: &[u8]) -> Result<Chip8, SaveStateError> {
+        let mut reader = StateReader { bytes };
+
+        // header
+        if reader.take(MAGIC.len()).ok() != Some(MAGIC) {
+            return Err(SaveStateError::BadMagic);
+        }
+        let version = reader.u8()?;
+        if version != VERSION {
+            return Err(SaveStateError::UnsupportedVersion(version));
+        }
+
+        // emulator configuration
+        let mut chip8 = Chip8::new();
+        chip8.mode = match reader.u8()? {
+            1 => EmulationMode::SuperChip,
+            2 => EmulationMode::XoChip,
+            _ => EmulationMode::Chip8,
+        };
+        chip8.hires = reader.bool()?;
+        chip8.planes = reader.u8()?;
+        chip8.exited = reader.bool()?;
+
+        // registers
+        chip8.registers.v.copy_from_slice(reader.take(16)?);
+        chip8.registers.dt = reader.u8()?;
+        chip8.registers.st = reader.u8()?;
+        chip8.registers.i = reader.u16()?;
+        chip8.registers.pc = reader.u16()?;
+        chip8.registers.sp = reader.u16()?;
+        // a corrupt state must not point the machine past the end of memory or the stack
+        check_range("index register", chip8.registers.i as usize, MEM_SIZE - 1)?;
+        check_range("program counter", chip8.registers.pc as usize, MEM_SIZE - 1)?;
+        check_range("stack pointer", chip8.registers.sp as usize, STACK_SIZE)?;
+        chip8.registers.flags.copy_from_slice(reader.take(16)?);
+        chip8.registers.pitch = reader.u8()?;
+
+        // memory
+        chip8.memory.ram.copy_from_slice(reader.take(MEM_SIZE)?);
+        for addr in chip8.memory.stack.iter_mut() {
+            *addr = reader.u16()?;
+        }
+        for plane in 0..PLANE_COUNT {
+            for y in 0..HIRES_HEIGHT {
+                let packed = reader.take(HIRES_WIDTH / 8)?;
+                for x in 0..HIRES_WIDTH {
+                    chip8.memory.back_vram[plane][y][x] = packed[x / 8] & (0x1 << (7 - x % 8)) > 0;
+                }
+            }
+        }
+        chip8
+            .memory
+            .audio_pattern
+            .copy_from_slice(reader.take(AUDIO_PATTERN_SIZE)?);
+
+        // input
+        chip8.input.curr = reader.u16()?;
+        let waiting = reader.bool()?;
+        let reg = reader.u8()? as usize & 0xF;
+        chip8.input.waiting_for_key = waiting.then_some(reg);
+        chip8.input.pressed_while_waiting = reader.u16()?;
+
+        // timing
+        chip8.total_cycles = u64::from_be_bytes(reader.array()?);
+        chip8.total_frames = u64::from_be_bytes(reader.array()?);
+        chip8.timer_accumulator = f32::from_be_bytes(reader.array()?);
+
+        // quirks
+        chip8.shift_quirk = reader.bool()?;
+        chip8.load_store_quirk = reader.bool()?;
+        chip8.load_store_by_x_quirk = reader.bool()?;
+        chip8.jump0_quirk = reader.bool()?;
+        chip8.vblank_quirk = reader.bool()?;
+        chip8.add_index_overflow_quirk = reader.bool()?;
+
+        // the random number generator
+        chip8.rng = ChaCha12Rng::from_seed(reader.array()?);
+        chip8.rng.set_word_pos(u128::from_be_bytes(reader.array()?));
+
+        // memory map
+        let font_len = reader.u16()? as usize;
+        check_range("font length", font_len, (PROGMEM_START - FONTMEM_START) as usize)?;
+        chip8.memory_map.set_font(FONTMEM_START, font_len);
+        let program_len = reader.u16()? as usize;
+        check_range("program length", program_len, MEM_SIZE - PROGMEM_START as usize)?;
+        chip8.memory_map.set_program(PROGMEM_START, program_len);
+        if chip8.mode != EmulationMode::Chip8 {
+            chip8.memory_map.set_large_font(LARGE_FONTMEM_START, LARGE_FONT.len());
+        }
+
+        // show the loaded display straight away, rather than after the next frame
+        chip8.present_display();
+
+        Ok(chip8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SaveStateError, MAGIC};
+    use crate::chip8::{Chip8, Chip8Builder, STACK_SIZE};
+    use alloc::vec::Vec;
+
+    /// Save the state of a machine that has run a short ROM
+    fn saved_state() -> Vec<u8> {
+        // V0 = 0x42, I = 0x300, then loop forever
+        let mut chip8 = Chip8Builder::default()
+            .rom_bytes(&[0x60, 0x42, 0xA3, 0x00, 0x12, 0x04])
+            .build()
+            .expect("the test ROM should load");
+        for _ in 0..3 {
+            chip8.do_next_instruction().expect("the instruction should run");
+        }
+        chip8.save_state()
+    }
+
+    #[test]
+    fn load_a_saved_state() {
+        let chip8 = Chip8::load_state(&saved_state()).expect("the state should load");
+        let registers = chip8.snapshot_registers();
+        assert_eq!(registers.v[0x0], 0x42);
+        assert_eq!(registers.i, 0x300);
+        assert_eq!(registers.pc, 0x204);
+        assert_eq!(chip8.memory_map().program_len(), 6);
+    }
+
+    #[test]
+    fn reject_a_font_longer_than_the_font_region() {
+        let mut bytes = saved_state();
+        let end = bytes.len();
+        bytes[end - 4..end - 2].copy_from_slice(&0x0201_u16.to_be_bytes());
+        assert_eq!(
+            Chip8::load_state(&bytes).err(),
+            Some(SaveStateError::OutOfRange {
+                field: "font length",
+                value: 0x201
+            })
+        );
+    }
+
+    #[test]
+    fn reject_a_program_longer_than_program_memory() {
+        let mut bytes = saved_state();
+        let end = bytes.len();
+        bytes[end - 2..].copy_from_slice(&0xFFFF_u16.to_be_bytes());
+        assert_eq!(
+            Chip8::load_state(&bytes).err(),
+            Some(SaveStateError::OutOfRange {
+                field: "program length",
+                value: 0xFFFF
+            })
+        );
+    }
+
+    /// Overwrite one of the 16-bit registers in a saved state, at its offset after the header
+    fn set_register(bytes: &mut [u8], offset: usize, value: u16) {
+        // the header, the 4 configuration bytes, V0 to VF, DT, and ST come first
+        let start = MAGIC.len() + 1 + 4 + 16 + 2 + offset;
+        bytes[start..start + 2].copy_from_slice(&value.to_be_bytes());
+    }
+
+    #[test]
+    fn reject_registers_past_the_end_of_memory_or_the_stack() {
+        let cases = [
+            (0, "index register", 0x1000),
+            (2, "program counter", 0x1000),
+            (4, "stack pointer", STACK_SIZE as u16 + 1),
+        ];
+        for (offset, field, value) in cases {
+            let mut bytes = saved_state();
+            set_register(&mut bytes, offset, value);
+            assert_eq!(
+                Chip8::load_state(&bytes).err(),
+                Some(SaveStateError::OutOfRange {
+                    field,
+                    value: value as usize
+                })
+            );
+
+            // the largest value that is in range still loads
+            set_register(&mut bytes, offset, value - 1);
+            assert!(Chip8::load_state(&bytes).is_ok(), "{}", field);
+        }
+    }
+
+
+    #[test]
+    fn a_loaded_state_carries_on_with_the_same_random_numbers() {
+        // V0 = a random byte, forever
+        let mut chip8 = Chip8Builder::default()
+            .rom_bytes(&[0xC0, 0xFF, 0x12, 0x00])
+            .seed(7)
+            .build()
+            .expect("the test ROM should load");
+        for _ in 0..10 {
+            chip8.do_next_instruction().expect("the instruction should run");
+        }
+        let mut loaded = Chip8::load_state(&chip8.save_state()).expect("the state should load");
+        assert_eq!(loaded.total_cycles(), 10);
+
+        for _ in 0..20 {
+            chip8.do_next_instruction().expect("the instruction should run");
+            loaded.do_next_instruction().expect("the instruction should run");
+            assert_eq!(loaded.snapshot_registers(), chip8.snapshot_registers());
+        }
+    }
+
+    #[test]
+    fn a_loaded_state_keeps_its_quirks_and_timing() {
+        let mut chip8 = Chip8Builder::default()
+            .rom_bytes(&[0x12, 0x00])
+            .shift_quirk(true)
+            .load_store_by_x_quirk(true)
+            .jump0_quirk(true)
+            .add_index_overflow_quirk(true)
+            .build()
+            .expect("the test ROM should load");
+        chip8.do_next_instruction().expect("the instruction should run");
+        chip8.do_frame();
+
+        let loaded = Chip8::load_state(&chip8.save_state()).expect("the state should load");
+        assert!(loaded.shift_quirk && loaded.load_store_by_x_quirk && loaded.jump0_quirk);
+        assert!(loaded.add_index_overflow_quirk);
+        assert!(!loaded.load_store_quirk && !loaded.vblank_quirk);
+        assert_eq!(loaded.total_frames(), 1);
+        assert_eq!(loaded.timer_accumulator, chip8.timer_accumulator);
+    }
+}
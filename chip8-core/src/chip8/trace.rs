@@ -0,0 +1,182 @@
+use crate::chip8::Chip8;
+use crate::instructions::Instruction;
+use alloc::vec::Vec;
+
+/// The default number of instructions kept in an execution trace
+pub const DEFAULT_TRACE_DEPTH: usize = 256;
+
+/// A single executed instruction
+#[derive(Debug, Clone)]
+pub struct TraceEntry {
+    /// The address of the instruction
+    pub pc: u16,
+
+    /// The raw opcode
+    pub opcode: u16,
+
+    /// The decoded instruction
+    pub instruction: Instruction,
+
+    /// The general purpose registers just before the instruction was executed
+    pub v_before: [u8; 16],
+
+    /// The general purpose registers just after the instruction was executed
+    /// If the instruction failed, these are the registers as it left them.
+    pub v_after: [u8; 16],
+
+    /// The number of instructions run before this one
+    pub cycle: u64,
+}
+
+/// A record of the last few instructions executed
+/// Entries are kept in order, oldest first. Old entries are dropped in batches, so the buffer
+/// never holds more than twice the depth.
+#[derive(Clone)]
+pub struct ExecutionTrace {
+    entries: Vec<TraceEntry>,
+    depth: usize,
+}
+
+impl ExecutionTrace {
+    /// Make an empty trace that keeps the last `depth` instructions
+    pub fn new(depth: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(depth * 2),
+            depth,
+        }
+    }
+
+    /// Add an instruction to the trace
+    fn record(&mut self, entry: TraceEntry) {
+        // nothing is kept, so don't let the buffer grow
+        if self.depth == 0 {
+            return;
+        }
+
+        if self.entries.len() >= self.depth * 2 {
+            self.entries.drain(..self.depth);
+        }
+        self.entries.push(entry);
+    }
+
+    /// Get the last `depth` instructions, oldest first
+    fn entries(&self) -> &[TraceEntry] {
+        &self.entries[self.entries.len().saturating_sub(self.depth)..]
+    }
+}
+
+impl Chip8 {
+    /// Record every instruction executed, keeping the last `depth` of them
+    pub fn with_trace(mut self, depth: usize) -> Self {
+        self.trace = Some(ExecutionTrace::new(depth));
+        self
+    }
+
+    /// Get the last instructions executed, oldest first
+    /// This is empty unless tracing was turned on with `with_trace`.
+    pub fn get_trace(&self) -> &[TraceEntry] {
+        match &self.trace {
+            Some(trace) => trace.entries(),
+            None => &[],
+        }
+    }
+
+    /// Add the instruction at the PC to the trace, if tracing is on
+    pub(super) fn record_trace(&mut self, opcode: u16) {
+        // only decode the instruction if it is going to be kept
+        if self.trace.is_none() {
+            return;
+        }
+        let instruction = self.get_current_instruction().unwrap_or(Instruction::Unknown);
+
+        if let Some(trace) = &mut self.trace {
+            trace.record(TraceEntry {
+                pc: self.registers.pc,
+                opcode,
+                instruction,
+                v_before: self.registers.v,
+                v_after: self.registers.v,
+                cycle: self.total_cycles,
+            });
+        }
+    }
+
+    /// Note the registers as the last traced instruction left them, if tracing is on
+    /// This is done even if the instruction failed, so the trace shows what it changed.
+    pub(super) fn finish_trace(&mut self) {
+        let v = self.registers.v;
+        if let Some(entry) = self.trace.as_mut().and_then(|trace| trace.entries.last_mut()) {
+            entry.v_after = v;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chip8::{Chip8, Chip8Builder};
+    use crate::disasm::format_trace;
+    use alloc::vec::Vec;
+
+    /// Make a machine with a ROM loaded, keeping a trace of the given depth
+    fn load(rom: &[u8], depth: usize) -> Chip8 {
+        Chip8Builder::default()
+            .rom_bytes(rom)
+            .build()
+            .expect("the test ROM should load")
+            .with_trace(depth)
+    }
+
+    #[test]
+    fn trace_of_depth_zero_keeps_nothing() {
+        let mut chip8 = load(&[0x12, 0x00], 0);
+        for _ in 0..10 {
+            chip8.do_next_instruction().expect("the jump should run");
+        }
+        // nothing is buffered either, so the trace can't grow without end
+        assert!(chip8.get_trace().is_empty());
+        assert!(chip8.trace.as_ref().is_some_and(|trace| trace.entries.is_empty()));
+    }
+
+    #[test]
+    fn trace_keeps_the_last_instructions() {
+        // V0 += 1 forever
+        let mut chip8 = load(&[0x70, 0x01, 0x12, 0x00], 3);
+        for _ in 0..10 {
+            chip8.do_next_instruction().expect("the instruction should run");
+        }
+        let cycles: Vec<u64> = chip8.get_trace().iter().map(|entry| entry.cycle).collect();
+        assert_eq!(cycles, [7, 8, 9]);
+    }
+
+    #[test]
+    fn trace_shows_the_registers_each_instruction_changed() {
+        // V0 = 0x11, V1 = 0x22, I = 0xFFE, then read V0 to V3 from past the end of memory
+        let rom = [0x60, 0x11, 0x61, 0x22, 0xAF, 0xFE, 0xF3, 0x65];
+        let mut chip8 = load(&rom, 8);
+        for _ in 0..3 {
+            chip8.do_next_instruction().expect("the instruction should run");
+        }
+        assert!(chip8.do_next_instruction().is_err());
+
+        let trace = chip8.get_trace();
+        assert_eq!(trace.len(), 4);
+        assert_eq!(trace[0].v_before[0x0], 0x00);
+        assert_eq!(trace[0].v_after[0x0], 0x11);
+        assert_eq!(trace[2].v_before, trace[2].v_after);
+
+        // the failing read still changed V0 and V1 before it ran off the end
+        assert_eq!(trace[3].v_before[..2], [0x11, 0x22]);
+        assert_eq!(trace[3].v_after[..2], [0x00, 0x00]);
+
+        let table = format_trace(trace);
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[1].ends_with("V0: 0x00 -> 0x11"), "{}", lines[1]);
+        assert!(lines[2].ends_with("V1: 0x00 -> 0x22"), "{}", lines[2]);
+        assert!(lines[3].ends_with("LD I, 0xffe"), "{}", lines[3]);
+        assert!(
+            lines[4].ends_with("V0: 0x11 -> 0x00, V1: 0x22 -> 0x00"),
+            "{}",
+            lines[4]
+        );
+    }
+}
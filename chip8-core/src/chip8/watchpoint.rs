@@ -0,0 +1,127 @@
+use crate::chip8::Chip8;
+use crate::error::Chip8Error;
+use alloc::format;
+use alloc::string::String;
+use core::fmt;
+use core::str::FromStr;
+
+/// The most watchpoints that can be set at once
+pub const MAX_WATCHPOINTS: usize = 16;
+
+/// The kinds of memory access that a watchpoint is triggered by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchMode {
+    /// Reads by an instruction, such as FX65 or DXYN
+    Read,
+
+    /// Writes by an instruction, such as FX55 or FX33
+    Write,
+
+    /// Both reads and writes
+    ReadWrite,
+}
+
+impl WatchMode {
+    /// Check if an access of the given kind triggers a watchpoint with this mode
+    fn is_triggered_by(self, access: WatchMode) -> bool {
+        self == WatchMode::ReadWrite || self == access
+    }
+}
+
+impl FromStr for WatchMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "r" | "read" => Ok(WatchMode::Read),
+            "w" | "write" => Ok(WatchMode::Write),
+            "rw" | "readwrite" | "read-write" => Ok(WatchMode::ReadWrite),
+            _ => Err(format!("Unknown watch mode '{}'", s)),
+        }
+    }
+}
+
+impl fmt::Display for WatchMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchMode::Read => write!(f, "read"),
+            WatchMode::Write => write!(f, "write"),
+            WatchMode::ReadWrite => write!(f, "read/write"),
+        }
+    }
+}
+
+/// An access to a watched address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WatchpointEvent {
+    /// The address that was accessed
+    pub addr: u16,
+
+    /// The value before the access
+    pub old_value: u8,
+
+    /// The value after the access, which is the same as the old value for reads
+    pub new_value: u8,
+
+    /// Whether the access was a read or a write
+    pub mode_triggered: WatchMode,
+
+    /// The address of the instruction that made the access
+    pub pc: u16,
+}
+
+impl Chip8 {
+    /// Watch an address for reads, writes, or both
+    /// Watching an address that is already watched replaces its mode. At most `MAX_WATCHPOINTS`
+    /// addresses can be watched at once.
+    pub fn add_watchpoint(&mut self, addr: u16, mode: WatchMode) -> Result<(), Chip8Error> {
+        let slot = self
+            .watchpoints
+            .iter()
+            .position(|watchpoint| matches!(watchpoint, Some((a, _)) if *a == addr))
+            .or_else(|| self.watchpoints.iter().position(Option::is_none))
+            .ok_or(Chip8Error::TooManyWatchpoints)?;
+
+        self.watchpoints[slot] = Some((addr, mode));
+        Ok(())
+    }
+
+    /// Stop watching an address
+    pub fn remove_watchpoint(&mut self, addr: u16) {
+        for watchpoint in &mut self.watchpoints {
+            if matches!(watchpoint, Some((a, _)) if *a == addr) {
+                *watchpoint = None;
+            }
+        }
+    }
+
+    /// Check whether any address is being watched
+    pub fn has_watchpoints(&self) -> bool {
+        self.watchpoints.iter().any(Option::is_some)
+    }
+
+    /// Get the watched access made by the last instruction, if it made one
+    /// If the instruction accessed several watched addresses, this is the last of them.
+    pub fn watchpoint_hit(&self) -> Option<WatchpointEvent> {
+        self.watchpoint_hit
+    }
+
+    /// Record an access by an instruction, if the address is watched
+    pub(super) fn check_watchpoint(&mut self, addr: usize, old: u8, new: u8, access: WatchMode) {
+        let watched = self
+            .watchpoints
+            .iter()
+            .flatten()
+            .any(|&(a, mode)| a as usize == addr && mode.is_triggered_by(access));
+
+        if watched {
+            self.watchpoint_hit = Some(WatchpointEvent {
+                addr: addr as u16,
+                old_value: old,
+                new_value: new,
+                mode_triggered: access,
+                pc: self.registers.pc,
+            });
+        }
+    }
+}
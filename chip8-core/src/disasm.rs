@@ -0,0 +1,206 @@
+//! Turning ROMs and execution traces back into CHIP-8 assembly
+
+use crate::instructions::Instruction::*;
+use crate::chip8::{TraceEntry, PROGMEM_START};
+use crate::instructions::{Instruction, Register};
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+
+/// A single disassembled instruction
+#[derive(Debug)]
+pub struct DisassembledLine {
+    /// The address of the instruction
+    pub address: u16,
+
+    /// The raw opcode
+    pub opcode: u16,
+
+    /// The decoded instruction
+    pub instruction: Instruction,
+
+    /// The instruction in CHIP-8 assembly
+    pub mnemonic: String,
+}
+
+/// Disassemble a ROM into CHIP-8 assembly
+/// Every two bytes are treated as an instruction, with a zero byte padding out odd-length ROMs.
+pub fn disassemble(rom: &[u8]) -> Vec<DisassembledLine> {
+    // the last opcode is padded out with a zero byte if needed
+    let opcodes: Vec<u16> = rom
+        .chunks(2)
+        .map(|pair| ((pair[0] as u16) << 8) | *pair.get(1).unwrap_or(&0x00) as u16)
+        .collect();
+
+    let mut lines: Vec<DisassembledLine> = Vec::new();
+    let mut index: usize = 0;
+    while index < opcodes.len() {
+        let opcode = opcodes[index];
+        let address = PROGMEM_START + (index * 2) as u16;
+
+        let instruction = match Instruction::decode(opcode) {
+            LoadLongAddress(_) if index + 1 < opcodes.len() => {
+                // the address of a long load is the next opcode
+                index += 1;
+                LoadLongAddress(opcodes[index] as usize)
+            }
+            instruction => instruction,
+        };
+
+        lines.push(DisassembledLine {
+            address,
+            opcode,
+            mnemonic: get_mnemonic(&instruction, opcode),
+            instruction,
+        });
+
+        index += 1;
+    }
+
+    lines
+}
+
+/// Format an execution trace as a table
+/// Each line shows the registers that the instruction changed.
+pub fn format_trace(trace: &[TraceEntry]) -> String {
+    let mut lines: Vec<String> =
+        vec!["CYCLE       ADDR   OPCODE  INSTRUCTION       CHANGES".to_string()];
+
+    for entry in trace {
+        let (before, after) = (&entry.v_before, &entry.v_after);
+        let changes: Vec<String> = (0..16)
+            .filter(|&r| before[r] != after[r])
+            .map(|r| format!("{}: {:#04x} -> {:#04x}", reg(&r), before[r], after[r]))
+            .collect();
+
+        let line = format!(
+            "{:<10}  {:#05x}  {:04X}    {:<16}  {}",
+            entry.cycle,
+            entry.pc,
+            entry.opcode,
+            get_mnemonic(&entry.instruction, entry.opcode),
+            changes.join(", ")
+        );
+        lines.push(line.trim_end().to_string());
+    }
+
+    lines.join("\n")
+}
+
+/// Format a register name
+fn reg(register: &Register) -> String {
+    format!("V{:X}", register)
+}
+
+/// Get the CHIP-8 assembly for an instruction
+/// Unknown opcodes are shown as data, since the instruction alone doesn't hold the opcode.
+pub fn get_mnemonic(instruction: &Instruction, opcode: u16) -> String {
+    match instruction {
+        Unknown => format!("DB {:#06x}", opcode),
+        instruction => instruction.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::disassemble;
+    use crate::instructions::Instruction;
+    use alloc::vec::Vec;
+
+    // an opcode for every instruction, with the assembly it should be shown as
+    const MNEMONICS: [(u16, &str); 51] = [
+        (0x01A2, "SYS 0x1a2"),
+        (0x00E0, "CLS"),
+        (0x00EE, "RET"),
+        (0x12A8, "JP 0x2a8"),
+        (0xB300, "JP V0, 0x300"),
+        (0x220C, "CALL 0x20c"),
+        (0x3A0F, "SE VA, 0x0f"),
+        (0x51B0, "SE V1, VB"),
+        (0x42FF, "SNE V2, 0xff"),
+        (0x93C0, "SNE V3, VC"),
+        (0x6442, "LD V4, 0x42"),
+        (0x85D0, "LD V5, VD"),
+        (0xA050, "LD I, 0x050"),
+        (0xF629, "LD F, V6"),
+        (0xF733, "LD B, V7"),
+        (0xF855, "LD [I], V8"),
+        (0xF965, "LD V9, [I]"),
+        (0x7E01, "ADD VE, 0x01"),
+        (0x80F4, "ADD V0, VF"),
+        (0xF11E, "ADD I, V1"),
+        (0x8235, "SUB V2, V3"),
+        (0x8457, "SUBN V4, V5"),
+        (0x8671, "OR V6, V7"),
+        (0x8892, "AND V8, V9"),
+        (0x8AB3, "XOR VA, VB"),
+        (0x8CD6, "SHR VC, VD"),
+        (0x8EFE, "SHL VE, VF"),
+        (0xC080, "RND V0, 0x80"),
+        (0xD125, "DRW V1, V2, 5"),
+        (0xE39E, "SKP V3"),
+        (0xE4A1, "SKNP V4"),
+        (0xF50A, "LD V5, K"),
+        (0xF607, "LD V6, DT"),
+        (0xF715, "LD DT, V7"),
+        (0xF818, "LD ST, V8"),
+        (0x00C4, "SCD 4"),
+        (0x00FC, "SCL"),
+        (0x00FB, "SCR"),
+        (0x00FD, "EXIT"),
+        (0x00FE, "LOW"),
+        (0x00FF, "HIGH"),
+        (0xD9A0, "DRW V9, VA, 0"),
+        (0xFB30, "LD HF, VB"),
+        (0xFC75, "LD R, VC"),
+        (0xFD85, "LD VD, R"),
+        (0x00D2, "SCU 2"),
+        (0x5142, "SAVE V1 - V4"),
+        (0x5253, "LOAD V2 - V5"),
+        (0xF301, "PLANE 3"),
+        (0xF002, "AUDIO"),
+        (0xFE3A, "PITCH VE"),
+    ];
+
+    #[test]
+    fn disassemble_each_instruction() {
+        for (opcode, mnemonic) in MNEMONICS {
+            let lines = disassemble(&opcode.to_be_bytes());
+            assert_eq!(lines.len(), 1);
+            assert_eq!(lines[0].address, 0x200);
+            assert_eq!(lines[0].opcode, opcode);
+            assert_eq!(lines[0].instruction, Instruction::decode(opcode));
+            assert_eq!(lines[0].mnemonic, mnemonic, "{:#06x}", opcode);
+        }
+    }
+
+    #[test]
+    fn disassemble_long_load_with_its_address() {
+        let lines = disassemble(&[0xF0, 0x00, 0x12, 0x34, 0x00, 0xE0]);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].instruction, Instruction::LoadLongAddress(0x1234));
+        assert_eq!(lines[0].mnemonic, "LD I, LONG 0x1234");
+        assert_eq!(lines[1].address, 0x204);
+        assert_eq!(lines[1].mnemonic, "CLS");
+    }
+
+    #[test]
+    fn disassemble_unknown_opcodes_as_data() {
+        let lines = disassemble(&[0x51, 0x21, 0x80, 0x08, 0xE1, 0x00]);
+        let mnemonics: Vec<_> = lines.iter().map(|line| line.mnemonic.as_str()).collect();
+        assert_eq!(mnemonics, ["DB 0x5121", "DB 0x8008", "DB 0xe100"]);
+        assert!(lines
+            .iter()
+            .all(|line| line.instruction == Instruction::Unknown));
+    }
+
+    #[test]
+    fn disassemble_odd_length_rom() {
+        let lines = disassemble(&[0x00, 0xE0, 0x12]);
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1].address, 0x202);
+        assert_eq!(lines[1].opcode, 0x1200);
+        assert_eq!(lines[1].mnemonic, "JP 0x200");
+    }
+}
@@ -0,0 +1,263 @@
+//! Errors from setting up and running the emulator
+
+use crate::chip8::MAX_WATCHPOINTS;
+use alloc::string::String;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::path::PathBuf;
+
+/// Errors that can occur while setting up the emulator
+#[derive(Debug)]
+pub enum Chip8Error {
+    /// The ROM file does not exist
+    #[cfg(feature = "std")]
+    RomNotFound(PathBuf),
+
+    /// The ROM file could not be read
+    #[cfg(feature = "std")]
+    RomReadError(io::Error),
+
+    /// The ROM does not fit in program memory
+    RomTooLarge {
+        /// The size of the ROM, in bytes
+        size: usize,
+
+        /// The most bytes that fit in program memory
+        max: usize,
+    },
+
+    /// The font does not fit in font memory
+    FontTooLarge,
+
+    /// The font file could not be opened
+    #[cfg(feature = "std")]
+    FontNotFound(PathBuf),
+
+    /// The font could not be read
+    #[cfg(feature = "std")]
+    FontReadError(io::Error),
+
+    /// The ROM could not be downloaded
+    NetworkError(String),
+
+    /// There is no instruction to step back from
+    NoHistory,
+
+    /// Every watchpoint is already in use
+    TooManyWatchpoints,
+
+    /// The entry point is odd, or outside of the ROM
+    InvalidEntryPoint(u16),
+
+    /// There is no register with this number
+    InvalidRegister(u8),
+
+    /// There is no key on the keypad with this number
+    InvalidKey(u8),
+
+    /// The program counter can't be set to this address, as it is odd or outside of memory
+    InvalidProgramCounter(u16),
+
+    /// A ROM written in hex has something other than hex digits in it
+    InvalidHexString {
+        /// The number of tokens before the one that couldn't be read
+        position: usize,
+
+        /// The token that couldn't be read
+        token: String,
+    },
+
+    /// Bytes written into RAM would run past the end of memory
+    OutOfBoundsMemoryAccess {
+        /// The address the bytes start at
+        addr: u16,
+
+        /// The number of bytes
+        len: usize,
+    },
+
+    /// Bytes written into RAM would overwrite the font while it is write protected
+    WriteProtectedRegion(u16),
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            #[cfg(feature = "std")]
+            Chip8Error::RomNotFound(path) => write!(f, "ROM not found: {}", path.display()),
+            #[cfg(feature = "std")]
+            Chip8Error::RomReadError(e) => write!(f, "unable to read ROM: {}", e),
+            Chip8Error::RomTooLarge { size, max } => write!(
+                f,
+                "ROM is too large: {} bytes, but at most {} bytes fit in memory",
+                size, max
+            ),
+            Chip8Error::FontTooLarge => write!(f, "font is too large to fit in font memory"),
+            #[cfg(feature = "std")]
+            Chip8Error::FontNotFound(path) => write!(f, "font not found: {}", path.display()),
+            #[cfg(feature = "std")]
+            Chip8Error::FontReadError(e) => write!(f, "unable to read font: {}", e),
+            Chip8Error::NetworkError(e) => write!(f, "unable to download ROM: {}", e),
+            Chip8Error::NoHistory => write!(f, "no instructions to step back through"),
+            Chip8Error::TooManyWatchpoints => write!(
+                f,
+                "too many watchpoints: at most {} can be set at once",
+                MAX_WATCHPOINTS
+            ),
+            Chip8Error::InvalidEntryPoint(entry) => write!(
+                f,
+                "invalid entry point {:#05x}: it must be an even address inside the ROM",
+                entry
+            ),
+            Chip8Error::InvalidRegister(reg) => {
+                write!(f, "invalid register {:#04x}: registers go from V0 to VF", reg)
+            }
+            Chip8Error::InvalidKey(key) => {
+                write!(f, "invalid key {:#04x}: keys go from 0x0 to 0xF", key)
+            }
+            Chip8Error::InvalidProgramCounter(pc) => write!(
+                f,
+                "invalid program counter {:#06x}: it must be an even address inside memory",
+                pc
+            ),
+            Chip8Error::InvalidHexString { position, token } => write!(
+                f,
+                "invalid hex string: token {} ('{}') is not a hex number of the right length",
+                position, token
+            ),
+            Chip8Error::OutOfBoundsMemoryAccess { addr, len } => write!(
+                f,
+                "memory access out of bounds: {} bytes at {:#05x} run past the end of memory",
+                len, addr
+            ),
+            Chip8Error::WriteProtectedRegion(addr) => {
+                write!(f, "write to the protected font region at {:#05x}", addr)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Chip8Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Chip8Error::RomReadError(e) => Some(e),
+            Chip8Error::FontReadError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Errors that can occur while running a program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EmulatorError {
+    /// The opcode is not a valid instruction in the current emulation mode
+    UnknownOpcode {
+        /// The opcode that was not understood
+        opcode: u16,
+
+        /// The address of the opcode
+        pc: u16,
+    },
+
+    /// A subroutine was called with no room left on the stack
+    StackOverflow {
+        /// The number of calls on the stack
+        depth: usize,
+    },
+
+    /// A subroutine returned with nothing on the stack
+    StackUnderflow,
+
+    /// An instruction read or wrote past the end of memory
+    OutOfBoundsMemoryAccess {
+        /// The address that was accessed
+        addr: usize,
+
+        /// The address of the instruction that accessed it
+        pc: u16,
+    },
+
+    /// An instruction wrote below program memory while the font region is write protected
+    WriteProtectedRegion {
+        /// The address that was written to
+        addr: usize,
+    },
+}
+
+impl fmt::Display for EmulatorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EmulatorError::UnknownOpcode { opcode, pc } => {
+                write!(f, "unknown opcode {:#06x} at {:#05x}", opcode, pc)
+            }
+            EmulatorError::StackOverflow { depth } => {
+                write!(f, "stack overflow at a depth of {} calls", depth)
+            }
+            EmulatorError::StackUnderflow => write!(f, "return with an empty stack"),
+            EmulatorError::OutOfBoundsMemoryAccess { addr, .. } => {
+                write!(f, "memory access out of bounds at {:#06x}", addr)
+            }
+            EmulatorError::WriteProtectedRegion { addr } => {
+                write!(f, "write to the protected font region at {:#05x}", addr)
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for EmulatorError {}
+
+/// The name given for a quirks preset isn't one of the known interpreters
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseQuirksPresetError(pub String);
+
+impl fmt::Display for ParseQuirksPresetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Unknown quirks preset '{}' (expected chip8, chip48, schip10, schip11, or xochip)",
+            self.0
+        )
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseQuirksPresetError {}
+
+/// Errors that can stop a run before what it was waiting for happens
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RunUntilError {
+    /// The run reached its limit of instructions first
+    MaxCyclesExceeded,
+
+    /// An instruction failed first
+    ExecutionError(EmulatorError),
+}
+
+impl From<EmulatorError> for RunUntilError {
+    fn from(e: EmulatorError) -> Self {
+        RunUntilError::ExecutionError(e)
+    }
+}
+
+impl fmt::Display for RunUntilError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RunUntilError::MaxCyclesExceeded => write!(f, "ran out of cycles before stopping"),
+            RunUntilError::ExecutionError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for RunUntilError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RunUntilError::ExecutionError(e) => Some(e),
+            _ => None,
+        }
+    }
+}
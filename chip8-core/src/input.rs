@@ -0,0 +1,234 @@
+//! Input from the keypad
+
+use crate::chip8::Chip8;
+use crate::error::EmulatorError;
+use alloc::vec::Vec;
+
+/// A change to one of the 16 keys on the keypad
+pub enum Input {
+    /// The key was pressed
+    Pressed(u8),
+
+    /// The key was released
+    Unpressed(u8),
+}
+
+/// Something to do in a scripted sequence of input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputStep {
+    Press(u8),
+    Release(u8),
+    Run(u32),
+}
+
+/// A scripted sequence of key presses, key releases and instructions, for driving a Chip8 from
+/// code instead of a keyboard
+/// Nothing happens until the sequence is run, and then everything happens in the order it was
+/// added.
+#[derive(Debug, Clone, Default)]
+pub struct MockInputSequence {
+    steps: Vec<InputStep>,
+}
+
+impl MockInputSequence {
+    /// Make an empty sequence
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Press a key, which must be from 0 to F
+    pub fn press(mut self, key: u8) -> Self {
+        assert!(key < 16, "There is no key {:X} on the keypad", key);
+        self.steps.push(InputStep::Press(key));
+        self
+    }
+
+    /// Release a key, which must be from 0 to F
+    pub fn release(mut self, key: u8) -> Self {
+        assert!(key < 16, "There is no key {:X} on the keypad", key);
+        self.steps.push(InputStep::Release(key));
+        self
+    }
+
+    /// Run the next instruction
+    pub fn step(self) -> Self {
+        self.steps(1)
+    }
+
+    /// Run the next few instructions
+    pub fn steps(mut self, count: u32) -> Self {
+        self.steps.push(InputStep::Run(count));
+        self
+    }
+
+    /// Play the sequence on an emulator, stopping at the first instruction that fails
+    pub fn run_on(&self, chip8: &mut Chip8) -> Result<(), EmulatorError> {
+        // keys are checked as they are added, so changing them can't fail
+        let change = |chip8: &mut Chip8, input| {
+            chip8
+                .change_input(input)
+                .expect("the key should be on the keypad");
+        };
+
+        for &step in &self.steps {
+            match step {
+                InputStep::Press(key) => change(chip8, Input::Pressed(key)),
+                InputStep::Release(key) => change(chip8, Input::Unpressed(key)),
+                InputStep::Run(count) => {
+                    for _ in 0..count {
+                        chip8.do_next_instruction()?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MockInputSequence;
+    use crate::chip8::{Chip8, Chip8Builder};
+
+    // wait for a key into V0, then spin at 0x202
+    const WAIT_FOR_KEY: [u8; 4] = [0xF0, 0x0A, 0x12, 0x02];
+
+    // V0 = 5, V1 = 1 unless key 5 is pressed, V2 = 1 unless it isn't, then spin at 0x20A
+    const SKIP_ON_KEY: [u8; 12] = [
+        0x60, 0x05, 0xE0, 0x9E, 0x61, 0x01, 0xE0, 0xA1, 0x62, 0x01, 0x12, 0x0A,
+    ];
+
+    /// Make a machine with a ROM loaded
+    fn load(rom: &[u8]) -> Chip8 {
+        Chip8Builder::default()
+            .rom_bytes(rom)
+            .build()
+            .expect("the test ROM should load")
+    }
+
+    #[test]
+    fn store_keypress_waits_for_a_release() {
+        let mut chip8 = load(&WAIT_FOR_KEY);
+        MockInputSequence::new()
+            .steps(3)
+            .press(4)
+            .step()
+            .run_on(&mut chip8)
+            .unwrap();
+
+        // the key is still held, so FX0A is still waiting
+        let registers = chip8.snapshot_registers();
+        assert_eq!(registers.pc, 0x200);
+        assert_eq!(registers.v[0], 0);
+
+        MockInputSequence::new()
+            .release(4)
+            .step()
+            .run_on(&mut chip8)
+            .unwrap();
+        let registers = chip8.snapshot_registers();
+        assert_eq!(registers.pc, 0x202);
+        assert_eq!(registers.v[0], 4);
+    }
+
+    #[test]
+    fn store_keypress_ignores_keys_held_before_waiting() {
+        let mut chip8 = load(&WAIT_FOR_KEY);
+        MockInputSequence::new()
+            .press(5)
+            .step()
+            .release(5)
+            .steps(2)
+            .run_on(&mut chip8)
+            .unwrap();
+        assert_eq!(chip8.snapshot_registers().pc, 0x200);
+
+        MockInputSequence::new()
+            .press(2)
+            .release(2)
+            .step()
+            .run_on(&mut chip8)
+            .unwrap();
+        assert_eq!(chip8.snapshot_registers().v[0], 2);
+    }
+
+    #[test]
+    fn store_keypress_stores_the_first_key_released() {
+        let mut chip8 = load(&WAIT_FOR_KEY);
+        MockInputSequence::new()
+            .step()
+            .press(3)
+            .press(7)
+            .step()
+            .release(7)
+            .step()
+            .release(3)
+            .steps(2)
+            .run_on(&mut chip8)
+            .unwrap();
+
+        let registers = chip8.snapshot_registers();
+        assert_eq!(registers.v[0], 7);
+        assert_eq!(registers.pc, 0x202);
+    }
+
+    #[test]
+    fn store_keypress_stores_the_lowest_of_keys_released_together() {
+        let mut chip8 = load(&WAIT_FOR_KEY);
+        MockInputSequence::new()
+            .step()
+            .press(0xC)
+            .press(9)
+            .release(0xC)
+            .release(9)
+            .step()
+            .run_on(&mut chip8)
+            .unwrap();
+
+        assert_eq!(chip8.snapshot_registers().v[0], 9);
+    }
+
+    #[test]
+    fn skip_if_key_pressed() {
+        let mut chip8 = load(&SKIP_ON_KEY);
+        MockInputSequence::new()
+            .press(5)
+            .steps(5)
+            .run_on(&mut chip8)
+            .unwrap();
+
+        let registers = chip8.snapshot_registers();
+        assert_eq!(registers.v[1], 0, "EX9E should skip while the key is pressed");
+        assert_eq!(registers.v[2], 1, "EXA1 shouldn't skip while the key is pressed");
+    }
+
+    #[test]
+    fn skip_if_key_not_pressed() {
+        let mut chip8 = load(&SKIP_ON_KEY);
+        MockInputSequence::new()
+            .press(4)
+            .steps(5)
+            .run_on(&mut chip8)
+            .unwrap();
+
+        let registers = chip8.snapshot_registers();
+        assert_eq!(registers.v[1], 1, "EX9E shouldn't skip while the key isn't pressed");
+        assert_eq!(registers.v[2], 0, "EXA1 should skip while the key isn't pressed");
+    }
+
+    #[test]
+    fn skip_after_key_released() {
+        let mut chip8 = load(&SKIP_ON_KEY);
+        MockInputSequence::new()
+            .press(5)
+            .release(5)
+            .steps(5)
+            .run_on(&mut chip8)
+            .unwrap();
+
+        let registers = chip8.snapshot_registers();
+        assert_eq!(registers.v[1], 1);
+        assert_eq!(registers.v[2], 0);
+    }
+}
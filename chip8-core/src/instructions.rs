@@ -0,0 +1,636 @@
+//! Decoding and encoding instructions
+
+use crate::chip8::EmulationMode;
+use crate::instructions::Instruction::*;
+use alloc::format;
+use core::fmt;
+
+/// A memory address in an instruction
+pub type Address = usize;
+
+/// A constant value in an instruction
+pub type Immediate = u8;
+
+/// The number of a general-purpose register, from 0x0 to 0xF
+pub type Register = usize;
+
+/// A decoded CHIP-8, SUPER-CHIP, or XO-CHIP instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    // emulator special
+    /// An opcode that isn't a valid instruction
+    Unknown,
+
+    // system
+    /// 0NNN: call a machine code routine (ignored)
+    Sys(Address),
+    /// 00E0: clear the display
+    Cls,
+    /// 00EE: return from a subroutine
+    Ret,
+    /// 1NNN: jump to NNN
+    Jump(Address),
+    /// BNNN: jump to NNN plus V0, or plus VX where X is the high nibble of NNN with the jump0 quirk
+    JumpWithOffset(Address),
+    /// 2NNN: call the subroutine at NNN
+    Call(Address),
+
+    // branching
+    /// 3XNN: skip the next instruction if VX equals NN
+    SkipEqualImm(Register, Immediate),
+    /// 5XY0: skip the next instruction if VX equals VY
+    SkipEqualReg(Register, Register),
+    /// 4XNN: skip the next instruction if VX doesn't equal NN
+    SkipNotEqualImm(Register, Immediate),
+    /// 9XY0: skip the next instruction if VX doesn't equal VY
+    SkipNotEqualReg(Register, Register),
+
+    // loads
+    /// 6XNN: set VX to NN
+    LoadImm(Register, Immediate),
+    /// 8XY0: set VX to VY
+    LoadReg(Register, Register),
+    /// ANNN: set I to NNN
+    LoadAddress(Address),
+    /// FX29: point I at the small font sprite for the digit in VX
+    SetSpriteLoc(Register),
+    /// FX33: store the decimal digits of VX at I
+    StoreBCD(Register),
+    /// FX55: store V0 to VX at I
+    StoreRegisters(Register),
+    /// FX65: read V0 to VX from I
+    ReadRegisters(Register),
+
+    // arithmetic
+    /// 7XNN: add NN to VX
+    AddImm(Register, Immediate),
+    /// 8XY4: add VY to VX, setting VF on carry
+    AddReg(Register, Register),
+    /// FX1E: add VX to I
+    AddIndex(Register),
+    /// 8XY5: subtract VY from VX, clearing VF on borrow
+    SubReg(Register, Register),
+    /// 8XY7: set VX to VY minus VX, clearing VF on borrow
+    SubNReg(Register, Register),
+
+    // logic
+    /// 8XY1: set VX to VX OR VY
+    OrReg(Register, Register),
+    /// 8XY2: set VX to VX AND VY
+    AndReg(Register, Register),
+    /// 8XY3: set VX to VX XOR VY
+    XorReg(Register, Register),
+    /// 8XY6: set VX to VY shifted right by one, with the old low bit in VF
+    ShiftRightReg(Register, Register),
+    /// 8XYE: set VX to VY shifted left by one, with the old high bit in VF
+    ShiftLeftReg(Register, Register),
+
+    // special
+    /// CXNN: set VX to a random number AND NN
+    RandAndImmediate(Register, Immediate),
+    /// DXYN: draw an N byte sprite from I at (VX, VY), setting VF on collision
+    Draw(Register, Register, Immediate),
+    /// EX9E: skip the next instruction if the key in VX is pressed
+    SkipIfKeyPressed(Register),
+    /// EXA1: skip the next instruction if the key in VX isn't pressed
+    SkipIfKeyNotPressed(Register),
+    /// FX0A: wait for a key press, and store the key in VX
+    StoreKeypress(Register),
+
+    // timers
+    /// FX07: set VX to the delay timer
+    ReadDelayTimer(Register),
+    /// FX15: set the delay timer to VX
+    WriteDelayTimer(Register),
+    /// FX18: set the sound timer to VX
+    WriteSoundTimer(Register),
+
+    // SUPER-CHIP
+    /// 00CN: scroll the display down by N pixels
+    ScrollDown(Immediate),
+    /// 00FC: scroll the display left by 4 pixels
+    ScrollLeft,
+    /// 00FB: scroll the display right by 4 pixels
+    ScrollRight,
+    /// 00FD: exit the interpreter
+    ExitInterpreter,
+    /// 00FE: switch to low resolution mode
+    LowRes,
+    /// 00FF: switch to high resolution mode
+    HighRes,
+    /// DXY0: draw a 16x16 sprite from I at (VX, VY)
+    DrawLarge(Register, Register),
+    /// FX30: point I at the large font sprite for the digit in VX
+    SetLargeSpriteLoc(Register),
+    /// FX75: store V0 to VX in the user flags
+    StoreFlags(Register),
+    /// FX85: read V0 to VX from the user flags
+    ReadFlags(Register),
+
+    // XO-CHIP
+    /// 00DN: scroll the display up by N pixels
+    ScrollUp(Immediate),
+    /// 5XY2: store VX to VY at I
+    StoreRange(Register, Register),
+    /// 5XY3: read VX to VY from I
+    LoadRange(Register, Register),
+    /// F000 NNNN: set I to the 16-bit address after the opcode
+    LoadLongAddress(Address),
+    /// FN01: select the display planes to draw to
+    SetPlanes(Immediate),
+    /// F002: load the 16 byte audio pattern from I
+    LoadAudio,
+    /// FX3A: set the audio pitch to VX
+    SetPitch(Register),
+}
+
+impl Instruction {
+    /// Identify the instruction for an opcode
+    /// The address of a long load is in the two bytes after its opcode, so it is decoded with
+    /// an address of 0 and must be filled in by the caller.
+    pub fn decode(opcode: u16) -> Instruction {
+        // get the opcode components
+        let inst_word: u8 = ((opcode & 0xF000) >> 12) as u8;
+        let addr: usize = (opcode & 0x0FFF) as usize;
+        let nibble: u8 = (opcode & 0x000F) as u8;
+        let imm: Immediate = (opcode & 0x00FF) as u8;
+        let regx: Register = ((opcode & 0x0F00) >> 8) as usize;
+        let regy: Register = ((opcode & 0x00F0) >> 4) as usize;
+
+        // use the components to make the instruction to return
+        match inst_word {
+            0x0 => {
+                // SYS, CLS, RET, or SUPER-CHIP display instructions
+                match addr {
+                    0x0C0..=0x0CF => ScrollDown(nibble),
+                    0x0D0..=0x0DF => ScrollUp(nibble),
+                    0x0E0 => Cls,
+                    0x0EE => Ret,
+                    0x0FB => ScrollRight,
+                    0x0FC => ScrollLeft,
+                    0x0FD => ExitInterpreter,
+                    0x0FE => LowRes,
+                    0x0FF => HighRes,
+                    _ => Sys(addr),
+                }
+            }
+            0x1 => {
+                // JP instruction
+                Jump(addr)
+            }
+            0x2 => {
+                // CALL instruction
+                Call(addr)
+            }
+            0x3 => {
+                // SE instruction (immediate)
+                SkipEqualImm(regx, imm)
+            }
+            0x4 => {
+                // SNE instruction (immediate)
+                SkipNotEqualImm(regx, imm)
+            }
+            0x5 => {
+                // SE instruction (register), or XO-CHIP register range store and load
+                match nibble {
+                    0x0 => SkipEqualReg(regx, regy),
+                    0x2 => StoreRange(regx, regy),
+                    0x3 => LoadRange(regx, regy),
+                    _ => Unknown,
+                }
+            }
+            0x6 => {
+                // LD instruction (immediate)
+                LoadImm(regx, imm)
+            }
+            0x7 => {
+                // ADD instruction (immediate)
+                AddImm(regx, imm)
+            }
+            0x8 => {
+                // LD, OR, AND, XOR, ADD, SUB, SUBN, SHR, and SHL instructions for registers
+                match nibble {
+                    0x0 => LoadReg(regx, regy),
+                    0x1 => OrReg(regx, regy),
+                    0x2 => AndReg(regx, regy),
+                    0x3 => XorReg(regx, regy),
+                    0x4 => AddReg(regx, regy),
+                    0x5 => SubReg(regx, regy),
+                    0x6 => ShiftRightReg(regx, regy),
+                    0x7 => SubNReg(regx, regy),
+                    0xE => ShiftLeftReg(regx, regy),
+                    _ => Unknown,
+                }
+            }
+            0x9 => {
+                // SNE instruction (register)
+                SkipNotEqualReg(regx, regy)
+            }
+            0xA => {
+                // LD instruction (index)
+                LoadAddress(addr)
+            }
+            0xB => {
+                // JP instruction with offset
+                JumpWithOffset(addr)
+            }
+            0xC => {
+                // RND instruction
+                RandAndImmediate(regx, imm)
+            }
+            0xD => {
+                // DRW instruction
+                match nibble {
+                    0x0 => DrawLarge(regx, regy),
+                    _ => Draw(regx, regy, nibble),
+                }
+            }
+            0xE => {
+                // Input instructions (SKP and SKNP)
+                match imm {
+                    0x9E => SkipIfKeyPressed(regx),
+                    0xA1 => SkipIfKeyNotPressed(regx),
+                    _ => Unknown,
+                }
+            }
+            0xF => {
+                // Special loads and adds
+                match imm {
+                    0x00 if regx == 0x0 => LoadLongAddress(0),
+                    0x01 => SetPlanes(regx as u8),
+                    0x02 if regx == 0x0 => LoadAudio,
+                    0x07 => ReadDelayTimer(regx),
+                    0x0A => StoreKeypress(regx),
+                    0x15 => WriteDelayTimer(regx),
+                    0x18 => WriteSoundTimer(regx),
+                    0x1E => AddIndex(regx),
+                    0x29 => SetSpriteLoc(regx),
+                    0x30 => SetLargeSpriteLoc(regx),
+                    0x33 => StoreBCD(regx),
+                    0x3A => SetPitch(regx),
+                    0x55 => StoreRegisters(regx),
+                    0x65 => ReadRegisters(regx),
+                    0x75 => StoreFlags(regx),
+                    0x85 => ReadFlags(regx),
+                    _ => Unknown,
+                }
+            }
+            _ => Unknown,
+        }
+    }
+
+    /// Get the name of the instruction, without its operands
+    pub fn name(&self) -> &'static str {
+        match self {
+            Unknown => "Unknown",
+            Sys(_) => "Sys",
+            Cls => "Cls",
+            Ret => "Ret",
+            Jump(_) => "Jump",
+            JumpWithOffset(_) => "JumpWithOffset",
+            Call(_) => "Call",
+            SkipEqualImm(..) => "SkipEqualImm",
+            SkipEqualReg(..) => "SkipEqualReg",
+            SkipNotEqualImm(..) => "SkipNotEqualImm",
+            SkipNotEqualReg(..) => "SkipNotEqualReg",
+            LoadImm(..) => "LoadImm",
+            LoadReg(..) => "LoadReg",
+            LoadAddress(_) => "LoadAddress",
+            SetSpriteLoc(_) => "SetSpriteLoc",
+            StoreBCD(_) => "StoreBCD",
+            StoreRegisters(_) => "StoreRegisters",
+            ReadRegisters(_) => "ReadRegisters",
+            AddImm(..) => "AddImm",
+            AddReg(..) => "AddReg",
+            AddIndex(_) => "AddIndex",
+            SubReg(..) => "SubReg",
+            SubNReg(..) => "SubNReg",
+            OrReg(..) => "OrReg",
+            AndReg(..) => "AndReg",
+            XorReg(..) => "XorReg",
+            ShiftRightReg(..) => "ShiftRightReg",
+            ShiftLeftReg(..) => "ShiftLeftReg",
+            RandAndImmediate(..) => "RandAndImmediate",
+            Draw(..) => "Draw",
+            SkipIfKeyPressed(_) => "SkipIfKeyPressed",
+            SkipIfKeyNotPressed(_) => "SkipIfKeyNotPressed",
+            StoreKeypress(_) => "StoreKeypress",
+            ReadDelayTimer(_) => "ReadDelayTimer",
+            WriteDelayTimer(_) => "WriteDelayTimer",
+            WriteSoundTimer(_) => "WriteSoundTimer",
+            ScrollDown(_) => "ScrollDown",
+            ScrollLeft => "ScrollLeft",
+            ScrollRight => "ScrollRight",
+            ExitInterpreter => "ExitInterpreter",
+            LowRes => "LowRes",
+            HighRes => "HighRes",
+            DrawLarge(..) => "DrawLarge",
+            SetLargeSpriteLoc(_) => "SetLargeSpriteLoc",
+            StoreFlags(_) => "StoreFlags",
+            ReadFlags(_) => "ReadFlags",
+            ScrollUp(_) => "ScrollUp",
+            StoreRange(..) => "StoreRange",
+            LoadRange(..) => "LoadRange",
+            LoadLongAddress(_) => "LoadLongAddress",
+            SetPlanes(_) => "SetPlanes",
+            LoadAudio => "LoadAudio",
+            SetPitch(_) => "SetPitch",
+        }
+    }
+
+    /// Get the least capable instruction set that has the instruction
+    /// Unknown opcodes aren't in any instruction set, so they are given as CHIP-8.
+    pub fn required_mode(&self) -> EmulationMode {
+        match self {
+            ScrollDown(_) | ScrollLeft | ScrollRight | ExitInterpreter | LowRes | HighRes
+            | DrawLarge(..) | SetLargeSpriteLoc(_) | StoreFlags(_) | ReadFlags(_) => {
+                EmulationMode::SuperChip
+            }
+            ScrollUp(_) | StoreRange(..) | LoadRange(..) | LoadLongAddress(_) | SetPlanes(_)
+            | LoadAudio | SetPitch(_) => EmulationMode::XoChip,
+            _ => EmulationMode::Chip8,
+        }
+    }
+}
+
+impl TryFrom<u16> for Instruction {
+    type Error = u16;
+
+    /// Decode an opcode, failing with the opcode if it isn't a known instruction
+    fn try_from(opcode: u16) -> Result<Self, Self::Error> {
+        match Instruction::decode(opcode) {
+            Unknown => Err(opcode),
+            instruction => Ok(instruction),
+        }
+    }
+}
+
+impl TryFrom<Instruction> for u16 {
+    type Error = Instruction;
+
+    /// Encode an instruction as its opcode, failing with the instruction if it is unknown or has
+    /// an operand too big for its place in the opcode
+    /// The address of a long load follows its opcode in memory, so it is not part of the result.
+    fn try_from(instruction: Instruction) -> Result<Self, Self::Error> {
+        encode(instruction).ok_or(instruction)
+    }
+}
+
+/// Encode an instruction as its opcode, or None if it can't be encoded
+fn encode(instruction: Instruction) -> Option<u16> {
+    // check each operand fits its place in the opcode, and shift it there
+    let addr = |addr: Address| (addr <= 0xFFF).then_some(addr as u16);
+    let x = |register: Register| (register <= 0xF).then_some((register as u16) << 8);
+    let y = |register: Register| (register <= 0xF).then_some((register as u16) << 4);
+    let n = |nibble: Immediate| (nibble <= 0xF).then_some(nibble as u16);
+    let imm = |imm: Immediate| imm as u16;
+
+    let opcode: u16 = match instruction {
+        Unknown => return None,
+        Sys(a) => addr(a)?,
+        Cls => 0x00E0,
+        Ret => 0x00EE,
+        Jump(a) => 0x1000 | addr(a)?,
+        JumpWithOffset(a) => 0xB000 | addr(a)?,
+        Call(a) => 0x2000 | addr(a)?,
+        SkipEqualImm(rx, nn) => 0x3000 | x(rx)? | imm(nn),
+        SkipEqualReg(rx, ry) => 0x5000 | x(rx)? | y(ry)?,
+        SkipNotEqualImm(rx, nn) => 0x4000 | x(rx)? | imm(nn),
+        SkipNotEqualReg(rx, ry) => 0x9000 | x(rx)? | y(ry)?,
+        LoadImm(rx, nn) => 0x6000 | x(rx)? | imm(nn),
+        LoadReg(rx, ry) => 0x8000 | x(rx)? | y(ry)?,
+        LoadAddress(a) => 0xA000 | addr(a)?,
+        SetSpriteLoc(rx) => 0xF029 | x(rx)?,
+        StoreBCD(rx) => 0xF033 | x(rx)?,
+        StoreRegisters(rx) => 0xF055 | x(rx)?,
+        ReadRegisters(rx) => 0xF065 | x(rx)?,
+        AddImm(rx, nn) => 0x7000 | x(rx)? | imm(nn),
+        AddReg(rx, ry) => 0x8004 | x(rx)? | y(ry)?,
+        AddIndex(rx) => 0xF01E | x(rx)?,
+        SubReg(rx, ry) => 0x8005 | x(rx)? | y(ry)?,
+        SubNReg(rx, ry) => 0x8007 | x(rx)? | y(ry)?,
+        OrReg(rx, ry) => 0x8001 | x(rx)? | y(ry)?,
+        AndReg(rx, ry) => 0x8002 | x(rx)? | y(ry)?,
+        XorReg(rx, ry) => 0x8003 | x(rx)? | y(ry)?,
+        ShiftRightReg(rx, ry) => 0x8006 | x(rx)? | y(ry)?,
+        ShiftLeftReg(rx, ry) => 0x800E | x(rx)? | y(ry)?,
+        RandAndImmediate(rx, nn) => 0xC000 | x(rx)? | imm(nn),
+        // a height of 0 is DXY0, which draws a large sprite instead
+        Draw(_, _, 0) => return None,
+        Draw(rx, ry, height) => 0xD000 | x(rx)? | y(ry)? | n(height)?,
+        SkipIfKeyPressed(rx) => 0xE09E | x(rx)?,
+        SkipIfKeyNotPressed(rx) => 0xE0A1 | x(rx)?,
+        StoreKeypress(rx) => 0xF00A | x(rx)?,
+        ReadDelayTimer(rx) => 0xF007 | x(rx)?,
+        WriteDelayTimer(rx) => 0xF015 | x(rx)?,
+        WriteSoundTimer(rx) => 0xF018 | x(rx)?,
+        ScrollDown(rows) => 0x00C0 | n(rows)?,
+        ScrollLeft => 0x00FC,
+        ScrollRight => 0x00FB,
+        ExitInterpreter => 0x00FD,
+        LowRes => 0x00FE,
+        HighRes => 0x00FF,
+        DrawLarge(rx, ry) => 0xD000 | x(rx)? | y(ry)?,
+        SetLargeSpriteLoc(rx) => 0xF030 | x(rx)?,
+        StoreFlags(rx) => 0xF075 | x(rx)?,
+        ReadFlags(rx) => 0xF085 | x(rx)?,
+        ScrollUp(rows) => 0x00D0 | n(rows)?,
+        StoreRange(rx, ry) => 0x5002 | x(rx)? | y(ry)?,
+        LoadRange(rx, ry) => 0x5003 | x(rx)? | y(ry)?,
+        // the address is the next two bytes, so it only has to fit in 16 bits
+        LoadLongAddress(a) if a > 0xFFFF => return None,
+        LoadLongAddress(_) => 0xF000,
+        SetPlanes(planes) => 0xF001 | n(planes)? << 8,
+        LoadAudio => 0xF002,
+        SetPitch(rx) => 0xF03A | x(rx)?,
+    };
+
+    Some(opcode)
+}
+
+impl fmt::Display for Instruction {
+    /// Format the instruction as CHIP-8 assembly, using Cowgod's mnemonics
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // format a register name
+        let reg = |register: &Register| format!("V{:X}", register);
+
+        match self {
+            Unknown => write!(f, "UNKNOWN"),
+            Sys(addr) => write!(f, "SYS {:#05x}", addr),
+            Cls => write!(f, "CLS"),
+            Ret => write!(f, "RET"),
+            Jump(addr) => write!(f, "JP {:#05x}", addr),
+            JumpWithOffset(addr) => write!(f, "JP V0, {:#05x}", addr),
+            Call(addr) => write!(f, "CALL {:#05x}", addr),
+            SkipEqualImm(x, imm) => write!(f, "SE {}, {:#04x}", reg(x), imm),
+            SkipEqualReg(x, y) => write!(f, "SE {}, {}", reg(x), reg(y)),
+            SkipNotEqualImm(x, imm) => write!(f, "SNE {}, {:#04x}", reg(x), imm),
+            SkipNotEqualReg(x, y) => write!(f, "SNE {}, {}", reg(x), reg(y)),
+            LoadImm(x, imm) => write!(f, "LD {}, {:#04x}", reg(x), imm),
+            LoadReg(x, y) => write!(f, "LD {}, {}", reg(x), reg(y)),
+            LoadAddress(addr) => write!(f, "LD I, {:#05x}", addr),
+            SetSpriteLoc(x) => write!(f, "LD F, {}", reg(x)),
+            StoreBCD(x) => write!(f, "LD B, {}", reg(x)),
+            StoreRegisters(x) => write!(f, "LD [I], {}", reg(x)),
+            ReadRegisters(x) => write!(f, "LD {}, [I]", reg(x)),
+            AddImm(x, imm) => write!(f, "ADD {}, {:#04x}", reg(x), imm),
+            AddReg(x, y) => write!(f, "ADD {}, {}", reg(x), reg(y)),
+            AddIndex(x) => write!(f, "ADD I, {}", reg(x)),
+            SubReg(x, y) => write!(f, "SUB {}, {}", reg(x), reg(y)),
+            SubNReg(x, y) => write!(f, "SUBN {}, {}", reg(x), reg(y)),
+            OrReg(x, y) => write!(f, "OR {}, {}", reg(x), reg(y)),
+            AndReg(x, y) => write!(f, "AND {}, {}", reg(x), reg(y)),
+            XorReg(x, y) => write!(f, "XOR {}, {}", reg(x), reg(y)),
+            ShiftRightReg(x, y) => write!(f, "SHR {}, {}", reg(x), reg(y)),
+            ShiftLeftReg(x, y) => write!(f, "SHL {}, {}", reg(x), reg(y)),
+            RandAndImmediate(x, imm) => write!(f, "RND {}, {:#04x}", reg(x), imm),
+            Draw(x, y, n) => write!(f, "DRW {}, {}, {}", reg(x), reg(y), n),
+            SkipIfKeyPressed(x) => write!(f, "SKP {}", reg(x)),
+            SkipIfKeyNotPressed(x) => write!(f, "SKNP {}", reg(x)),
+            StoreKeypress(x) => write!(f, "LD {}, K", reg(x)),
+            ReadDelayTimer(x) => write!(f, "LD {}, DT", reg(x)),
+            WriteDelayTimer(x) => write!(f, "LD DT, {}", reg(x)),
+            WriteSoundTimer(x) => write!(f, "LD ST, {}", reg(x)),
+            ScrollDown(n) => write!(f, "SCD {}", n),
+            ScrollLeft => write!(f, "SCL"),
+            ScrollRight => write!(f, "SCR"),
+            ExitInterpreter => write!(f, "EXIT"),
+            LowRes => write!(f, "LOW"),
+            HighRes => write!(f, "HIGH"),
+            DrawLarge(x, y) => write!(f, "DRW {}, {}, 0", reg(x), reg(y)),
+            SetLargeSpriteLoc(x) => write!(f, "LD HF, {}", reg(x)),
+            StoreFlags(x) => write!(f, "LD R, {}", reg(x)),
+            ReadFlags(x) => write!(f, "LD {}, R", reg(x)),
+            ScrollUp(n) => write!(f, "SCU {}", n),
+            StoreRange(x, y) => write!(f, "SAVE {} - {}", reg(x), reg(y)),
+            LoadRange(x, y) => write!(f, "LOAD {} - {}", reg(x), reg(y)),
+            LoadLongAddress(addr) => write!(f, "LD I, LONG {:#06x}", addr),
+            SetPlanes(n) => write!(f, "PLANE {}", n),
+            LoadAudio => write!(f, "AUDIO"),
+            SetPitch(x) => write!(f, "PITCH {}", reg(x)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Instruction;
+    use super::Instruction::*;
+    use alloc::string::ToString;
+
+    // every instruction, with the assembly it should be shown as
+    const MNEMONICS: [(Instruction, &str); 53] = [
+        (Unknown, "UNKNOWN"),
+        (Sys(0x1a2), "SYS 0x1a2"),
+        (Cls, "CLS"),
+        (Ret, "RET"),
+        (Jump(0x2a8), "JP 0x2a8"),
+        (JumpWithOffset(0x300), "JP V0, 0x300"),
+        (Call(0x20c), "CALL 0x20c"),
+        (SkipEqualImm(0xA, 0x0f), "SE VA, 0x0f"),
+        (SkipEqualReg(0x1, 0xB), "SE V1, VB"),
+        (SkipNotEqualImm(0x2, 0xff), "SNE V2, 0xff"),
+        (SkipNotEqualReg(0x3, 0xC), "SNE V3, VC"),
+        (LoadImm(0x4, 0x42), "LD V4, 0x42"),
+        (LoadReg(0x5, 0xD), "LD V5, VD"),
+        (LoadAddress(0x050), "LD I, 0x050"),
+        (SetSpriteLoc(0x6), "LD F, V6"),
+        (StoreBCD(0x7), "LD B, V7"),
+        (StoreRegisters(0x8), "LD [I], V8"),
+        (ReadRegisters(0x9), "LD V9, [I]"),
+        (AddImm(0xE, 0x01), "ADD VE, 0x01"),
+        (AddReg(0x0, 0xF), "ADD V0, VF"),
+        (AddIndex(0x1), "ADD I, V1"),
+        (SubReg(0x2, 0x3), "SUB V2, V3"),
+        (SubNReg(0x4, 0x5), "SUBN V4, V5"),
+        (OrReg(0x6, 0x7), "OR V6, V7"),
+        (AndReg(0x8, 0x9), "AND V8, V9"),
+        (XorReg(0xA, 0xB), "XOR VA, VB"),
+        (ShiftRightReg(0xC, 0xD), "SHR VC, VD"),
+        (ShiftLeftReg(0xE, 0xF), "SHL VE, VF"),
+        (RandAndImmediate(0x0, 0x80), "RND V0, 0x80"),
+        (Draw(0x1, 0x2, 0x5), "DRW V1, V2, 5"),
+        (SkipIfKeyPressed(0x3), "SKP V3"),
+        (SkipIfKeyNotPressed(0x4), "SKNP V4"),
+        (StoreKeypress(0x5), "LD V5, K"),
+        (ReadDelayTimer(0x6), "LD V6, DT"),
+        (WriteDelayTimer(0x7), "LD DT, V7"),
+        (WriteSoundTimer(0x8), "LD ST, V8"),
+        (ScrollDown(0x4), "SCD 4"),
+        (ScrollLeft, "SCL"),
+        (ScrollRight, "SCR"),
+        (ExitInterpreter, "EXIT"),
+        (LowRes, "LOW"),
+        (HighRes, "HIGH"),
+        (DrawLarge(0x9, 0xA), "DRW V9, VA, 0"),
+        (SetLargeSpriteLoc(0xB), "LD HF, VB"),
+        (StoreFlags(0xC), "LD R, VC"),
+        (ReadFlags(0xD), "LD VD, R"),
+        (ScrollUp(0x2), "SCU 2"),
+        (StoreRange(0x1, 0x4), "SAVE V1 - V4"),
+        (LoadRange(0x2, 0x5), "LOAD V2 - V5"),
+        (LoadLongAddress(0x1234), "LD I, LONG 0x1234"),
+        (SetPlanes(0x3), "PLANE 3"),
+        (LoadAudio, "AUDIO"),
+        (SetPitch(0xE), "PITCH VE"),
+    ];
+
+    #[test]
+    fn display_mnemonics() {
+        for (instruction, mnemonic) in MNEMONICS {
+            assert_eq!(instruction.to_string(), mnemonic, "{:?}", instruction);
+        }
+    }
+
+    #[test]
+    fn encode_and_decode_each_instruction() {
+        for (instruction, _) in &MNEMONICS[1..] {
+            let opcode = u16::try_from(*instruction).expect("the instruction should encode");
+            let decoded = match Instruction::decode(opcode) {
+                // the address of a long load comes from the bytes after the opcode
+                LoadLongAddress(_) => LoadLongAddress(0x1234),
+                decoded => decoded,
+            };
+            assert_eq!(decoded, *instruction, "{:#06x}", opcode);
+        }
+    }
+
+    #[test]
+    fn decode_and_encode_each_opcode() {
+        // some opcodes ignore a nibble, such as the N of 9XYN, so they are encoded without it
+        for opcode in 0..=0xFFFF {
+            let instruction = Instruction::decode(opcode);
+            match u16::try_from(instruction) {
+                Ok(encoded) => assert_eq!(Instruction::decode(encoded), instruction),
+                Err(_) => assert_eq!(instruction, Unknown, "{:#06x}", opcode),
+            }
+        }
+    }
+
+    #[test]
+    fn refuse_to_encode_operands_that_dont_fit() {
+        let instructions = [
+            Sys(0x1000),
+            Jump(0x1000),
+            JumpWithOffset(0x1000),
+            Call(0x1000),
+            LoadAddress(0x1000),
+            LoadLongAddress(0x10000),
+            SkipEqualImm(0x10, 0x00),
+            SkipEqualReg(0x0, 0x10),
+            LoadReg(0x10, 0x0),
+            AddIndex(0x10),
+            Draw(0x0, 0x0, 0x10),
+            Draw(0x0, 0x0, 0x0),
+            DrawLarge(0x0, 0x10),
+            ScrollDown(0x10),
+            ScrollUp(0x10),
+            SetPlanes(0x10),
+            StoreRange(0x10, 0x0),
+            SetPitch(0x10),
+        ];
+        for instruction in instructions {
+            assert_eq!(u16::try_from(instruction), Err(instruction));
+        }
+    }
+}
@@ -0,0 +1,21 @@
+//! A CHIP-8 virtual machine, with SUPER-CHIP and XO-CHIP extensions
+//!
+//! The [`chip8::Chip8`] type holds the whole machine. Make one with a [`chip8::Chip8Builder`],
+//! then call [`chip8::Chip8::do_next_instruction`] to run the program and
+//! [`chip8::Chip8::do_frame`] sixty times a second to count down the timers and get the display.
+//!
+//! Without the default `std` feature, the crate is `no_std` and only needs `alloc`. ROMs and fonts
+//! can then only be loaded from bytes, and the random numbers used by CXNN must be seeded.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+#![warn(missing_docs)]
+
+extern crate alloc;
+
+pub mod analysis;
+pub mod chip8;
+pub mod disasm;
+pub mod error;
+pub mod input;
+pub mod instructions;
+pub mod validator;
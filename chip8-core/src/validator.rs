@@ -0,0 +1,230 @@
+//! Checking ROMs for likely bugs before they are run
+
+use crate::chip8::PROGMEM_START;
+use crate::instructions::Instruction;
+use crate::instructions::Instruction::*;
+use alloc::format;
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+
+/// How serious a problem found in a ROM is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Something that will almost certainly crash the program
+    Error,
+
+    /// Something that may be a bug, but could be intended
+    Warning,
+
+    /// Something worth knowing about the ROM, which is not a problem in itself
+    Info,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Info => write!(f, "info"),
+        }
+    }
+}
+
+/// A problem found in a ROM
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationWarning {
+    /// How serious the problem is
+    pub severity: Severity,
+
+    /// The address of the instruction or byte the problem is at
+    pub address: u16,
+
+    /// A description of the problem
+    pub message: String,
+}
+
+impl fmt::Display for ValidationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at {:#05x}: {}", self.severity, self.address, self.message)
+    }
+}
+
+/// Finds likely bugs in a ROM without running it
+/// Only the instructions that can be reached from the start of the program are checked, by
+/// following every jump, call and skip.
+pub struct RomValidator;
+
+impl RomValidator {
+    /// Check a ROM, returning the problems found in order of address
+    pub fn validate(rom: &[u8]) -> Vec<ValidationWarning> {
+        let mut warnings = Vec::new();
+        let start = PROGMEM_START as usize;
+        let end = start + rom.len();
+
+        if !rom.len().is_multiple_of(2) {
+            warnings.push(ValidationWarning {
+                severity: Severity::Warning,
+                address: (end - 1) as u16,
+                message: String::from(
+                    "the ROM has an odd length, so its last byte is not a whole instruction",
+                ),
+            });
+        }
+
+        // each address can be reached both inside and outside of a subroutine
+        let mut visited = vec![[false; 2]; rom.len()];
+        let mut reached = vec![false; rom.len()];
+        let mut pending = vec![(start, false)];
+
+        while let Some((mut addr, in_subroutine)) = pending.pop() {
+            // I is only followed along a straight run of instructions
+            let mut i_in_font = false;
+
+            loop {
+                if addr + 1 >= end {
+                    warnings.push(ValidationWarning {
+                        severity: Severity::Warning,
+                        address: addr as u16,
+                        message: String::from("execution runs past the end of the ROM"),
+                    });
+                    break;
+                }
+
+                let offset = addr - start;
+                if visited[offset][in_subroutine as usize] {
+                    break;
+                }
+                visited[offset][in_subroutine as usize] = true;
+                reached[offset] = true;
+                reached[offset + 1] = true;
+
+                let opcode = u16::from_be_bytes([rom[offset], rom[offset + 1]]);
+                let mut next = addr + 2;
+
+                match Instruction::decode(opcode) {
+                    Unknown => {
+                        warnings.push(ValidationWarning {
+                            severity: Severity::Error,
+                            address: addr as u16,
+                            message: format!("unknown opcode {:04X}", opcode),
+                        });
+                        break;
+                    }
+                    Ret => {
+                        if !in_subroutine {
+                            warnings.push(ValidationWarning {
+                                severity: Severity::Error,
+                                address: addr as u16,
+                                message: String::from("RET with no CALL before it"),
+                            });
+                        }
+                        break;
+                    }
+                    ExitInterpreter => break,
+                    // 0000 is usually padding, or a placeholder for code written as the ROM runs
+                    Sys(0) => break,
+                    Jump(target) => {
+                        if Self::check_target(&mut warnings, addr, target, start..end, "JP") {
+                            pending.push((target, in_subroutine));
+                        }
+                        break;
+                    }
+                    JumpWithOffset(_) => {
+                        warnings.push(ValidationWarning {
+                            severity: Severity::Info,
+                            address: addr as u16,
+                            message: String::from("the targets of JP V0 are not checked"),
+                        });
+                        break;
+                    }
+                    Call(target) => {
+                        if Self::check_target(&mut warnings, addr, target, start..end, "CALL") {
+                            pending.push((target, true));
+                        }
+
+                        // the subroutine may have changed I
+                        i_in_font = false;
+                    }
+                    SkipEqualImm(..)
+                    | SkipEqualReg(..)
+                    | SkipNotEqualImm(..)
+                    | SkipNotEqualReg(..)
+                    | SkipIfKeyPressed(_)
+                    | SkipIfKeyNotPressed(_) => {
+                        pending.push((addr + 4, in_subroutine));
+                    }
+                    LoadAddress(target) => i_in_font = target < start,
+                    LoadLongAddress(_) => {
+                        // the address is the word after the opcode
+                        if addr + 3 < end {
+                            let target = u16::from_be_bytes([rom[offset + 2], rom[offset + 3]]);
+                            i_in_font = (target as usize) < start;
+                            reached[offset + 2] = true;
+                            reached[offset + 3] = true;
+                        }
+                        next = addr + 4;
+                    }
+                    SetSpriteLoc(_) | SetLargeSpriteLoc(_) => i_in_font = true,
+                    StoreRegisters(_) | StoreRange(..) if i_in_font => {
+                        warnings.push(ValidationWarning {
+                            severity: Severity::Error,
+                            address: addr as u16,
+                            message: String::from("registers are stored with I in font memory"),
+                        });
+                        i_in_font = false;
+                    }
+                    AddIndex(_) | StoreRegisters(_) | ReadRegisters(_) => i_in_font = false,
+                    _ => {}
+                }
+
+                addr = next;
+            }
+        }
+
+        // anything that can't be reached is probably sprites, or other data
+        let unreached = reached.iter().filter(|&&reached| !reached).count();
+        if unreached > 0 {
+            warnings.push(ValidationWarning {
+                severity: Severity::Info,
+                address: PROGMEM_START,
+                message: format!("{} bytes are never executed, and may be data", unreached),
+            });
+        }
+
+        // the same problem is found again when code is reached from inside a subroutine
+        warnings.sort_by(|a, b| a.address.cmp(&b.address).then(a.message.cmp(&b.message)));
+        warnings.dedup();
+        warnings
+    }
+
+    /// Check the target of a jump or call, returning whether it is inside the ROM
+    fn check_target(
+        warnings: &mut Vec<ValidationWarning>,
+        addr: usize,
+        target: usize,
+        rom: Range<usize>,
+        mnemonic: &str,
+    ) -> bool {
+        if !rom.contains(&target) {
+            warnings.push(ValidationWarning {
+                severity: Severity::Error,
+                address: addr as u16,
+                message: format!("{} to {:#05x}, which is outside the ROM", mnemonic, target),
+            });
+            return false;
+        }
+
+        if !target.is_multiple_of(2) {
+            warnings.push(ValidationWarning {
+                severity: Severity::Warning,
+                address: addr as u16,
+                message: format!("{} to {:#05x}, which is not an even offset", mnemonic, target),
+            });
+        }
+
+        true
+    }
+}
@@ -0,0 +1,112 @@
+//! Running known-good test ROMs and checking the display they leave against golden files
+//!
+//! The ROMs are Timendus' CHIP-8 test suite and Corax89's opcode test, kept in `tests/roms`, where
+//! the build script downloads any that are missing when the `integration-tests` feature is on.
+//! Each golden file in `tests/golden` is the display drawn with `#` for pixels that are on. A
+//! missing golden file is written from the current display, so new ROMs can be added by running
+//! the tests once and checking the file that was written.
+
+use chip8_core::chip8::{Chip8, Chip8Builder};
+use std::fs;
+use std::path::PathBuf;
+
+// where the test suite reads the test to run from 0x1FF, as an offset into the ROM
+const TEST_SUITE_SELECTION: usize = 0x50;
+
+/// Get the path of a file in the tests directory
+fn test_path(dir: &str, name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join(dir)
+        .join(name)
+}
+
+/// Load a test ROM, with some of its bytes patched first
+fn load(rom: &str, patches: &[(usize, &[u8])]) -> Chip8 {
+    let mut bytes = fs::read(test_path("roms", rom)).expect("the test ROM should be readable");
+    for &(offset, patch) in patches {
+        bytes[offset..offset + patch.len()].copy_from_slice(patch);
+    }
+    Chip8Builder::default()
+        .rom_bytes(&bytes)
+        .build()
+        .expect("the test ROM should load")
+}
+
+/// Load the test suite, running one of its tests instead of showing its menu
+fn load_test_suite(test: u8) -> Chip8 {
+    // V0 = the test, then add 0 to V0, in place of I = 0x1FF and reading V0 from there
+    let patch = [0x60, test, 0x70, 0x00];
+    load("chip8-test-suite.ch8", &[(TEST_SUITE_SELECTION, &patch)])
+}
+
+/// Run a number of instructions, and get the display as text once the frame is shown
+fn run_and_capture(chip8: &mut Chip8, cycles: u32) -> String {
+    for cycle in 0..cycles {
+        if let Err(e) = chip8.do_next_instruction() {
+            panic!("instruction {} failed: {}", cycle, e);
+        }
+    }
+    chip8.do_frame();
+
+    let (width, height) = chip8.display_size();
+    (0..height)
+        .map(|y| {
+            let row: String = (0..width)
+                .map(|x| match chip8.peek_vram(x, y) {
+                    Some(true) => '#',
+                    _ => '.',
+                })
+                .collect();
+            row + "\n"
+        })
+        .collect()
+}
+
+/// Check a display against its golden file, writing the file if there isn't one yet
+fn check_golden(name: &str, display: &str) {
+    let path = test_path("golden", &format!("{}.txt", name));
+    match fs::read_to_string(&path) {
+        Ok(golden) => assert!(
+            golden == display,
+            "the display differs from {}\nexpected:\n{}\nactual:\n{}",
+            path.display(),
+            golden,
+            display
+        ),
+        Err(_) => {
+            fs::write(&path, display).expect("the golden file should be writable");
+            eprintln!("wrote {}", path.display());
+        }
+    }
+}
+
+#[test]
+fn corax89_opcode_test() {
+    let mut chip8 = load("test_opcode.ch8", &[]);
+    check_golden("test_opcode", &run_and_capture(&mut chip8, 1000));
+}
+
+#[test]
+fn test_suite_splash_screen() {
+    let mut chip8 = load("chip8-test-suite.ch8", &[]);
+    check_golden("test_suite_splash", &run_and_capture(&mut chip8, 100));
+}
+
+#[test]
+fn test_suite_ibm_logo() {
+    let mut chip8 = load_test_suite(1);
+    check_golden("test_suite_ibm_logo", &run_and_capture(&mut chip8, 1000));
+}
+
+#[test]
+fn test_suite_opcodes() {
+    let mut chip8 = load_test_suite(2);
+    check_golden("test_suite_opcodes", &run_and_capture(&mut chip8, 1000));
+}
+
+#[test]
+fn test_suite_flags() {
+    let mut chip8 = load_test_suite(3);
+    check_golden("test_suite_flags", &run_and_capture(&mut chip8, 5000));
+}
@@ -0,0 +1,79 @@
+// Draws the display like a phosphor screen, with fading, bloom and scanlines.
+// The decay pass runs first, fading the glow texture towards the current display. The bloom pass
+// then blurs the glow, adds it back, and darkens every other row into the output texture.
+
+struct Params {
+    // the color for each combination of display planes
+    palette: array<vec4<f32>, 16>,
+
+    // the size of the display, in CHIP-8 pixels
+    resolution: vec2<u32>,
+
+    // the size of the glow and output textures, in texels
+    texture_size: vec2<u32>,
+
+    // the brightness kept by a pixel each frame after it turns off
+    decay: f32,
+};
+
+struct Vram {
+    // the color of each pixel, eight to a word, one row of the hires display after another
+    pixels: array<u32, 1024>,
+};
+
+@group(0) @binding(0)
+var<uniform> params: Params;
+
+@group(0) @binding(1)
+var<storage, read> vram: Vram;
+
+@group(0) @binding(2)
+var glow: texture_storage_2d<rgba8unorm, read_write>;
+
+@group(0) @binding(3)
+var output: texture_storage_2d<rgba8unorm, write>;
+
+@compute @workgroup_size(8, 8, 1)
+fn decay(@builtin(global_invocation_id) id: vec3<u32>) {
+    let coords = vec2<i32>(id.xy);
+
+    // find the pixel under this texel, and the color it is now
+    let pixel = id.xy * params.resolution / params.texture_size;
+    let index = pixel.y * 128u + pixel.x;
+    let word = vram.pixels[index / 8u];
+    let color = params.palette[(word >> ((index % 8u) * 4u)) & 0xfu];
+
+    // lit pixels light up at once, but unlit pixels fade out
+    let previous = textureLoad(glow, coords);
+    textureStore(glow, coords, max(color, previous * params.decay));
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn bloom(@builtin(global_invocation_id) id: vec3<u32>) {
+    let coords = vec2<i32>(id.xy);
+    let last = vec2<i32>(params.texture_size) - vec2<i32>(1, 1);
+    let sigma = 1.5;
+
+    // blur the glow around this texel, out to a little under three standard deviations
+    var blurred = vec4<f32>(0.0, 0.0, 0.0, 0.0);
+    var total = 0.0;
+    for (var dy = -4; dy <= 4; dy = dy + 1) {
+        for (var dx = -4; dx <= 4; dx = dx + 1) {
+            let sample_coords = clamp(coords + vec2<i32>(dx, dy), vec2<i32>(0, 0), last);
+            let weight = exp(-f32(dx * dx + dy * dy) / (2.0 * sigma * sigma));
+            blurred = blurred + textureLoad(glow, sample_coords) * weight;
+            total = total + weight;
+        }
+    }
+
+    // light spills over from bright pixels onto their neighbours
+    let spill = 0.5 * blurred.rgb / total;
+    var color = min(textureLoad(glow, coords).rgb + spill, vec3<f32>(1.0, 1.0, 1.0));
+
+    // darken every other row, like the gaps between the scanlines of a CRT
+    if (id.y % 2u == 1u) {
+        color = color * 0.85;
+    }
+
+    textureStore(output, coords, vec4<f32>(color, 1.0));
+}
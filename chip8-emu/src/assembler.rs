@@ -0,0 +1,519 @@
+use crate::chip8::{MEM_SIZE, PROGMEM_START};
+use std::collections::HashMap;
+use std::fmt;
+
+// mnemonics and directives that the assembler understands
+const MNEMONICS: [&str; 33] = [
+    "CLS", "RET", "SYS", "JP", "CALL", "SE", "SNE", "LD", "ADD", "OR", "AND", "XOR", "SUB", "SHR",
+    "SUBN", "SHL", "RND", "DRW", "SKP", "SKNP", "SCD", "SCR", "SCL", "EXIT", "LOW", "HIGH", "SCU",
+    "SAVE", "LOAD", "PLANE", "AUDIO", "PITCH", "DB",
+];
+
+// names with a special meaning as operands, which can't be used for labels or constants
+const KEYWORDS: [&str; 11] = ["I", "[I]", "DT", "ST", "K", "F", "HF", "B", "R", "LONG", "EQU"];
+
+/// Errors that can occur while assembling a program
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum AssembleError {
+    /// The mnemonic is not a known instruction or directive
+    UnknownMnemonic(String),
+
+    /// An operand is not valid for its instruction
+    InvalidOperand { line: usize, token: String },
+
+    /// A label or constant is used without being defined
+    UndefinedLabel(String),
+
+    /// A label or constant is defined more than once
+    DuplicateLabel(String),
+
+    /// The program does not fit in program memory
+    OutputTooLarge,
+}
+
+impl fmt::Display for AssembleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssembleError::UnknownMnemonic(mnemonic) => {
+                write!(f, "unknown mnemonic '{}'", mnemonic)
+            }
+            AssembleError::InvalidOperand { line, token } => {
+                write!(f, "invalid operand '{}' on line {}", token, line)
+            }
+            AssembleError::UndefinedLabel(label) => write!(f, "undefined label '{}'", label),
+            AssembleError::DuplicateLabel(label) => {
+                write!(f, "label '{}' is already defined", label)
+            }
+            AssembleError::OutputTooLarge => write!(f, "program is too large to fit in memory"),
+        }
+    }
+}
+
+impl std::error::Error for AssembleError {}
+
+/// An instruction or directive, with any label and comment removed
+struct Statement {
+    /// The line of the source that the statement is on, starting from 1
+    line: usize,
+
+    /// The mnemonic, in upper case
+    mnemonic: String,
+
+    /// The operands, as written
+    operands: Vec<String>,
+}
+
+impl Statement {
+    /// Get the number of bytes the statement assembles to
+    fn size(&self) -> usize {
+        match self.mnemonic.as_str() {
+            "DB" => self.operands.len(),
+            "LD" if self.operands.len() == 2 && is_long(&self.operands[1]) => 4,
+            _ => 2,
+        }
+    }
+}
+
+/// Assemble CHIP-8 assembly into a ROM
+/// The syntax is the same as the disassembler's output, with the addition of `LABEL:` label
+/// definitions, `NAME EQU value` constants, `DB` byte literals, and `;` comments. Labels can be
+/// used before they are defined.
+pub fn assemble(source: &str) -> Result<Vec<u8>, AssembleError> {
+    let mut symbols: HashMap<String, u16> = HashMap::new();
+    let mut statements: Vec<Statement> = Vec::new();
+
+    // first pass: find the address of every label, and the value of every constant
+    let mut address: usize = PROGMEM_START as usize;
+    for (index, raw_line) in source.lines().enumerate() {
+        let line = index + 1;
+
+        // remove the comment
+        let mut code = raw_line.split(';').next().unwrap_or_default().trim();
+
+        // define the label, if there is one
+        if let Some((label, rest)) = code.split_once(':') {
+            define(&mut symbols, label.trim(), address as u16, line)?;
+            code = rest.trim();
+        }
+
+        if code.is_empty() {
+            continue;
+        }
+
+        // define the constant, if this is one
+        let words: Vec<&str> = code.split_whitespace().collect();
+        if words.len() == 3 && words[1].eq_ignore_ascii_case("EQU") {
+            let value = Operands { symbols: &symbols, line }.value(words[2], 0xFFFF)?;
+            define(&mut symbols, words[0], value, line)?;
+            continue;
+        }
+
+        // split the mnemonic from the operands
+        // a register range, like V1 - V4, is a single operand
+        let (mnemonic, operands) = code.split_once(char::is_whitespace).unwrap_or((code, ""));
+        let statement = Statement {
+            line,
+            mnemonic: mnemonic.to_uppercase(),
+            operands: operands
+                .split(',')
+                .map(|operand| operand.trim().to_string())
+                .filter(|operand| !operand.is_empty())
+                .collect(),
+        };
+
+        address += statement.size();
+        if address > MEM_SIZE {
+            return Err(AssembleError::OutputTooLarge);
+        }
+
+        statements.push(statement);
+    }
+
+    // second pass: assemble each statement, now that every label is known
+    let mut rom: Vec<u8> = Vec::new();
+    for statement in &statements {
+        rom.extend(encode(statement, &symbols)?);
+    }
+
+    Ok(rom)
+}
+
+/// Define a label or constant
+fn define(
+    symbols: &mut HashMap<String, u16>,
+    name: &str,
+    value: u16,
+    line: usize,
+) -> Result<(), AssembleError> {
+    // names must look like identifiers, and can't be mistaken for a mnemonic or register
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && register(name).is_none()
+        && !MNEMONICS.contains(&name.to_uppercase().as_str())
+        && !KEYWORDS.contains(&name.to_uppercase().as_str());
+    if !valid {
+        return Err(AssembleError::InvalidOperand {
+            line,
+            token: name.to_string(),
+        });
+    }
+
+    if symbols.insert(name.to_string(), value).is_some() {
+        return Err(AssembleError::DuplicateLabel(name.to_string()));
+    }
+
+    Ok(())
+}
+
+/// Check if an operand is a long address, as in `LD I, LONG 0x1234`
+fn is_long(operand: &str) -> bool {
+    matches!(operand.get(..5), Some(prefix) if prefix.eq_ignore_ascii_case("LONG "))
+}
+
+/// Get the number of a register operand, like V3
+fn register(operand: &str) -> Option<u16> {
+    let digit = operand.strip_prefix(['V', 'v'])?;
+    if digit.len() != 1 {
+        return None;
+    }
+    u16::from_str_radix(digit, 16).ok()
+}
+
+/// Parse a number, in hex with a leading 0x or #, in binary with a leading 0b, or in decimal
+fn number(operand: &str) -> Option<u16> {
+    let lower = operand.to_lowercase();
+    if let Some(hex) = lower.strip_prefix("0x").or_else(|| lower.strip_prefix('#')) {
+        u16::from_str_radix(hex, 16).ok()
+    } else if let Some(binary) = lower.strip_prefix("0b") {
+        u16::from_str_radix(binary, 2).ok()
+    } else {
+        lower.parse().ok()
+    }
+}
+
+/// Reads the operands of a statement
+struct Operands<'a> {
+    symbols: &'a HashMap<String, u16>,
+    line: usize,
+}
+
+impl<'a> Operands<'a> {
+    /// Make an error for an operand
+    fn invalid(&self, operand: &str) -> AssembleError {
+        AssembleError::InvalidOperand {
+            line: self.line,
+            token: operand.to_string(),
+        }
+    }
+
+    /// Read a register operand
+    fn register(&self, operand: &str) -> Result<u16, AssembleError> {
+        register(operand).ok_or_else(|| self.invalid(operand))
+    }
+
+    /// Read a register range operand, like V1 - V4, as its first and last registers
+    fn register_range(&self, operand: &str) -> Result<(u16, u16), AssembleError> {
+        let (first, last) = operand
+            .split_once('-')
+            .ok_or_else(|| self.invalid(operand))?;
+        Ok((self.register(first.trim())?, self.register(last.trim())?))
+    }
+
+    /// Read a number, label, or constant no larger than `max`
+    fn value(&self, operand: &str, max: u16) -> Result<u16, AssembleError> {
+        let value = match number(operand) {
+            Some(value) => value,
+            None if operand.starts_with(|c: char| c.is_ascii_digit()) => {
+                return Err(self.invalid(operand));
+            }
+            None => *self
+                .symbols
+                .get(operand)
+                .ok_or_else(|| AssembleError::UndefinedLabel(operand.to_string()))?,
+        };
+
+        if value > max {
+            return Err(self.invalid(operand));
+        }
+
+        Ok(value)
+    }
+}
+
+/// Assemble a single statement
+fn encode(
+    statement: &Statement,
+    symbols: &HashMap<String, u16>,
+) -> Result<Vec<u8>, AssembleError> {
+    let ops = Operands {
+        symbols,
+        line: statement.line,
+    };
+    let operands: Vec<&str> = statement.operands.iter().map(String::as_str).collect();
+    let keywords: Vec<String> = operands.iter().map(|op| op.to_uppercase()).collect();
+    let keywords: Vec<&str> = keywords.iter().map(String::as_str).collect();
+
+    // byte literals
+    if statement.mnemonic == "DB" {
+        return operands
+            .iter()
+            .map(|op| ops.value(op, 0xFF).map(|value| value as u8))
+            .collect();
+    }
+
+    // XO-CHIP long load, which is followed by the address
+    let long_load = keywords.len() == 2 && keywords[0] == "I" && is_long(operands[1]);
+    if statement.mnemonic == "LD" && long_load {
+        let addr = ops.value(operands[1][5..].trim(), 0xFFFF)?;
+        let [high, low] = addr.to_be_bytes();
+        return Ok(vec![0xF0, 0x00, high, low]);
+    }
+
+    let opcode: u16 = match (statement.mnemonic.as_str(), keywords.as_slice()) {
+        // no operands
+        ("CLS", []) => 0x00E0,
+        ("RET", []) => 0x00EE,
+        ("SCR", []) => 0x00FB,
+        ("SCL", []) => 0x00FC,
+        ("EXIT", []) => 0x00FD,
+        ("LOW", []) => 0x00FE,
+        ("HIGH", []) => 0x00FF,
+        ("AUDIO", []) => 0xF002,
+
+        // immediates and addresses
+        ("SCD", [_]) => 0x00C0 | ops.value(operands[0], 0xF)?,
+        ("SCU", [_]) => 0x00D0 | ops.value(operands[0], 0xF)?,
+        ("PLANE", [_]) => 0xF001 | ops.value(operands[0], 0xF)? << 8,
+        ("SYS", [_]) => ops.value(operands[0], 0xFFF)?,
+        ("JP", ["V0", _]) => 0xB000 | ops.value(operands[1], 0xFFF)?,
+        ("JP", [_]) => 0x1000 | ops.value(operands[0], 0xFFF)?,
+        ("CALL", [_]) => 0x2000 | ops.value(operands[0], 0xFFF)?,
+
+        // one register
+        ("SKP", [_]) => 0xE09E | ops.register(operands[0])? << 8,
+        ("SKNP", [_]) => 0xE0A1 | ops.register(operands[0])? << 8,
+        ("PITCH", [_]) => 0xF03A | ops.register(operands[0])? << 8,
+        ("SHR", [_]) => 0x8006 | ops.register(operands[0])? << 8,
+        ("SHL", [_]) => 0x800E | ops.register(operands[0])? << 8,
+        ("ADD", ["I", _]) => 0xF01E | ops.register(operands[1])? << 8,
+
+        // a register and either a register or an immediate
+        ("SE", [_, _]) | ("SNE", [_, _]) | ("ADD", [_, _]) => {
+            let x = ops.register(operands[0])? << 8;
+            let reg_op = match statement.mnemonic.as_str() {
+                "SE" => 0x5000,
+                "SNE" => 0x9000,
+                _ => 0x8004,
+            };
+            let imm_op = match statement.mnemonic.as_str() {
+                "SE" => 0x3000,
+                "SNE" => 0x4000,
+                _ => 0x7000,
+            };
+            match register(operands[1]) {
+                Some(y) => reg_op | x | y << 4,
+                None => imm_op | x | ops.value(operands[1], 0xFF)?,
+            }
+        }
+        ("RND", [_, _]) => {
+            0xC000 | ops.register(operands[0])? << 8 | ops.value(operands[1], 0xFF)?
+        }
+
+        // two registers
+        ("OR", [_, _])
+        | ("AND", [_, _])
+        | ("XOR", [_, _])
+        | ("SUB", [_, _])
+        | ("SHR", [_, _])
+        | ("SUBN", [_, _])
+        | ("SHL", [_, _]) => {
+            let base = match statement.mnemonic.as_str() {
+                "OR" => 0x8001,
+                "AND" => 0x8002,
+                "XOR" => 0x8003,
+                "SUB" => 0x8005,
+                "SHR" => 0x8006,
+                "SUBN" => 0x8007,
+                _ => 0x800E,
+            };
+            base | ops.register(operands[0])? << 8 | ops.register(operands[1])? << 4
+        }
+
+        // register ranges
+        ("SAVE", [_]) | ("LOAD", [_]) => {
+            let base = match statement.mnemonic.as_str() {
+                "SAVE" => 0x5002,
+                _ => 0x5003,
+            };
+            let (first, last) = ops.register_range(operands[0])?;
+            base | first << 8 | last << 4
+        }
+        ("DRW", [_, _, _]) => {
+            0xD000
+                | ops.register(operands[0])? << 8
+                | ops.register(operands[1])? << 4
+                | ops.value(operands[2], 0xF)?
+        }
+
+        // loads from special registers
+        ("LD", [_, "DT"]) => 0xF007 | ops.register(operands[0])? << 8,
+        ("LD", [_, "K"]) => 0xF00A | ops.register(operands[0])? << 8,
+        ("LD", [_, "[I]"]) => 0xF065 | ops.register(operands[0])? << 8,
+        ("LD", [_, "R"]) => 0xF085 | ops.register(operands[0])? << 8,
+
+        // stores to special registers
+        ("LD", ["I", _]) => 0xA000 | ops.value(operands[1], 0xFFF)?,
+        ("LD", ["DT", _]) => 0xF015 | ops.register(operands[1])? << 8,
+        ("LD", ["ST", _]) => 0xF018 | ops.register(operands[1])? << 8,
+        ("LD", ["F", _]) => 0xF029 | ops.register(operands[1])? << 8,
+        ("LD", ["HF", _]) => 0xF030 | ops.register(operands[1])? << 8,
+        ("LD", ["B", _]) => 0xF033 | ops.register(operands[1])? << 8,
+        ("LD", ["[I]", _]) => 0xF055 | ops.register(operands[1])? << 8,
+        ("LD", ["R", _]) => 0xF075 | ops.register(operands[1])? << 8,
+
+        // loads into general purpose registers
+        ("LD", [_, _]) => {
+            let x = ops.register(operands[0])? << 8;
+            match register(operands[1]) {
+                Some(y) => 0x8000 | x | y << 4,
+                None => 0x6000 | x | ops.value(operands[1], 0xFF)?,
+            }
+        }
+
+        // known mnemonics with the wrong number of operands
+        (mnemonic, _) if MNEMONICS.contains(&mnemonic) => {
+            return Err(ops.invalid(&statement.operands.join(", ")));
+        }
+        (mnemonic, _) => return Err(AssembleError::UnknownMnemonic(mnemonic.to_string())),
+    };
+
+    Ok(opcode.to_be_bytes().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{assemble, AssembleError};
+
+    // every instruction, as written, with the bytes it should assemble to
+    const INSTRUCTIONS: [(&str, &[u8]); 54] = [
+        ("SYS 0x1a2", &[0x01, 0xA2]),
+        ("CLS", &[0x00, 0xE0]),
+        ("RET", &[0x00, 0xEE]),
+        ("JP 0x2a8", &[0x12, 0xA8]),
+        ("JP V0, 0x300", &[0xB3, 0x00]),
+        ("CALL 0x20c", &[0x22, 0x0C]),
+        ("SE VA, 0x0f", &[0x3A, 0x0F]),
+        ("SE V1, VB", &[0x51, 0xB0]),
+        ("SNE V2, 0xff", &[0x42, 0xFF]),
+        ("SNE V3, VC", &[0x93, 0xC0]),
+        ("LD V4, 0x42", &[0x64, 0x42]),
+        ("LD V5, VD", &[0x85, 0xD0]),
+        ("LD I, 0x050", &[0xA0, 0x50]),
+        ("LD F, V6", &[0xF6, 0x29]),
+        ("LD B, V7", &[0xF7, 0x33]),
+        ("LD [I], V8", &[0xF8, 0x55]),
+        ("LD V9, [I]", &[0xF9, 0x65]),
+        ("ADD VE, 0x01", &[0x7E, 0x01]),
+        ("ADD V0, VF", &[0x80, 0xF4]),
+        ("ADD I, V1", &[0xF1, 0x1E]),
+        ("SUB V2, V3", &[0x82, 0x35]),
+        ("SUBN V4, V5", &[0x84, 0x57]),
+        ("OR V6, V7", &[0x86, 0x71]),
+        ("AND V8, V9", &[0x88, 0x92]),
+        ("XOR VA, VB", &[0x8A, 0xB3]),
+        ("SHR VC, VD", &[0x8C, 0xD6]),
+        ("SHR VC", &[0x8C, 0x06]),
+        ("SHL VE, VF", &[0x8E, 0xFE]),
+        ("SHL VE", &[0x8E, 0x0E]),
+        ("RND V0, 0x80", &[0xC0, 0x80]),
+        ("DRW V1, V2, 5", &[0xD1, 0x25]),
+        ("SKP V3", &[0xE3, 0x9E]),
+        ("SKNP V4", &[0xE4, 0xA1]),
+        ("LD V5, K", &[0xF5, 0x0A]),
+        ("LD V6, DT", &[0xF6, 0x07]),
+        ("LD DT, V7", &[0xF7, 0x15]),
+        ("LD ST, V8", &[0xF8, 0x18]),
+        ("SCD 4", &[0x00, 0xC4]),
+        ("SCL", &[0x00, 0xFC]),
+        ("SCR", &[0x00, 0xFB]),
+        ("EXIT", &[0x00, 0xFD]),
+        ("LOW", &[0x00, 0xFE]),
+        ("HIGH", &[0x00, 0xFF]),
+        ("DRW V9, VA, 0", &[0xD9, 0xA0]),
+        ("LD HF, VB", &[0xFB, 0x30]),
+        ("LD R, VC", &[0xFC, 0x75]),
+        ("LD VD, R", &[0xFD, 0x85]),
+        ("SCU 2", &[0x00, 0xD2]),
+        ("SAVE V1 - V4", &[0x51, 0x42]),
+        ("LOAD V2 - V5", &[0x52, 0x53]),
+        ("LD I, LONG 0x1234", &[0xF0, 0x00, 0x12, 0x34]),
+        ("PLANE 3", &[0xF3, 0x01]),
+        ("AUDIO", &[0xF0, 0x02]),
+        ("PITCH VE", &[0xFE, 0x3A]),
+    ];
+
+    #[test]
+    fn assemble_each_instruction() {
+        for (source, bytes) in INSTRUCTIONS {
+            assert_eq!(assemble(source).as_deref(), Ok(bytes), "{}", source);
+        }
+    }
+
+    #[test]
+    fn assemble_byte_literals() {
+        assert_eq!(assemble("DB 1, 0x02, #03, 0b100"), Ok(vec![1, 2, 3, 4]));
+    }
+
+    #[test]
+    fn labels_can_be_used_before_they_are_defined() {
+        let source = "
+            start:
+                CALL draw   ; draw is defined below
+                JP start
+            draw:
+                LD I, sprite
+                DRW V0, V1, 1
+                RET
+            sprite: DB 0xF0
+        ";
+        let rom = [0x22, 0x04, 0x12, 0x00, 0xA2, 0x0A, 0xD0, 0x11, 0x00, 0xEE, 0xF0];
+        assert_eq!(assemble(source), Ok(rom.to_vec()));
+    }
+
+    #[test]
+    fn constants_are_replaced_by_their_values() {
+        assert_eq!(assemble("SPEED EQU 3\nADD V0, SPEED"), Ok(vec![0x70, 0x03]));
+    }
+
+    #[test]
+    fn operands_are_only_split_on_commas() {
+        // a minus sign is part of a register range, not a separator
+        assert_eq!(
+            assemble("LD V1-V2, 3"),
+            Err(AssembleError::InvalidOperand {
+                line: 1,
+                token: String::from("V1-V2"),
+            })
+        );
+        assert_eq!(
+            assemble("SAVE V1, V4"),
+            Err(AssembleError::InvalidOperand {
+                line: 1,
+                token: String::from("V1, V4"),
+            })
+        );
+        assert_eq!(assemble("SAVE V1-V4"), Ok(vec![0x51, 0x42]));
+    }
+
+    #[test]
+    fn report_undefined_and_duplicate_labels() {
+        assert_eq!(
+            assemble("JP nowhere"),
+            Err(AssembleError::UndefinedLabel(String::from("nowhere")))
+        );
+        assert_eq!(
+            assemble("here: CLS\nhere: RET"),
+            Err(AssembleError::DuplicateLabel(String::from("here")))
+        );
+    }
+}
@@ -0,0 +1,38 @@
+use crate::commands::analyze::AnalyzeArgs;
+use crate::commands::asm::AsmArgs;
+use crate::commands::disasm::DisasmArgs;
+#[cfg(feature = "debug")]
+use crate::commands::repl::ReplArgs;
+use crate::commands::test::TestArgs;
+use crate::emulator::RunArgs;
+use clap::{Parser, Subcommand};
+
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Commands,
+}
+
+/// What to do
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    /// Run a ROM
+    Run(RunArgs),
+
+    /// Print the disassembly of a ROM
+    Disasm(DisasmArgs),
+
+    /// Count the instructions in a ROM, and find the instruction set it needs
+    Analyze(AnalyzeArgs),
+
+    /// Assemble a program into a ROM
+    Asm(AsmArgs),
+
+    /// Run a ROM and check the display against a reference file
+    Test(TestArgs),
+
+    /// Run opcodes typed in as hex, printing the registers after each one
+    #[cfg(feature = "debug")]
+    Repl(ReplArgs),
+}
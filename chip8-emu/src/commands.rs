@@ -0,0 +1,8 @@
+//! Tools that are run instead of the emulator
+
+pub mod analyze;
+pub mod asm;
+pub mod disasm;
+#[cfg(feature = "debug")]
+pub mod repl;
+pub mod test;
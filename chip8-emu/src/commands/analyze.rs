@@ -0,0 +1,49 @@
+use crate::analysis::analyze_frequency;
+use crate::chip8::EmulationMode;
+use clap::Args;
+
+/// Options for analyzing a ROM
+#[derive(Args, Debug)]
+pub struct AnalyzeArgs {
+    /// Path to the ROM
+    pub rom: String,
+}
+
+/// Print how often each kind of instruction appears in a ROM, and the instruction set it needs
+pub fn run(args: &AnalyzeArgs) {
+    let rom = std::fs::read(&args.rom).expect("Unable to open ROM!");
+    let report = analyze_frequency(&rom);
+
+    println!("{:<20}{:>8}{:>9}  {}", "instruction", "count", "%", "needs");
+    for entry in &report.entries {
+        let row = format!(
+            "{:<20}{:>8}{:>8.2}%  {}",
+            entry.name,
+            entry.count,
+            entry.percent,
+            extension_name(entry.mode)
+        );
+        println!("{}", row.trim_end());
+    }
+
+    // data is counted as well as code, so the mode is only a guess
+    println!();
+    match report.required_mode() {
+        EmulationMode::Chip8 => println!("Only CHIP-8 instructions were found"),
+        EmulationMode::SuperChip => {
+            println!("SUPER-CHIP instructions were found, so try running with --mode schip")
+        }
+        EmulationMode::XoChip => {
+            println!("XO-CHIP instructions were found, so try running with --mode xochip")
+        }
+    }
+}
+
+/// Get the name of the extension an instruction set adds, or nothing for plain CHIP-8
+fn extension_name(mode: EmulationMode) -> &'static str {
+    match mode {
+        EmulationMode::Chip8 => "",
+        EmulationMode::SuperChip => "SUPER-CHIP",
+        EmulationMode::XoChip => "XO-CHIP",
+    }
+}
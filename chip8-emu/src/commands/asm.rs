@@ -0,0 +1,37 @@
+use crate::assembler::assemble;
+use crate::chip8::PROGMEM_START;
+use clap::Args;
+
+/// Options for assembling a program
+#[derive(Args, Debug)]
+pub struct AsmArgs {
+    /// Path to the assembly source
+    pub source: String,
+
+    /// Path to write the ROM to, instead of printing it as hex
+    #[arg(short, long)]
+    pub output: Option<String>,
+}
+
+/// Assemble a program, and either write the ROM to a file or print it as hex
+pub fn run(args: &AsmArgs) {
+    let source = std::fs::read_to_string(&args.source).expect("Unable to open source file!");
+
+    let rom = match assemble(&source) {
+        Ok(rom) => rom,
+        Err(e) => {
+            eprintln!("Unable to assemble {}: {}", args.source, e);
+            std::process::exit(1);
+        }
+    };
+
+    match &args.output {
+        Some(path) => std::fs::write(path, rom).expect("Unable to write ROM file!"),
+        None => {
+            for (row, bytes) in rom.chunks(16).enumerate() {
+                let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+                println!("{:#05x}: {}", PROGMEM_START as usize + row * 16, hex.join(" "));
+            }
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use crate::disasm::disassemble;
+use clap::Args;
+use serde_json::json;
+use std::str::FromStr;
+
+/// How a disassembly is printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DisasmFormat {
+    /// One instruction to a line, with its address and opcode
+    Text,
+
+    /// An array of objects, one for each instruction
+    Json,
+}
+
+impl FromStr for DisasmFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "text" => Ok(DisasmFormat::Text),
+            "json" => Ok(DisasmFormat::Json),
+            _ => Err(format!("Unknown disassembly format '{}'", s)),
+        }
+    }
+}
+
+/// Options for disassembling a ROM
+#[derive(Args, Debug)]
+pub struct DisasmArgs {
+    /// Path to the ROM
+    pub rom: String,
+
+    /// How to print the disassembly (text or json)
+    #[arg(short, long, default_value = "text")]
+    pub format: DisasmFormat,
+}
+
+/// Print the disassembly of a ROM to stdout
+pub fn run(args: &DisasmArgs) {
+    let rom = std::fs::read(&args.rom).expect("Unable to open ROM!");
+    let lines = disassemble(&rom);
+
+    match args.format {
+        DisasmFormat::Text => {
+            for line in lines {
+                println!("{:#05x}: {:04X}  {}", line.address, line.opcode, line.mnemonic);
+            }
+        }
+        DisasmFormat::Json => {
+            let lines: Vec<serde_json::Value> = lines
+                .iter()
+                .map(|line| {
+                    json!({
+                        "address": line.address,
+                        "opcode": line.opcode,
+                        "mnemonic": line.mnemonic,
+                    })
+                })
+                .collect();
+            println!("{}", serde_json::Value::Array(lines));
+        }
+    }
+}
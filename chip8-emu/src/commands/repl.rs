@@ -0,0 +1,83 @@
+use crate::chip8::{Chip8Builder, EmulationMode, QuirksPreset, RegisterSnapshot};
+use clap::Args;
+use std::io::{self, BufRead, Write};
+
+/// Options for running opcodes from a prompt
+#[derive(Args, Debug)]
+pub struct ReplArgs {
+    /// Instruction set to emulate (chip8, schip, or xochip)
+    #[arg(short, long, default_value = "chip8")]
+    pub mode: EmulationMode,
+
+    /// Quirks of a known interpreter (chip8, chip48, schip10, schip11, or xochip)
+    #[arg(long)]
+    pub preset: Option<QuirksPreset>,
+
+    /// Seed for the random numbers used by CXNN, to make runs repeatable
+    #[arg(long)]
+    pub rng_seed: Option<u64>,
+}
+
+/// Run opcodes typed on stdin, printing the registers after each one
+/// Each line is one opcode in hex, with or without a leading "0x". The session ends at the end of
+/// input, or when "q" is entered.
+pub fn run(args: &ReplArgs) {
+    let mut builder = Chip8Builder::default().mode(args.mode);
+    if let Some(preset) = args.preset {
+        builder = builder.quirks_preset(preset);
+    }
+    if let Some(seed) = args.rng_seed {
+        builder = builder.seed(seed);
+    }
+    let mut state = match builder.build() {
+        Ok(state) => state,
+        Err(e) => {
+            eprintln!("Unable to start emulator: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    print_registers(&state.snapshot_registers());
+    prompt();
+
+    for line in io::stdin().lock().lines() {
+        let line = line.expect("Unable to read from stdin!");
+        let line = line.trim();
+        if line.eq_ignore_ascii_case("q") {
+            break;
+        }
+
+        if !line.is_empty() {
+            match u16::from_str_radix(line.trim_start_matches("0x"), 16) {
+                Ok(opcode) => match state.inject_opcode(opcode) {
+                    Ok(_) => print_registers(&state.snapshot_registers()),
+                    Err(e) => eprintln!("Emulator error: {}", e),
+                },
+                Err(_) => eprintln!("'{}' is not a valid hex opcode", line),
+            }
+        }
+        prompt();
+    }
+}
+
+/// Show that the next opcode can be entered
+fn prompt() {
+    print!("> ");
+    io::stdout().flush().expect("Unable to write to stdout!");
+}
+
+/// Print the registers, with the general-purpose registers four to a line
+fn print_registers(registers: &RegisterSnapshot) {
+    println!(
+        "PC: {:#05x}  I: {:#05x}  SP: {}  DT: {:#04x}  ST: {:#04x}",
+        registers.pc, registers.i, registers.sp, registers.dt, registers.st
+    );
+    for (row, values) in registers.v.chunks(4).enumerate() {
+        let line: Vec<String> = values
+            .iter()
+            .enumerate()
+            .map(|(col, value)| format!("V{:X}: {:#04x}", row * 4 + col, value))
+            .collect();
+        println!("{}", line.join("  "));
+    }
+}
@@ -0,0 +1,168 @@
+use crate::chip8::{Chip8Builder, LORES_HEIGHT, LORES_WIDTH};
+use crate::emulator::metadata::{QuirksConfig, RomMetadata};
+use clap::Args;
+use std::path::Path;
+
+/// Options for checking a ROM's display against a reference file
+#[derive(Args, Debug)]
+pub struct TestArgs {
+    /// Path to the ROM
+    pub rom: String,
+
+    /// Path to the reference display, with 8 pixels to a byte
+    #[arg(short, long)]
+    pub golden: String,
+
+    /// Number of instructions to run before checking the display
+    #[arg(short, long, default_value_t = 1000)]
+    pub cycles: u32,
+
+    /// Write the display to the reference file instead of checking it
+    #[arg(short, long)]
+    pub update: bool,
+}
+
+/// The low resolution display, as captured after running a ROM
+pub type Capture = [[bool; LORES_WIDTH]; LORES_HEIGHT];
+
+// the size of a capture in a reference file, with 8 pixels to a byte
+const CAPTURE_BYTES: usize = LORES_WIDTH * LORES_HEIGHT / 8;
+
+/// Run a ROM for a number of instructions and capture the display
+/// A frame passes after every frame's worth of instructions, so timers and the vblank quirk
+/// behave as they would in a window.
+pub fn run_and_capture(
+    rom: &[u8],
+    cycles: u32,
+    quirks: &QuirksConfig,
+) -> Result<Capture, String> {
+    let mut builder = Chip8Builder::default()
+        .rom_bytes(rom)
+        .shift_quirk(quirks.shift_quirk.unwrap_or_default())
+        .load_store_quirk(quirks.load_store_quirk.unwrap_or_default())
+        .vblank_quirk(quirks.vblank_quirk.unwrap_or_default())
+        .wrap_mode(quirks.wrap_mode.unwrap_or_default());
+    if let Some(cycles_per_frame) = quirks.cycles_per_frame {
+        builder = builder.cycles_per_frame(cycles_per_frame.max(1));
+    }
+    let mut state = builder.build().map_err(|e| e.to_string())?;
+
+    for cycle in 1..=cycles {
+        state
+            .do_next_instruction()
+            .map_err(|e| format!("emulator error after {} instructions: {}", cycle - 1, e))?;
+
+        if cycle % state.cycles_per_frame() == 0 {
+            state.do_frame();
+        }
+        if state.has_exited() {
+            break;
+        }
+    }
+
+    let mut capture: Capture = [[false; LORES_WIDTH]; LORES_HEIGHT];
+    for (y, row) in capture.iter_mut().enumerate() {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            *pixel = state.get_pixel_color(x, y) > 0;
+        }
+    }
+
+    Ok(capture)
+}
+
+/// Pack a capture 8 pixels to a byte, row by row, for a reference file
+fn encode_capture(capture: &Capture) -> Vec<u8> {
+    capture
+        .iter()
+        .flat_map(|row| row.chunks(8))
+        .map(|pixels| pixels.iter().fold(0, |byte, &pixel| (byte << 1) | pixel as u8))
+        .collect()
+}
+
+/// Unpack a capture from a reference file
+fn decode_capture(bytes: &[u8]) -> Result<Capture, String> {
+    if bytes.len() != CAPTURE_BYTES {
+        return Err(format!(
+            "reference should be {} bytes, but is {} bytes",
+            CAPTURE_BYTES,
+            bytes.len()
+        ));
+    }
+
+    let mut capture: Capture = [[false; LORES_WIDTH]; LORES_HEIGHT];
+    for (y, row) in capture.iter_mut().enumerate() {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let index = y * LORES_WIDTH + x;
+            *pixel = bytes[index / 8] & (0x80 >> (index % 8)) > 0;
+        }
+    }
+
+    Ok(capture)
+}
+
+/// Show the pixels that differ between two captures
+/// Matching pixels are shown as # or space, extra pixels as +, and missing pixels as -.
+fn format_diff(expected: &Capture, actual: &Capture) -> String {
+    expected
+        .iter()
+        .zip(actual)
+        .map(|(expected_row, actual_row)| {
+            expected_row
+                .iter()
+                .zip(actual_row)
+                .map(|(&expected, &actual)| match (expected, actual) {
+                    (true, true) => '#',
+                    (false, false) => ' ',
+                    (false, true) => '+',
+                    (true, false) => '-',
+                })
+                .collect::<String>()
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Run a ROM and check the display against a reference file
+/// Exits with an error if they differ. The quirks come from the ROM's metadata file, if it has
+/// one. With `update`, the reference file is written instead.
+pub fn run(args: &TestArgs) {
+    let rom_path = args.rom.as_str();
+    let reference_path = args.golden.as_str();
+
+    let rom = std::fs::read(rom_path).expect("Unable to open ROM!");
+    let quirks = RomMetadata::load_from_sidecar(Path::new(rom_path))
+        .map(|metadata| metadata.quirks)
+        .unwrap_or_default();
+
+    let actual = match run_and_capture(&rom, args.cycles, &quirks) {
+        Ok(capture) => capture,
+        Err(e) => {
+            eprintln!("{}: {}", rom_path, e);
+            std::process::exit(1);
+        }
+    };
+
+    if args.update {
+        std::fs::write(reference_path, encode_capture(&actual))
+            .expect("Unable to write reference file!");
+        println!("{}: wrote {}", rom_path, reference_path);
+        return;
+    }
+
+    let reference = std::fs::read(reference_path).expect("Unable to open reference file!");
+    let expected = match decode_capture(&reference) {
+        Ok(capture) => capture,
+        Err(e) => {
+            eprintln!("{}: {}", reference_path, e);
+            std::process::exit(1);
+        }
+    };
+
+    if expected == actual {
+        println!("{}: ok", rom_path);
+    } else {
+        eprintln!("{}: display differs from {}", rom_path, reference_path);
+        eprintln!("{}", format_diff(&expected, &actual));
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,295 @@
+mod args;
+mod audio;
+mod auto_speed;
+mod colors;
+mod compare;
+mod config;
+mod crash_dump;
+mod debug_server;
+mod debugger;
+#[cfg(feature = "network")]
+mod download;
+mod error_overlay;
+mod frame_limiter;
+mod headless;
+mod hud;
+mod inspectors;
+mod keymap;
+pub mod metadata;
+mod phosphor;
+mod plugin;
+mod profiler;
+mod recording;
+mod rewind;
+mod rom_dump;
+mod save_states;
+mod screenshot;
+mod startup_systems;
+mod systems;
+#[cfg(feature = "terminal")]
+mod terminal;
+mod util;
+
+use crate::chip8::{Chip8, HIRES_HEIGHT, HIRES_WIDTH, LORES_HEIGHT, LORES_WIDTH};
+use crate::error::EmulatorError;
+use crate::emulator::args::Backend;
+use crate::emulator::compare::get_layout_size;
+pub use crate::emulator::args::RunArgs;
+use crate::emulator::frame_limiter::{frame_limiter, TargetFrameRate};
+use crate::emulator::headless::run_headless;
+use crate::emulator::keymap::Keymap;
+pub use crate::emulator::plugin::{Chip8Config, Chip8Plugin};
+use crate::emulator::startup_systems::report_setup_error;
+#[cfg(feature = "terminal")]
+use crate::emulator::terminal::run_emulator_terminal;
+use crate::validator::{RomValidator, Severity};
+use bevy::prelude::KeyCode::*;
+use bevy::prelude::*;
+use bevy::window::PresentMode;
+
+// default colors
+const ON_COLOR: Color = Color::RED;
+const OFF_COLOR: Color = Color::BLACK;
+
+// colors for each combination of XO-CHIP display planes
+const PALETTE: [Color; 16] = [
+    OFF_COLOR,
+    ON_COLOR,
+    Color::GREEN,
+    Color::YELLOW,
+    Color::BLUE,
+    Color::FUCHSIA,
+    Color::CYAN,
+    Color::WHITE,
+    Color::DARK_GRAY,
+    Color::MAROON,
+    Color::DARK_GREEN,
+    Color::OLIVE,
+    Color::NAVY,
+    Color::PURPLE,
+    Color::TEAL,
+    Color::SILVER,
+];
+
+// mapping between gamepad buttons and the chip8 keys
+// the D-pad is laid out like the 2/4/6/8 movement keys most programs use
+const GAMEPAD_MAP: [(GamepadButtonType, u8); 16] = [
+    (GamepadButtonType::DPadUp, 0x2),
+    (GamepadButtonType::DPadDown, 0x8),
+    (GamepadButtonType::DPadLeft, 0x4),
+    (GamepadButtonType::DPadRight, 0x6),
+    (GamepadButtonType::South, 0x5),
+    (GamepadButtonType::East, 0x4),
+    (GamepadButtonType::West, 0x6),
+    (GamepadButtonType::North, 0x0),
+    (GamepadButtonType::LeftTrigger, 0x1),
+    (GamepadButtonType::RightTrigger, 0x3),
+    (GamepadButtonType::LeftTrigger2, 0x7),
+    (GamepadButtonType::RightTrigger2, 0x9),
+    (GamepadButtonType::Select, 0xA),
+    (GamepadButtonType::Start, 0xB),
+    (GamepadButtonType::LeftThumb, 0xC),
+    (GamepadButtonType::RightThumb, 0xD),
+];
+
+// the key held to run the emulator faster
+const TURBO_KEY: KeyCode = Tab;
+
+// default size of each pixel in the window
+const DEFAULT_SCALE: u32 = 10;
+
+// limits on the number of instructions run each frame
+const MIN_CYCLES_PER_FRAME: u32 = 1;
+const MAX_CYCLES_PER_FRAME: u32 = 5000;
+
+// font used for all text on screen
+const UI_FONT: &str = "fonts/DejaVuSansMono.ttf";
+
+#[derive(Resource)]
+pub struct Emulator {
+    state: Chip8,
+
+    /// The display size that the pixels were last laid out for
+    resolution: (usize, usize),
+
+    /// The save state slot that was last used
+    slot: usize,
+
+    /// The number of instructions to run each frame
+    cycles_per_frame: u32,
+
+    /// The color for each combination of display planes
+    palette: [Color; 16],
+
+    /// The error that halted the emulator, if any
+    error: Option<EmulatorError>,
+
+    /// The frames left for each pixel to fade out after turning off
+    decay: [[u8; HIRES_WIDTH]; HIRES_HEIGHT],
+
+    /// How far each pixel has faded on, from 0.0 to 1.0, in LCD mode
+    brightness: [[f32; HIRES_WIDTH]; HIRES_HEIGHT],
+
+    /// Whether the pixels have to be redrawn even if the display didn't change in the last frame
+    redraw: bool,
+
+    /// The z coordinate the display is drawn at
+    layer: f32,
+}
+
+impl Emulator {
+    /// Replace the emulator state, such as after loading a save state
+    /// The audio sink is moved over to the new state, so the sound carries on.
+    pub fn replace_state(&mut self, mut state: Chip8) {
+        if let Some(sink) = self.state.take_audio_sink() {
+            state.set_audio_sink(sink);
+        }
+        self.state = state;
+        self.redraw = true;
+    }
+}
+
+/// Whether the turbo key is held, running more instructions each frame
+#[derive(Resource, Debug, Default)]
+pub struct TurboActive(pub bool);
+
+#[derive(Component)]
+pub struct Coordinate {
+    x: usize,
+    y: usize,
+}
+
+/// Which emulator a pixel belongs to, when comparing two ROMs side by side
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EmulatorTag(pub u8);
+
+/// Sent when something happens in the emulator, so systems can react without polling it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmuEvent {
+    /// An instruction was run, with its opcode
+    /// This is only sent for instructions run one at a time, which happens while stepping in the
+    /// debugger or when breakpoints, watchpoints or --run-until are set. Otherwise instructions
+    /// are run in batches, without an event for each.
+    OpcodeExecuted(u16),
+
+    /// An opcode wasn't understood, with the opcode and its address
+    UnknownOpcode(u16, u16),
+
+    /// The sound timer counted down to zero
+    SoundTimerExpired,
+
+    /// The delay timer counted down to zero
+    DelayTimerExpired,
+
+    /// The display changed in the last frame
+    VramUpdated,
+
+    /// A key was pressed or released, with the key and whether it is now pressed
+    InputChanged(u8, bool),
+}
+
+/// Get the window title, showing the program name and the emulator speed
+fn get_window_title(args: &RunArgs, cycles_per_frame: u32) -> String {
+    format!("{} ({} cycles/frame)", args.title(), cycles_per_frame)
+}
+
+/// Run a ROM, in a window, the terminal, or headless
+pub fn run(args: RunArgs) {
+    // log everything the emulator does, without the libraries it uses
+    if args.verbose {
+        std::env::set_var("RUST_LOG", "chip8=trace,chip8_core=trace");
+    }
+
+    if args.validate || args.strict {
+        validate_rom(&args);
+    }
+
+    if args.headless {
+        // the window has its own logger, which is set up by bevy
+        env_logger::init();
+        run_headless(&args);
+    } else {
+        match args.backend {
+            Backend::Window => run_window(args),
+            #[cfg(feature = "terminal")]
+            Backend::Terminal => run_emulator_terminal(&args),
+        }
+    }
+}
+
+/// Check the ROM for likely bugs before running it
+/// Everything found is printed with --validate, and errors stop the ROM from running with --strict.
+fn validate_rom(args: &RunArgs) {
+    // a ROM that can't be read is reported when it is loaded
+    let Ok(rom) = std::fs::read(args.rom()) else {
+        return;
+    };
+
+    let mut has_errors = false;
+    for warning in RomValidator::validate(&rom) {
+        let is_error = warning.severity == Severity::Error;
+        has_errors |= is_error;
+        if args.validate || (args.strict && is_error) {
+            eprintln!("{}", warning);
+        }
+    }
+
+    if args.strict && has_errors {
+        eprintln!("Not running {}, as errors were found in it", args.rom());
+        std::process::exit(1);
+    }
+}
+
+/// Run the emulator in a window
+fn run_window(args: RunArgs) {
+    let keymap = match &args.keymap {
+        Some(keymap) => Keymap::load(keymap).unwrap_or_else(|e| {
+            eprintln!("{}: {}", keymap, e);
+            std::process::exit(1);
+        }),
+        None => Keymap::default(),
+    };
+
+    // the pixels are resized to fit the monitor when fullscreen
+    let mode = if args.fullscreen {
+        WindowMode::Fullscreen
+    } else {
+        WindowMode::Windowed
+    };
+
+    let scale = args.scale;
+    let (width, height) = get_layout_size((LORES_WIDTH, LORES_HEIGHT), &args);
+    let fps = args.fps;
+    let title = get_window_title(&args, args.cycles_per_frame);
+
+    let plugin = match Chip8Plugin::from_args(args) {
+        Ok(plugin) => plugin,
+        Err(e) => {
+            report_setup_error(In(Err(e)));
+            return;
+        }
+    };
+
+    App::new()
+        .add_plugins(DefaultPlugins.set(WindowPlugin {
+            window: WindowDescriptor {
+                width: (width as u32 * scale) as f32,
+                height: (height as u32 * scale) as f32,
+                title,
+                resizable: true,
+                decorations: true,
+                cursor_visible: true,
+                mode,
+                // frames are paced by the frame limiter, and waiting for vsync as well would slow
+                // them down on monitors that aren't 60hz
+                present_mode: PresentMode::AutoNoVsync,
+                ..default()
+            },
+            ..default()
+        }))
+        .insert_resource(TargetFrameRate(fps))
+        .insert_resource(keymap)
+        .add_system_to_stage(CoreStage::First, frame_limiter)
+        .add_plugin(plugin)
+        .run();
+}
@@ -0,0 +1,675 @@
+use crate::chip8::{
+    Breakpoint, EmulationMode, FontSet, QuirksPreset, TimerDecrement, WatchMode, WrapMode,
+    DEFAULT_CYCLES_PER_FRAME, DEFAULT_TIMER_HZ, DEFAULT_TRACE_DEPTH,
+};
+use crate::emulator::colors::{Chip8Color, Theme};
+use crate::emulator::config::ConfigFile;
+use crate::emulator::frame_limiter::DEFAULT_FPS;
+use crate::emulator::metadata::RomMetadata;
+use crate::emulator::{DEFAULT_SCALE, MAX_CYCLES_PER_FRAME, MIN_CYCLES_PER_FRAME};
+use bevy::prelude::*;
+use clap::parser::ValueSource;
+use clap::{ArgAction, ArgMatches, Args, Command, FromArgMatches};
+#[cfg(feature = "network")]
+use crate::emulator::download::download_rom;
+use std::path::Path;
+use std::str::FromStr;
+#[cfg(feature = "network")]
+use std::time::Duration;
+
+/// Options for running a ROM
+#[derive(Args, Debug, Clone, Resource)]
+pub struct RunArgs {
+    /// Path to the ROM
+    #[cfg_attr(feature = "network", arg(short, long, required_unless_present = "url"))]
+    #[cfg_attr(not(feature = "network"), arg(short, long, required = true))]
+    rom: Option<String>,
+
+    /// URL to download the ROM from, instead of giving a path
+    #[cfg(feature = "network")]
+    #[arg(long, conflicts_with = "rom")]
+    url: Option<String>,
+
+    /// Seconds to wait for the ROM to download
+    #[cfg(feature = "network")]
+    #[arg(long, default_value_t = 10)]
+    download_timeout: u64,
+
+    /// Address to start the ROM from instead of 0x200, in hex, for ROMs with a self-test or
+    /// several programs
+    #[arg(long, value_parser = parse_address)]
+    pub entry_point: Option<u16>,
+
+    /// Bytes to write into memory before running, as a hex address and hex bytes like
+    /// 2A0:00E0 (can be given more than once)
+    #[arg(long, value_parser = parse_patch)]
+    pub patch: Vec<(u16, Vec<u8>)>,
+
+    /// Path to a custom font ROM
+    #[arg(short, long)]
+    pub font: Option<String>,
+
+    /// Font of a historical interpreter to use instead of the usual one (original, dream, eti,
+    /// or cosmac)
+    #[arg(long, conflicts_with = "font")]
+    pub font_set: Option<FontSet>,
+
+    /// Instruction set to emulate (chip8, schip, or xochip)
+    #[arg(short, long, default_value = "chip8")]
+    pub mode: EmulationMode,
+
+    /// What happens to sprites drawn past the edges of the display (clip or wrap)
+    #[arg(short, long, default_value = "clip")]
+    pub wrap_mode: WrapMode,
+
+    /// Shift VX in place in 8XY6 and 8XYE, ignoring VY (CHIP-48 and SUPER-CHIP behavior)
+    #[arg(long)]
+    pub shift_quirk: bool,
+
+    /// Leave I unchanged in FX55 and FX65 (SUPER-CHIP 1.1 behavior)
+    #[arg(long)]
+    pub load_store_quirk: bool,
+
+    /// Add X to I in FX55 and FX65 instead of X + 1 (CHIP-48 and SUPER-CHIP 1.0 behavior)
+    #[arg(long)]
+    pub load_store_by_x_quirk: bool,
+
+    /// Jump to NNN plus VX in BNNN, where X is the high nibble of NNN (CHIP-48 behavior)
+    #[arg(long)]
+    pub jump0_quirk: bool,
+
+    /// Wait for the next frame before drawing a sprite in DXYN (original CHIP-8 behavior)
+    #[arg(long)]
+    pub vblank_quirk: bool,
+
+    /// Set VF when I goes past 0xFFF in FX1E (Amiga interpreter behavior)
+    #[arg(long)]
+    pub add_index_overflow_quirk: bool,
+
+    /// Quirks of a known interpreter (chip8, chip48, schip10, schip11, or xochip), with any
+    /// individual quirk options given turned on as well
+    #[arg(long)]
+    pub preset: Option<QuirksPreset>,
+
+    /// Path to a second ROM to run beside the first, with the same inputs, to compare them
+    #[arg(long, conflicts_with = "phosphor")]
+    pub compare: Option<String>,
+
+    /// Quirks of a known interpreter to run the second ROM with, instead of the same quirks as
+    /// the first
+    #[arg(long, requires = "compare")]
+    pub compare_preset: Option<QuirksPreset>,
+
+    /// Show the second display as a comparison with the first: red where they differ, and green
+    /// where both pixels are on
+    #[arg(long, requires = "compare")]
+    pub diff_mode: bool,
+
+    /// Allow writes below program memory, for ROMs that use the font region for their own data
+    #[arg(long = "no-protect-font", action = ArgAction::SetFalse)]
+    pub protect_font: bool,
+
+    /// Show sprites as soon as they are drawn, instead of once each frame ends, which can show
+    /// frames that are only partly drawn
+    #[arg(long = "no-double-buffer", action = ArgAction::SetFalse)]
+    pub double_buffer: bool,
+
+    /// Seed for the random numbers used by CXNN, to make runs repeatable
+    #[arg(long)]
+    pub rng_seed: Option<u64>,
+
+    /// Rate at which the delay and sound timers count down (usually 60, or 50 for PAL programs)
+    #[arg(
+        long,
+        default_value_t = DEFAULT_TIMER_HZ,
+        value_parser = clap::value_parser!(u8).range(1..)
+    )]
+    pub timer_hz: u8,
+
+    /// When the delay and sound timers count down (frame, or instruction to count down after
+    /// every few instructions like some interpreters did)
+    #[arg(long, default_value = "frame")]
+    pub timer_decrement_mode: TimerDecrementMode,
+
+    /// Number of instructions run between each count down of the timers, when they count down
+    /// on instructions
+    #[arg(
+        long,
+        default_value_t = 1,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub timer_decrement_n: u32,
+
+    /// How many times faster the emulator runs while Tab is held
+    #[arg(
+        long,
+        default_value_t = 10,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub turbo_multiplier: u32,
+
+    /// Address to pause execution at, in hex (can be given more than once)
+    #[arg(short, long, value_parser = parse_address)]
+    pub breakpoint: Vec<u16>,
+
+    /// Condition to pause execution at: a register value like V3=10, a range of addresses like
+    /// 200..240, or a number of instructions like cycle=1000 (can be given more than once)
+    #[arg(long)]
+    pub break_when: Vec<Breakpoint>,
+
+    /// Address to pause at when accessed, in hex, with an optional :r, :w or :rw (can be given
+    /// more than once)
+    #[arg(long, value_parser = parse_watchpoint)]
+    pub watch: Vec<(u16, WatchMode)>,
+
+    /// Start paused in the debugger, before the first instruction is run
+    #[arg(long)]
+    pub pause_on_start: bool,
+
+    /// Address to fast-forward to and then pause at, in hex
+    #[arg(long, value_parser = parse_address)]
+    pub run_until: Option<u16>,
+
+    /// Port to serve the registers, memory and breakpoints on as JSON over HTTP
+    #[arg(long)]
+    pub debug_server: Option<u16>,
+
+    /// Volume of the beep, from 0.0 to 1.0
+    #[arg(long, default_value_t = 0.25, value_parser = parse_volume)]
+    pub audio_volume: f32,
+
+    /// Do not open an audio device
+    #[arg(long)]
+    pub mute: bool,
+
+    /// Number of instructions to run each frame
+    #[arg(
+        short,
+        long,
+        default_value_t = DEFAULT_CYCLES_PER_FRAME,
+        value_parser = clap::value_parser!(u32)
+            .range(MIN_CYCLES_PER_FRAME as i64..=MAX_CYCLES_PER_FRAME as i64)
+    )]
+    pub cycles_per_frame: u32,
+
+    /// Adjust the number of instructions run each frame to suit the ROM, by watching how it
+    /// waits on the delay timer over the first few seconds
+    #[arg(long)]
+    pub auto_speed: bool,
+
+    /// Number of frames to run each second, whatever the refresh rate of the monitor (lower
+    /// rates run in slow motion)
+    #[arg(
+        long,
+        default_value_t = DEFAULT_FPS,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub fps: u32,
+
+    /// Color of pixels that are on, as a hex code like #00FF00 or a color name
+    #[arg(long)]
+    pub on_color: Option<Chip8Color>,
+
+    /// Color of pixels that are off, as a hex code like #000000 or a color name
+    #[arg(long)]
+    pub off_color: Option<Chip8Color>,
+
+    /// Color theme (classic, amber, green, lcd, or matrix)
+    #[arg(short, long)]
+    pub theme: Option<Theme>,
+
+    /// Swap the on and off colors
+    #[arg(long)]
+    pub invert: bool,
+
+    /// Number of frames pixels take to fade out after turning off, like a phosphor display
+    #[arg(long, default_value_t = 0)]
+    pub persistence: u8,
+
+    /// Fade pixels on and off over a few frames, like a slow LCD panel
+    #[arg(long)]
+    pub lcd_mode: bool,
+
+    /// Draw the display with a shader that adds bloom and scanlines, and fades pixels smoothly
+    #[arg(long)]
+    pub phosphor: bool,
+
+    /// Space left between pixels, in screen pixels
+    #[arg(long, default_value_t = 0.0, value_parser = parse_pixel_gap)]
+    pub pixel_gap: f32,
+
+    /// Color shown between pixels and around the display, instead of the off color
+    #[arg(long)]
+    pub gap_color: Option<Chip8Color>,
+
+    /// Size of each pixel in the initial window, in screen pixels
+    #[arg(
+        short,
+        long,
+        default_value_t = DEFAULT_SCALE,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub scale: u32,
+
+    /// Run fullscreen, ignoring the scale
+    #[arg(long)]
+    pub fullscreen: bool,
+
+    /// Stretch the display to fill the window, instead of keeping its shape
+    #[arg(long = "no-maintain-aspect", action = ArgAction::SetFalse)]
+    pub maintain_aspect: bool,
+
+    /// Path to a TOML keymap file, or a built-in layout (qwerty or numpad)
+    #[arg(short, long)]
+    pub keymap: Option<String>,
+
+    /// Path to a TOML file of options, used instead of ~/.chip8emu/config.toml and
+    /// <rom_path>.toml
+    #[arg(long)]
+    pub config: Option<String>,
+
+    /// Ignore gamepads, using only the keyboard
+    #[arg(long)]
+    pub no_gamepad: bool,
+
+    /// Which gamepad to use when more than one is connected
+    #[arg(long, default_value_t = 0)]
+    pub gamepad_index: usize,
+
+    /// Log the time spent on instructions, the display, and input, with a summary on exit
+    #[arg(long)]
+    pub profile: bool,
+
+    /// Count how often each opcode is executed, and print the most frequent on exit
+    #[arg(long)]
+    pub profile_opcodes: bool,
+
+    /// Log each instruction executed, along with timer and input events, to stderr
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Keep a trace of the last instructions executed, and print it if the emulator halts
+    #[arg(long)]
+    pub trace: bool,
+
+    /// Number of instructions kept in the trace
+    #[arg(
+        long,
+        default_value_t = DEFAULT_TRACE_DEPTH,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub trace_depth: usize,
+
+    /// Write the registers, stack, RAM and display to crash_<timestamp>.txt if the emulator halts
+    #[arg(long)]
+    pub dump_on_crash: bool,
+
+    /// Write the program as it is in memory to this path when the emulator exits, keeping any
+    /// changes it made to its own code
+    #[arg(long)]
+    pub dump_rom: Option<String>,
+
+    /// Check the ROM for likely bugs, and print what was found before running it
+    #[arg(long)]
+    pub validate: bool,
+
+    /// Refuse to run the ROM if checking it finds any errors
+    #[arg(long)]
+    pub strict: bool,
+
+    /// Run without a window
+    #[arg(long)]
+    pub headless: bool,
+
+    /// Where to show the display (window, or terminal if built with the terminal feature)
+    #[arg(long, default_value = "window")]
+    pub backend: Backend,
+
+    /// Number of frames to run for without a window
+    #[arg(long, default_value_t = 3600)]
+    pub max_frames: u32,
+
+    /// Print the display once done running without a window
+    #[arg(long)]
+    pub dump_display: bool,
+
+    /// Mark which instructions are executed without a window, and print how much of the ROM
+    /// was covered once done
+    #[arg(long)]
+    pub coverage: bool,
+
+    /// Write the coverage to this path as an LCOV tracefile once done running without a window
+    #[arg(long, requires = "coverage")]
+    pub coverage_lcov: Option<String>,
+
+    /// Save a screenshot of the display when the emulator exits
+    #[arg(long)]
+    pub screenshot_on_exit: bool,
+
+    /// Path to save the screenshot taken on exit to
+    #[arg(long, default_value = "screenshot.png")]
+    pub screenshot_output: String,
+
+    /// Size of each display pixel in screenshots, in image pixels
+    #[arg(
+        long,
+        default_value_t = 4,
+        value_parser = clap::value_parser!(u32).range(1..)
+    )]
+    pub screenshot_scale: u32,
+
+    /// Record the display to an animated GIF, which is finished when the emulator exits
+    #[arg(long)]
+    pub record: Option<String>,
+
+    /// Number of frames recorded each second, out of the 60 run
+    #[arg(
+        long,
+        default_value_t = 60,
+        value_parser = clap::value_parser!(u32).range(1..=60)
+    )]
+    pub record_fps: u32,
+
+    /// Information about the ROM, if it has a metadata file
+    #[arg(skip)]
+    pub metadata: Option<RomMetadata>,
+}
+
+/// Where the display is shown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    /// A window drawn with bevy
+    Window,
+
+    /// The terminal, drawn with block characters
+    #[cfg(feature = "terminal")]
+    Terminal,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "window" => Ok(Backend::Window),
+            #[cfg(feature = "terminal")]
+            "terminal" => Ok(Backend::Terminal),
+            #[cfg(not(feature = "terminal"))]
+            "terminal" => Err("the terminal backend needs the terminal feature".to_string()),
+            _ => Err(format!("Unknown backend '{}'", s)),
+        }
+    }
+}
+
+/// What the delay and sound timers count down on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimerDecrementMode {
+    /// Frames, at the timer frequency
+    Frame,
+
+    /// Instructions, once every `--timer-decrement-n` of them
+    Instruction,
+}
+
+impl FromStr for TimerDecrementMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "frame" => Ok(TimerDecrementMode::Frame),
+            "instruction" => Ok(TimerDecrementMode::Instruction),
+            _ => Err(format!("Unknown timer decrement mode '{}'", s)),
+        }
+    }
+}
+
+impl RunArgs {
+    /// Fetch the ROM if needed, and read its metadata and config files if it has any
+    /// Options that the files give are used unless they were given on the command line, so the
+    /// matches for the run command are needed to tell which were.
+    pub fn load_metadata(&mut self, matches: &ArgMatches) {
+        // downloaded ROMs are cached, and run from the cache like a local ROM
+        #[cfg(feature = "network")]
+        if let Some(url) = &self.url {
+            let timeout = Duration::from_secs(self.download_timeout);
+            let path = download_rom(url, timeout).unwrap_or_else(|e| {
+                eprintln!("{}", e);
+                std::process::exit(1);
+            });
+            self.rom = Some(path.to_string_lossy().into_owned());
+        }
+
+        if self.rom.is_some() {
+            self.metadata = RomMetadata::load_from_sidecar(Path::new(self.rom()));
+        }
+        if let Some(metadata) = self.metadata.clone() {
+            self.apply_metadata(&metadata, matches);
+        }
+        for config in self.load_config() {
+            self.apply_config(&config, matches);
+        }
+        if let Some(preset) = self.preset {
+            self.apply_preset(preset, matches);
+        }
+    }
+
+    /// Use the options from a ROM's metadata in place of any defaults
+    fn apply_metadata(&mut self, metadata: &RomMetadata, matches: &ArgMatches) {
+        let is_default = |id: &str| matches.value_source(id) == Some(ValueSource::DefaultValue);
+        let quirks = &metadata.quirks;
+
+        if let Some(enabled) = quirks.shift_quirk.filter(|_| is_default("shift_quirk")) {
+            self.shift_quirk = enabled;
+        }
+        if let Some(enabled) = quirks.load_store_quirk.filter(|_| is_default("load_store_quirk")) {
+            self.load_store_quirk = enabled;
+        }
+        if let Some(enabled) = quirks.jump0_quirk.filter(|_| is_default("jump0_quirk")) {
+            self.jump0_quirk = enabled;
+        }
+        if let Some(enabled) = quirks.vblank_quirk.filter(|_| is_default("vblank_quirk")) {
+            self.vblank_quirk = enabled;
+        }
+        if let Some(wrap_mode) = quirks.wrap_mode.filter(|_| is_default("wrap_mode")) {
+            self.wrap_mode = wrap_mode;
+        }
+        if let Some(cycles) = quirks.cycles_per_frame.filter(|_| is_default("cycles_per_frame")) {
+            self.cycles_per_frame = cycles.clamp(MIN_CYCLES_PER_FRAME, MAX_CYCLES_PER_FRAME);
+        }
+    }
+
+    /// Read the config file given, or else the ones that are looked for by default
+    fn load_config(&self) -> Vec<ConfigFile> {
+        match &self.config {
+            Some(path) => {
+                let config = ConfigFile::from_file(Path::new(path)).unwrap_or_else(|e| {
+                    eprintln!("{}: {}", path, e);
+                    std::process::exit(1);
+                });
+                vec![config]
+            }
+            None if self.rom.is_some() => ConfigFile::load_default(Path::new(self.rom())),
+            None => Vec::new(),
+        }
+    }
+
+    /// Use the options from a config file in place of any not given on the command line
+    fn apply_config(&mut self, config: &ConfigFile, matches: &ArgMatches) {
+        let is_unset = |id: &str| matches.value_source(id) != Some(ValueSource::CommandLine);
+        let parse_color = |color: &Option<String>| {
+            color
+                .as_deref()
+                .and_then(|color| Chip8Color::from_str(color).ok())
+        };
+
+        if let Some(enabled) = config.shift_quirk.filter(|_| is_unset("shift_quirk")) {
+            self.shift_quirk = enabled;
+        }
+        if let Some(enabled) = config.load_store_quirk.filter(|_| is_unset("load_store_quirk")) {
+            self.load_store_quirk = enabled;
+        }
+        if let Some(enabled) = config.vblank_quirk.filter(|_| is_unset("vblank_quirk")) {
+            self.vblank_quirk = enabled;
+        }
+        if let Some(enabled) = config.jump0_quirk.filter(|_| is_unset("jump0_quirk")) {
+            self.jump0_quirk = enabled;
+        }
+        if let Some(wrap_mode) = config.wrap_mode.filter(|_| is_unset("wrap_mode")) {
+            self.wrap_mode = wrap_mode;
+        }
+        if let Some(cycles) = config.cycles_per_frame.filter(|_| is_unset("cycles_per_frame")) {
+            self.cycles_per_frame = cycles.clamp(MIN_CYCLES_PER_FRAME, MAX_CYCLES_PER_FRAME);
+        }
+        if let Some(hz) = config.timer_frequency.filter(|_| is_unset("timer_hz")) {
+            self.timer_hz = hz.max(1);
+        }
+        if let Some(color) = parse_color(&config.on_color).filter(|_| is_unset("on_color")) {
+            self.on_color = Some(color);
+        }
+        if let Some(color) = parse_color(&config.off_color).filter(|_| is_unset("off_color")) {
+            self.off_color = Some(color);
+        }
+        if let Some(scale) = config.scale.filter(|_| is_unset("scale")) {
+            self.scale = scale.max(1);
+        }
+        if let Some(keymap) = config.keymap_path.clone().filter(|_| is_unset("keymap")) {
+            self.keymap = Some(keymap);
+        }
+    }
+
+    /// Use the quirks of a known interpreter, except for any quirk options that were given
+    fn apply_preset(&mut self, preset: QuirksPreset, matches: &ArgMatches) {
+        let is_default = |id: &str| matches.value_source(id) == Some(ValueSource::DefaultValue);
+        let quirks = preset.quirks();
+
+        if is_default("shift_quirk") {
+            self.shift_quirk = quirks.shift;
+        }
+        if is_default("load_store_quirk") {
+            self.load_store_quirk = quirks.load_store;
+        }
+        if is_default("load_store_by_x_quirk") {
+            self.load_store_by_x_quirk = quirks.load_store_by_x;
+        }
+        if is_default("jump0_quirk") {
+            self.jump0_quirk = quirks.jump0;
+        }
+        if is_default("vblank_quirk") {
+            self.vblank_quirk = quirks.vblank;
+        }
+    }
+
+    /// Get the name of the program, from its metadata if it has any
+    pub fn title(&self) -> &str {
+        self.metadata
+            .as_ref()
+            .and_then(|metadata| metadata.title.as_deref())
+            .unwrap_or("CHIP-8")
+    }
+
+    /// Get the default options, for a ROM that isn't read from a file
+    pub fn embedded() -> Self {
+        let command = RunArgs::augment_args(Command::new("chip8").no_binary_name(true));
+        let matches = command
+            .try_get_matches_from(["--rom", "embedded"])
+            .expect("The default options should always parse");
+        RunArgs::from_arg_matches(&matches).expect("The default options should always parse")
+    }
+
+    /// Get when the timers count down, from the decrement mode and the number of instructions
+    pub fn timer_decrement(&self) -> TimerDecrement {
+        match self.timer_decrement_mode {
+            TimerDecrementMode::Frame => TimerDecrement::OnFrame,
+            TimerDecrementMode::Instruction => {
+                TimerDecrement::OnInstruction(self.timer_decrement_n)
+            }
+        }
+    }
+
+    /// Get the options for the ROM being compared against, if one was given
+    /// These are the same as for the first ROM, apart from the quirks of the compare preset if one
+    /// was given. The patches and watchpoints are left out, as they are for the first ROM.
+    pub fn compare_args(&self) -> Option<RunArgs> {
+        let mut args = self.clone();
+        args.rom = Some(self.compare.clone()?);
+        args.patch.clear();
+        args.watch.clear();
+        if let Some(preset) = self.compare_preset {
+            let quirks = preset.quirks();
+            args.shift_quirk = quirks.shift;
+            args.load_store_quirk = quirks.load_store;
+            args.load_store_by_x_quirk = quirks.load_store_by_x;
+            args.jump0_quirk = quirks.jump0;
+            args.vblank_quirk = quirks.vblank;
+        }
+
+        Some(args)
+    }
+
+    /// Get the path to the ROM
+    pub fn rom(&self) -> &str {
+        self.rom.as_deref().unwrap_or_default()
+    }
+}
+
+/// Parse a hex address, with or without a leading "0x"
+pub fn parse_address(s: &str) -> Result<u16, String> {
+    u16::from_str_radix(s.trim_start_matches("0x"), 16)
+        .map_err(|_| format!("'{}' is not a valid hex address", s))
+}
+
+/// Parse a patch, as a hex address and the bytes to write there
+fn parse_patch(s: &str) -> Result<(u16, Vec<u8>), String> {
+    let (addr, hex) = s
+        .split_once(':')
+        .ok_or_else(|| format!("'{}' is not a patch like 2A0:00E0", s))?;
+    Ok((parse_address(addr)?, parse_hex_bytes(hex)?))
+}
+
+/// Parse bytes written as pairs of hex digits, like 00E0
+pub fn parse_hex_bytes(s: &str) -> Result<Vec<u8>, String> {
+    let invalid = || format!("'{}' is not a whole number of hex bytes", s);
+    if s.is_empty() || !s.len().is_multiple_of(2) || !s.is_ascii() {
+        return Err(invalid());
+    }
+
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|_| invalid()))
+        .collect()
+}
+
+/// Parse a watchpoint, as a hex address and the kind of access to watch for
+/// Without a kind, both reads and writes are watched.
+fn parse_watchpoint(s: &str) -> Result<(u16, WatchMode), String> {
+    match s.split_once(':') {
+        Some((addr, mode)) => Ok((parse_address(addr)?, mode.parse()?)),
+        None => Ok((parse_address(s)?, WatchMode::ReadWrite)),
+    }
+}
+
+/// Parse a volume between 0.0 and 1.0
+fn parse_volume(s: &str) -> Result<f32, String> {
+    let volume: f32 = s
+        .parse()
+        .map_err(|_| format!("'{}' is not a valid volume", s))?;
+
+    if (0.0..=1.0).contains(&volume) {
+        Ok(volume)
+    } else {
+        Err(format!("volume must be between 0.0 and 1.0, got {}", volume))
+    }
+}
+
+/// Parse a pixel gap, which can't be negative
+fn parse_pixel_gap(s: &str) -> Result<f32, String> {
+    let gap: f32 = s
+        .parse()
+        .map_err(|_| format!("'{}' is not a valid pixel gap", s))?;
+
+    if gap >= 0.0 {
+        Ok(gap)
+    } else {
+        Err(format!("pixel gap can't be negative, got {}", gap))
+    }
+}
@@ -0,0 +1,172 @@
+use crate::chip8::AudioSink;
+use crate::emulator::args::RunArgs;
+use crate::emulator::{Emulator, TurboActive};
+use bevy::prelude::*;
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{BufferSize, Sample, SampleFormat, Stream, StreamConfig};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+// number of frames in each audio buffer
+const BUFFER_FRAMES: u32 = 512;
+
+// most samples that can wait to be played, as a fraction of a second
+// if the emulator runs ahead of the device, the oldest samples are dropped to keep the delay down
+const MAX_QUEUED_SECONDS: f32 = 0.1;
+
+/// Samples from the emulator waiting to be played
+/// Shared with the audio thread.
+#[derive(Default)]
+pub struct SampleQueue {
+    samples: Mutex<VecDeque<f32>>,
+
+    /// Whether the samples should be played silently
+    muted: AtomicBool,
+}
+
+/// The samples being sent to the audio device, if it could be opened
+#[derive(Resource)]
+pub struct Beeper {
+    queue: Option<Arc<SampleQueue>>,
+}
+
+/// Sends the emulator's samples to the audio device
+struct CpalSink {
+    queue: Arc<SampleQueue>,
+    sample_rate: u32,
+}
+
+impl AudioSink for CpalSink {
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    fn output_sample(&mut self, sample: f32) {
+        let max = (self.sample_rate as f32 * MAX_QUEUED_SECONDS) as usize;
+        let mut samples = self.queue.samples.lock().expect("Sample queue was poisoned!");
+        if samples.len() >= max {
+            samples.pop_front();
+        }
+        samples.push_back(sample);
+    }
+}
+
+/// Open the audio device and send the emulator's sound to it, unless the emulator is muted
+pub fn audio_setup(mut commands: Commands, args: Res<RunArgs>, mut emu: ResMut<Emulator>) {
+    let mut queue = None;
+
+    if !args.mute {
+        let shared = Arc::new(SampleQueue::default());
+        if let Some(sample_rate) = spawn_audio_thread(shared.clone(), args.audio_volume) {
+            emu.state.set_audio_sink(Box::new(CpalSink {
+                queue: shared.clone(),
+                sample_rate,
+            }));
+            queue = Some(shared);
+        }
+    }
+
+    commands.insert_resource(Beeper { queue });
+}
+
+/// Let the audio thread know whether the sound should be heard
+/// The sound is kept quiet in turbo, where the beep would sound far too often.
+pub fn update_tone(turbo: Res<TurboActive>, beeper: Res<Beeper>) {
+    if let Some(queue) = &beeper.queue {
+        queue.muted.store(turbo.0, Ordering::Relaxed);
+    }
+}
+
+/// Start a thread which plays the samples in the queue, returning the device's sample rate
+/// Returns None if the device couldn't be opened.
+fn spawn_audio_thread(queue: Arc<SampleQueue>, volume: f32) -> Option<u32> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        // the stream must stay on this thread for as long as it is playing
+        let _stream = match open_stream(queue, volume) {
+            Ok((stream, sample_rate)) => {
+                // nothing is waiting on the rate if the app has already gone
+                let _ = sender.send(Some(sample_rate));
+                stream
+            }
+            Err(e) => {
+                warn!("Unable to open audio device, continuing without sound: {}", e);
+                let _ = sender.send(None);
+                return;
+            }
+        };
+
+        loop {
+            thread::park();
+        }
+    });
+
+    receiver.recv().ok().flatten()
+}
+
+/// Open the default output device and start a stream playing the queued samples
+/// Returns the stream along with its sample rate.
+fn open_stream(queue: Arc<SampleQueue>, volume: f32) -> Result<(Stream, u32), String> {
+    let device = cpal::default_host()
+        .default_output_device()
+        .ok_or("no output device available")?;
+    let supported_config = device
+        .default_output_config()
+        .map_err(|e| e.to_string())?;
+
+    let sample_format = supported_config.sample_format();
+    let mut config: StreamConfig = supported_config.into();
+    config.buffer_size = BufferSize::Fixed(BUFFER_FRAMES);
+
+    let stream = match sample_format {
+        SampleFormat::F32 => build_stream::<f32>(&device, &config, queue, volume),
+        SampleFormat::I16 => build_stream::<i16>(&device, &config, queue, volume),
+        SampleFormat::U16 => build_stream::<u16>(&device, &config, queue, volume),
+    }?;
+    stream.play().map_err(|e| e.to_string())?;
+
+    Ok((stream, config.sample_rate.0))
+}
+
+/// Build a stream for a given sample format
+fn build_stream<T: Sample>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    queue: Arc<SampleQueue>,
+    volume: f32,
+) -> Result<Stream, String> {
+    let channels = config.channels as usize;
+
+    // silence the output if the device stops working
+    let silenced = Arc::new(AtomicBool::new(false));
+    let silenced_on_error = silenced.clone();
+
+    device
+        .build_output_stream(
+            config,
+            move |data: &mut [T], _: &cpal::OutputCallbackInfo| {
+                let playing =
+                    !queue.muted.load(Ordering::Relaxed) && !silenced.load(Ordering::Relaxed);
+                let mut samples = queue.samples.lock().expect("Sample queue was poisoned!");
+
+                // play silence if the emulator has fallen behind
+                for frame in data.chunks_mut(channels) {
+                    let queued = samples.pop_front().unwrap_or_default();
+                    let value: f32 = if playing { queued * volume } else { 0.0 };
+
+                    for sample in frame.iter_mut() {
+                        *sample = Sample::from(&value);
+                    }
+                }
+            },
+            move |e| {
+                warn!("Audio device error, silencing output: {}", e);
+                silenced_on_error.store(true, Ordering::Relaxed);
+            },
+        )
+        .map_err(|e| e.to_string())
+}
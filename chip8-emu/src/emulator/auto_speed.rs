@@ -0,0 +1,113 @@
+use crate::emulator::args::RunArgs;
+use crate::emulator::rewind::RewindState;
+use crate::emulator::{get_window_title, Emulator, TurboActive};
+use bevy::prelude::*;
+
+// the range the speed is adjusted within, in instructions per frame
+const MIN_AUTO_CYCLES_PER_FRAME: u32 = 1;
+const MAX_AUTO_CYCLES_PER_FRAME: u32 = 200;
+
+// frames the delay timer can run for before the ROM is taken to be sleeping away spare time
+const SLEEP_FRAMES: u32 = 10;
+
+// frames the delay timer can sit at zero after running out before the ROM is taken to have
+// stopped using it, rather than being too slow to set it again
+const LATE_FRAMES: u32 = 10;
+
+// frames to adjust the speed for, before settling on it
+const LOCK_FRAMES: u32 = 300;
+
+/// Adjusts the speed to suit the ROM, based on how it uses the delay timer
+/// A ROM that is too fast spends its frames waiting for the timer to run out, and one that is
+/// too slow leaves the timer at zero for a while before getting round to setting it again.
+#[derive(Resource, Debug, Default)]
+pub struct SpeedController {
+    /// The number of frames watched so far
+    frames: u32,
+
+    /// The number of frames in a row the delay timer has been running
+    running_frames: u32,
+
+    /// The number of frames in a row the delay timer has been at zero since it ran out, if it
+    /// has run out
+    expired_frames: Option<u32>,
+
+    /// Whether the speed has been settled on
+    locked: bool,
+}
+
+impl SpeedController {
+    /// Watch the delay timer at the end of a frame, returning a new speed if it should change
+    pub fn update(&mut self, delay_timer: u8, cycles_per_frame: u32) -> Option<u32> {
+        if self.locked {
+            return None;
+        }
+
+        self.frames += 1;
+        if self.frames >= LOCK_FRAMES {
+            self.locked = true;
+            debug!("Auto speed settled on {} cycles/frame", cycles_per_frame);
+            return None;
+        }
+
+        let too_fast = if delay_timer > 0 {
+            self.running_frames += 1;
+            self.expired_frames = None;
+            self.running_frames > SLEEP_FRAMES
+        } else {
+            if self.running_frames > 0 {
+                self.expired_frames = Some(0);
+            }
+            self.running_frames = 0;
+            false
+        };
+
+        // the timer runs out at the end of a frame, so the ROM gets one frame to set it again
+        let too_slow = match &mut self.expired_frames {
+            Some(frames) => {
+                *frames += 1;
+                (2..=LATE_FRAMES).contains(frames)
+            }
+            None => false,
+        };
+
+        if too_fast && cycles_per_frame > MIN_AUTO_CYCLES_PER_FRAME {
+            Some(cycles_per_frame - 1)
+        } else if too_slow && cycles_per_frame < MAX_AUTO_CYCLES_PER_FRAME {
+            Some(cycles_per_frame + 1)
+        } else {
+            None
+        }
+    }
+}
+
+/// Adjust the speed once a frame has passed, if asked to
+pub fn auto_speed_update(
+    args: Res<RunArgs>,
+    turbo: Res<TurboActive>,
+    rewind_state: Res<RewindState>,
+    mut controller: ResMut<SpeedController>,
+    mut emu: ResMut<Emulator>,
+    mut windows: ResMut<Windows>,
+) {
+    // the timer only says how fast the ROM is at the usual speed, going forward
+    if !args.auto_speed || turbo.0 || *rewind_state != RewindState::Forward {
+        return;
+    }
+
+    let delay_timer = emu.state.snapshot_registers().dt;
+    let Some(cycles_per_frame) = controller.update(delay_timer, emu.cycles_per_frame) else {
+        return;
+    };
+    debug!(
+        "Auto speed changed from {} to {} cycles/frame",
+        emu.cycles_per_frame, cycles_per_frame
+    );
+    emu.cycles_per_frame = cycles_per_frame;
+
+    // show the new speed in the window title
+    windows
+        .get_primary_mut()
+        .expect("Unable to get primary window!")
+        .set_title(get_window_title(&args, cycles_per_frame));
+}
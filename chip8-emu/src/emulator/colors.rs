@@ -0,0 +1,95 @@
+use crate::emulator::args::RunArgs;
+use crate::emulator::{OFF_COLOR, ON_COLOR, PALETTE};
+use bevy::prelude::*;
+use std::str::FromStr;
+
+// colors that can be given by name
+const NAMED_COLORS: [(&str, Color); 10] = [
+    ("black", Color::BLACK),
+    ("white", Color::WHITE),
+    ("red", Color::RED),
+    ("green", Color::GREEN),
+    ("blue", Color::BLUE),
+    ("yellow", Color::YELLOW),
+    ("cyan", Color::CYAN),
+    ("magenta", Color::FUCHSIA),
+    ("gray", Color::GRAY),
+    ("amber", Color::rgb(1.0, 0.69, 0.0)),
+];
+
+// on and off colors for each theme
+const THEMES: [(&str, Color, Color); 5] = [
+    ("classic", Color::WHITE, Color::BLACK),
+    ("amber", Color::rgb(1.0, 0.69, 0.0), Color::rgb(0.1, 0.06, 0.0)),
+    ("green", Color::rgb(0.2, 1.0, 0.2), Color::rgb(0.0, 0.08, 0.0)),
+    ("lcd", Color::rgb(0.18, 0.24, 0.11), Color::rgb(0.6, 0.7, 0.36)),
+    ("matrix", Color::rgb(0.0, 1.0, 0.25), Color::rgb(0.0, 0.02, 0.0)),
+];
+
+/// A display color, given as a hex code like #00FF00 or by name
+#[derive(Debug, Clone, Copy)]
+pub struct Chip8Color(pub Color);
+
+impl FromStr for Chip8Color {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.to_ascii_lowercase();
+        if let Some((_, color)) = NAMED_COLORS.iter().find(|(n, _)| *n == name) {
+            return Ok(Chip8Color(*color));
+        }
+
+        Color::hex(s.trim_start_matches('#'))
+            .map(Chip8Color)
+            .map_err(|_| format!("'{}' is not a hex color or a known color name", s))
+    }
+}
+
+/// A pair of on and off colors
+#[derive(Debug, Clone, Copy)]
+pub struct Theme {
+    on: Color,
+    off: Color,
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let name = s.to_ascii_lowercase();
+        THEMES
+            .iter()
+            .find(|(n, _, _)| *n == name)
+            .map(|(_, on, off)| Theme { on: *on, off: *off })
+            .ok_or_else(|| format!("unknown theme '{}'", s))
+    }
+}
+
+/// Mix two colors, from all of `from` at 0.0 to all of `to` at 1.0
+pub fn blend(from: Color, to: Color, amount: f32) -> Color {
+    let [r1, g1, b1, a1] = from.as_rgba_f32();
+    let [r2, g2, b2, a2] = to.as_rgba_f32();
+    let mix = |a: f32, b: f32| a + (b - a) * amount;
+
+    Color::rgba(mix(r1, r2), mix(g1, g2), mix(b1, b2), mix(a1, a2))
+}
+
+/// Get the display palette from the color arguments
+/// Colors given on their own take priority over the theme.
+pub fn get_palette(args: &RunArgs) -> [Color; 16] {
+    let theme = args.theme.unwrap_or(Theme {
+        on: ON_COLOR,
+        off: OFF_COLOR,
+    });
+    let mut on = args.on_color.map_or(theme.on, |c| c.0);
+    let mut off = args.off_color.map_or(theme.off, |c| c.0);
+
+    if args.invert {
+        std::mem::swap(&mut on, &mut off);
+    }
+
+    let mut palette = PALETTE;
+    palette[0] = off;
+    palette[1] = on;
+    palette
+}
@@ -0,0 +1,139 @@
+use crate::chip8::Chip8;
+use crate::emulator::args::RunArgs;
+use crate::emulator::rewind::RewindState;
+use crate::emulator::startup_systems::make_chip8;
+use crate::emulator::{Coordinate, Emulator, EmulatorTag, TurboActive};
+use crate::error::{Chip8Error, EmulatorError};
+use crate::input::Input::{Pressed, Unpressed};
+use bevy::prelude::*;
+
+// the tags of the pixels of the first and second emulators
+pub const EMULATOR_A: u8 = 0;
+pub const EMULATOR_B: u8 = 1;
+
+// the colors of pixels that differ between the displays, and that are on in both, in diff mode
+const DIFF_COLOR: Color = Color::RED;
+const MATCH_COLOR: Color = Color::GREEN;
+
+/// The second emulator, running the ROM being compared against
+#[derive(Resource)]
+pub struct Chip8B {
+    state: Chip8,
+
+    /// The error that halted the emulator, if any
+    error: Option<EmulatorError>,
+}
+
+/// Get the number of pixels the displays take up together, laid out side by side
+pub fn get_layout_size(resolution: (usize, usize), args: &RunArgs) -> (usize, usize) {
+    if args.compare.is_some() {
+        (resolution.0 * 2, resolution.1)
+    } else {
+        resolution
+    }
+}
+
+/// Get where a display starts, with the second one just to the right of the first
+pub fn get_display_origin(
+    origin: Vec2,
+    pixel_size: (f32, f32),
+    resolution: (usize, usize),
+    tag: &EmulatorTag,
+) -> Vec2 {
+    origin + Vec2::new(tag.0 as f32 * resolution.0 as f32 * pixel_size.0, 0.0)
+}
+
+/// Make the second emulator, if a ROM to compare against was given
+pub fn compare_setup(mut commands: Commands, args: Res<RunArgs>) -> Result<(), Chip8Error> {
+    let Some(compare_args) = args.compare_args() else {
+        return Ok(());
+    };
+
+    commands.insert_resource(Chip8B {
+        state: make_chip8(&compare_args)?,
+        error: None,
+    });
+    info!("Comparing against {}", compare_args.rom());
+
+    Ok(())
+}
+
+/// Give the second emulator the same keys as the first
+pub fn compare_input(emu: Res<Emulator>, chip8_b: Option<ResMut<Chip8B>>) {
+    let Some(mut chip8_b) = chip8_b else {
+        return;
+    };
+
+    let (keys, keys_b) = (emu.state.pressed_keys(), chip8_b.state.pressed_keys());
+    for key in 0..16 {
+        let pressed = keys & (1 << key) != 0;
+        if pressed != (keys_b & (1 << key) != 0) {
+            chip8_b
+                .state
+                .change_input(if pressed { Pressed(key) } else { Unpressed(key) })
+                .expect("Unable to change input!");
+        }
+    }
+}
+
+/// Run a frame of the second emulator, at the same speed as the first
+pub fn compare_update(
+    args: Res<RunArgs>,
+    turbo: Res<TurboActive>,
+    rewind_state: Res<RewindState>,
+    emu: Res<Emulator>,
+    chip8_b: Option<ResMut<Chip8B>>,
+) {
+    let Some(mut chip8_b) = chip8_b else {
+        return;
+    };
+
+    // the second emulator can't rewind, so it waits for the first to catch up
+    if chip8_b.error.is_some() || *rewind_state != RewindState::Forward {
+        return;
+    }
+
+    let cycles = if turbo.0 {
+        emu.cycles_per_frame.saturating_mul(args.turbo_multiplier)
+    } else {
+        emu.cycles_per_frame
+    };
+    for _ in 0..cycles {
+        if let Err(e) = chip8_b.state.do_next_instruction() {
+            let pc = chip8_b.state.snapshot_registers().pc;
+            error!("Compared emulator error at {:#05x}: {}", pc, e);
+            chip8_b.error = Some(e);
+            return;
+        }
+    }
+    chip8_b.state.do_frame();
+}
+
+/// Update the second display, or show how it differs from the first in diff mode
+pub fn compare_display(
+    args: Res<RunArgs>,
+    emu: Res<Emulator>,
+    chip8_b: Option<Res<Chip8B>>,
+    mut pixels_query: Query<(&Coordinate, &EmulatorTag, &mut Sprite)>,
+) {
+    let Some(chip8_b) = chip8_b else {
+        return;
+    };
+
+    for (coord, _, mut pixel) in pixels_query
+        .iter_mut()
+        .filter(|(_, tag, _)| tag.0 == EMULATOR_B)
+    {
+        let color = chip8_b.state.get_pixel_color(coord.x, coord.y) as usize;
+        pixel.color = if args.diff_mode {
+            let color_a = emu.state.get_pixel_color(coord.x, coord.y) as usize;
+            match (color_a > 0, color > 0) {
+                (true, true) => MATCH_COLOR,
+                (false, false) => emu.palette[0],
+                _ => DIFF_COLOR,
+            }
+        } else {
+            emu.palette[color]
+        };
+    }
+}
@@ -0,0 +1,127 @@
+use crate::chip8::WrapMode;
+use crate::emulator::colors::Chip8Color;
+use serde::{Deserialize, Serialize};
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+// extension added to the ROM path to get the path to its config file
+const ROM_CONFIG_EXTENSION: &str = ".toml";
+
+/// Options kept in a TOML file, so that a ROM runs the same way every time
+/// Options that aren't given are left to the command line arguments.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConfigFile {
+    /// Whether 8XY6 and 8XYE shift VX in place, ignoring VY
+    pub shift_quirk: Option<bool>,
+
+    /// Whether FX55 and FX65 leave I unchanged
+    pub load_store_quirk: Option<bool>,
+
+    /// Whether DXYN waits for the next frame before drawing
+    pub vblank_quirk: Option<bool>,
+
+    /// Whether BNNN jumps to NNN plus VX instead of V0
+    pub jump0_quirk: Option<bool>,
+
+    /// What happens to sprites drawn past the edges of the display
+    pub wrap_mode: Option<WrapMode>,
+
+    /// The number of instructions to run each frame
+    pub cycles_per_frame: Option<u32>,
+
+    /// The rate at which the timers count down, in hz
+    pub timer_frequency: Option<u8>,
+
+    /// The color of pixels that are on, by name or as a hex code
+    pub on_color: Option<String>,
+
+    /// The color of pixels that are off, by name or as a hex code
+    pub off_color: Option<String>,
+
+    /// The size of each pixel in the window
+    pub scale: Option<u32>,
+
+    /// Path to a TOML keymap file, or a built-in layout
+    pub keymap_path: Option<String>,
+}
+
+/// Errors that can occur while reading a config file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    /// The file could not be read
+    Unreadable(String),
+
+    /// The file is not valid TOML, or has options of the wrong type
+    InvalidToml(String),
+
+    /// A color is not a known name or hex code
+    InvalidColor(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Unreadable(e) => write!(f, "unable to read config: {}", e),
+            ConfigError::InvalidToml(e) => write!(f, "invalid config: {}", e),
+            ConfigError::InvalidColor(e) => write!(f, "invalid color in config: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl ConfigFile {
+    /// Read a config file
+    pub fn from_file(path: &Path) -> Result<ConfigFile, ConfigError> {
+        let toml =
+            std::fs::read_to_string(path).map_err(|e| ConfigError::Unreadable(e.to_string()))?;
+        let config: ConfigFile =
+            toml::from_str(&toml).map_err(|e| ConfigError::InvalidToml(e.to_string()))?;
+
+        // check the colors now, so they can't fail once the options are being set
+        for color in [&config.on_color, &config.off_color].into_iter().flatten() {
+            Chip8Color::from_str(color).map_err(ConfigError::InvalidColor)?;
+        }
+
+        Ok(config)
+    }
+
+    /// Read the config files that are looked for when none is given
+    /// The config in the home directory is read first, and then the one next to the ROM, so the
+    /// ROM's options win. Files that don't exist are skipped, and ones that can't be read are
+    /// reported and ignored.
+    pub fn load_default(rom_path: &Path) -> Vec<ConfigFile> {
+        let mut paths: Vec<PathBuf> = Vec::new();
+        if let Some(home) = home_dir() {
+            paths.push(home.join(".chip8emu").join("config.toml"));
+        }
+        paths.push(rom_config_path(rom_path));
+
+        paths
+            .iter()
+            .filter(|path| path.exists())
+            .filter_map(|path| {
+                ConfigFile::from_file(path)
+                    .map_err(|e| eprintln!("Unable to use {}: {}", path.display(), e))
+                    .ok()
+            })
+            .collect()
+    }
+}
+
+/// Get the home directory of the user
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+/// Get the path to the config file for a ROM
+fn rom_config_path(rom_path: &Path) -> PathBuf {
+    let mut path: OsString = rom_path.as_os_str().to_owned();
+    path.push(ROM_CONFIG_EXTENSION);
+    PathBuf::from(path)
+}
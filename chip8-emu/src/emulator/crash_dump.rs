@@ -0,0 +1,24 @@
+use crate::chip8::Chip8;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Write the state of an emulator that has crashed to a timestamped file
+/// Where the dump was written, or why it couldn't be, is printed to stderr.
+pub fn write_crash_dump(state: &Chip8) {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let path = format!("crash_{}.txt", timestamp);
+
+    let result = File::create(&path).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        state.dump_state_to_writer(&mut writer)?;
+        writer.flush()
+    });
+    match result {
+        Ok(()) => eprintln!("Wrote the emulator state to {}", path),
+        Err(e) => eprintln!("Unable to write the emulator state to {}: {}", path, e),
+    }
+}
@@ -0,0 +1,270 @@
+use crate::chip8::{RegisterSnapshot, MEM_SIZE};
+use crate::emulator::args::{parse_address, parse_hex_bytes, RunArgs};
+use crate::emulator::debugger::DebuggerState;
+use crate::emulator::Emulator;
+use bevy::prelude::*;
+use serde_json::json;
+use std::mem;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::Duration;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+// the number of bytes of RAM returned when no length is given
+const DEFAULT_RAM_LEN: usize = 64;
+
+// how long a request waits for the emulator to copy RAM or the display before giving up
+const COPY_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// What the debug server can see of the emulator, and the breakpoints it has set
+/// The emulator copies the registers in once a frame. RAM and the display are only copied on
+/// the frame after a request asks for them, and the request waits until they are.
+pub struct DebugState {
+    /// The registers as of the last frame
+    registers: Option<RegisterSnapshot>,
+
+    /// RAM as of the last time it was asked for
+    ram: Vec<u8>,
+
+    /// The display as of the last time it was asked for, one row at a time
+    vram: Vec<Vec<bool>>,
+
+    /// Whether a request is waiting for RAM to be copied
+    ram_wanted: bool,
+
+    /// Whether a request is waiting for the display to be copied
+    vram_wanted: bool,
+
+    /// Addresses to pause execution at
+    /// These replace the debugger's breakpoints each frame.
+    breakpoints: Vec<u16>,
+
+    /// Bytes to write into RAM on the next frame, with the address they start at
+    patches: Vec<(u16, Vec<u8>)>,
+}
+
+/// The state shared with the debug server thread, if the server was started, and a signal for
+/// when the emulator has copied its state in
+#[derive(Resource)]
+pub struct DebugServer(Arc<(Mutex<DebugState>, Condvar)>);
+
+/// Start the debug server in the background, if a port was given
+pub fn debug_server_setup(mut commands: Commands, args: Res<RunArgs>) {
+    let Some(port) = args.debug_server else {
+        return;
+    };
+
+    let server = match Server::http(("127.0.0.1", port)) {
+        Ok(server) => server,
+        Err(e) => {
+            error!("Unable to start the debug server on port {}: {}", port, e);
+            return;
+        }
+    };
+    info!("Debug server listening on port {}", port);
+
+    let shared = Arc::new((
+        Mutex::new(DebugState {
+            registers: None,
+            ram: Vec::new(),
+            vram: Vec::new(),
+            ram_wanted: false,
+            vram_wanted: false,
+            breakpoints: args.breakpoint.clone(),
+            patches: Vec::new(),
+        }),
+        Condvar::new(),
+    ));
+    let state = shared.clone();
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            handle_request(request, &state);
+        }
+    });
+
+    commands.insert_resource(DebugServer(shared));
+}
+
+/// Copy the emulator state to the debug server, and take the breakpoints and patches it has set
+pub fn debug_server_sync(
+    server: Option<Res<DebugServer>>,
+    mut emu: ResMut<Emulator>,
+    mut debugger: ResMut<DebuggerState>,
+) {
+    let Some(server) = server else {
+        return;
+    };
+    let (state, copied) = &*server.0;
+    let mut shared = state.lock().expect("Debug server state was poisoned!");
+
+    for (addr, bytes) in shared.patches.drain(..) {
+        match emu.state.inject_ram(addr, &bytes) {
+            Ok(()) => info!("Patched {} bytes at {:#05x}", bytes.len(), addr),
+            Err(e) => warn!("Unable to patch memory: {}", e),
+        }
+    }
+
+    shared.registers = Some(emu.state.snapshot_registers());
+
+    // RAM and the display are only copied for the requests waiting on them
+    if mem::take(&mut shared.ram_wanted) {
+        shared.ram = (0..MEM_SIZE as u16)
+            .map(|addr| emu.state.peek_ram(addr).unwrap_or_default())
+            .collect();
+    }
+    if mem::take(&mut shared.vram_wanted) {
+        let (width, height) = emu.state.display_size();
+        shared.vram = (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| emu.state.peek_vram(x, y).unwrap_or_default())
+                    .collect()
+            })
+            .collect();
+    }
+    copied.notify_all();
+
+    debugger.breakpoints.set_addresses(&shared.breakpoints);
+}
+
+/// Ask the emulator to copy RAM or the display on its next frame, and wait until it has
+fn wait_for_copy<'a>(
+    mut state: MutexGuard<'a, DebugState>,
+    copied: &Condvar,
+    wanted: fn(&mut DebugState) -> &mut bool,
+) -> Result<MutexGuard<'a, DebugState>, (u16, String)> {
+    *wanted(&mut state) = true;
+    let (state, timeout) = copied
+        .wait_timeout_while(state, COPY_TIMEOUT, |state| *wanted(state))
+        .expect("Debug server state was poisoned!");
+
+    if timeout.timed_out() {
+        return Err((503, "the emulator did not respond in time".to_string()));
+    }
+    Ok(state)
+}
+
+/// Answer a request to the debug server
+fn handle_request(request: Request, shared: &(Mutex<DebugState>, Condvar)) {
+    let (path, query) = request.url().split_once('?').unwrap_or((request.url(), ""));
+    let param = |name: &str| {
+        query
+            .split('&')
+            .filter_map(|pair| pair.split_once('='))
+            .find(|(key, _)| *key == name)
+            .map(|(_, value)| value.to_string())
+    };
+
+    let (state, copied) = shared;
+    let result = {
+        let mut state = state.lock().expect("Debug server state was poisoned!");
+        match (request.method(), path) {
+            (Method::Get, "/registers") => get_registers(&state),
+            (Method::Get, "/ram") => wait_for_copy(state, copied, |state| &mut state.ram_wanted)
+                .and_then(|state| get_ram(&state, param("start"), param("len"))),
+            (Method::Get, "/vram") => wait_for_copy(state, copied, |state| &mut state.vram_wanted)
+                .map(|state| json!(state.vram)),
+            (Method::Post, "/breakpoint") => {
+                parse_breakpoint(param("addr")).map(|addr| {
+                    if !state.breakpoints.contains(&addr) {
+                        state.breakpoints.push(addr);
+                    }
+                    json!(state.breakpoints)
+                })
+            }
+            (Method::Delete, "/breakpoint") => parse_breakpoint(param("addr")).map(|addr| {
+                state.breakpoints.retain(|&breakpoint| breakpoint != addr);
+                json!(state.breakpoints)
+            }),
+            (Method::Post, "/patch") => parse_patch(param("addr"), param("data")).map(|patch| {
+                let response = json!({ "addr": patch.0, "len": patch.1.len() });
+                state.patches.push(patch);
+                response
+            }),
+            _ => Err((404, format!("no endpoint for {} {}", request.method(), path))),
+        }
+    };
+
+    let (status, body) = match result {
+        Ok(body) => (200, body),
+        Err((status, message)) => (status, json!({ "error": message })),
+    };
+    let response = Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(
+            Header::from_bytes("Content-Type", "application/json")
+                .expect("Content type header should be valid"),
+        );
+
+    if let Err(e) = request.respond(response) {
+        warn!("Unable to respond to a debug server request: {}", e);
+    }
+}
+
+/// Get the registers as JSON
+fn get_registers(state: &DebugState) -> Result<serde_json::Value, (u16, String)> {
+    let registers = state
+        .registers
+        .ok_or((503, "the emulator has not run yet".to_string()))?;
+
+    Ok(json!({
+        "v": registers.v,
+        "i": registers.i,
+        "pc": registers.pc,
+        "sp": registers.sp,
+        "dt": registers.dt,
+        "st": registers.st,
+    }))
+}
+
+/// Get a range of RAM as a hex string
+fn get_ram(
+    state: &DebugState,
+    start: Option<String>,
+    len: Option<String>,
+) -> Result<serde_json::Value, (u16, String)> {
+    let start = match start {
+        Some(start) => parse_address(&start).map_err(|e| (400, e))? as usize,
+        None => 0,
+    };
+    let len = match len {
+        Some(len) => len
+            .parse::<usize>()
+            .map_err(|_| (400, format!("'{}' is not a valid length", len)))?,
+        None => DEFAULT_RAM_LEN,
+    };
+
+    let bytes = state
+        .ram
+        .get(start..start.saturating_add(len))
+        .ok_or((400, format!("{:#05x} plus {} bytes is past the end of RAM", start, len)))?;
+    let hex: String = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+
+    Ok(json!({ "start": start, "len": len, "data": hex }))
+}
+
+/// Get the address of a breakpoint to add or remove
+fn parse_breakpoint(addr: Option<String>) -> Result<u16, (u16, String)> {
+    let addr = addr.ok_or((400, "no breakpoint address given".to_string()))?;
+    parse_address(&addr).map_err(|e| (400, e))
+}
+
+/// Get the address and bytes of a patch to write into RAM
+fn parse_patch(
+    addr: Option<String>,
+    data: Option<String>,
+) -> Result<(u16, Vec<u8>), (u16, String)> {
+    let addr = addr.ok_or((400, "no patch address given".to_string()))?;
+    let addr = parse_address(&addr).map_err(|e| (400, e))?;
+    let data = data.ok_or((400, "no patch data given".to_string()))?;
+    let bytes = parse_hex_bytes(&data).map_err(|e| (400, e))?;
+
+    if addr as usize + bytes.len() > MEM_SIZE {
+        return Err((
+            400,
+            format!("{:#05x} plus {} bytes is past the end of RAM", addr, bytes.len()),
+        ));
+    }
+
+    Ok((addr, bytes))
+}
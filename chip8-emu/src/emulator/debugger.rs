@@ -0,0 +1,289 @@
+use crate::chip8::{Breakpoint, BreakpointManager, MemoryRegion, DEFAULT_HISTORY_DEPTH};
+use crate::emulator::args::RunArgs;
+use crate::emulator::systems::run_instruction;
+use crate::emulator::{EmuEvent, Emulator, UI_FONT};
+use bevy::prelude::*;
+
+// text style of the debugger overlay
+const OVERLAY_FONT_SIZE: f32 = 16.0;
+const OVERLAY_COLOR: Color = Color::WHITE;
+
+// the number of bytes of memory shown, starting at I
+const MEMORY_BYTES: u16 = 8;
+
+/// Whether the emulator is running freely, paused in the debugger, or stopped by an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AppState {
+    Running,
+    Debugging,
+    Halted,
+}
+
+/// The state of the debugger
+#[derive(Resource)]
+pub struct DebuggerState {
+    /// Addresses and conditions to pause execution at
+    pub breakpoints: BreakpointManager,
+
+    /// Whether the emulator is executing one instruction at a time
+    pub single_step: bool,
+
+    /// An address to fast-forward to and then pause at, which is forgotten once reached
+    pub run_until: Option<u16>,
+}
+
+/// Sent when a breakpoint pauses the emulator, or the address being run until is reached
+#[derive(Debug, Clone)]
+pub struct BreakpointHit(pub Breakpoint);
+
+/// Marker for the text showing the registers while debugging
+#[derive(Component)]
+pub struct DebuggerOverlay;
+
+/// Make the debugger state and overlay
+pub fn debugger_setup(
+    mut commands: Commands,
+    args: Res<RunArgs>,
+    asset_server: Res<AssetServer>,
+) {
+    let mut breakpoints = BreakpointManager::default();
+    for &addr in &args.breakpoint {
+        breakpoints.add(Breakpoint::AtAddress(addr));
+    }
+    for breakpoint in &args.break_when {
+        breakpoints.add(breakpoint.clone());
+    }
+
+    commands.insert_resource(DebuggerState {
+        breakpoints,
+        single_step: args.pause_on_start,
+        run_until: args.run_until,
+    });
+
+    commands.spawn((
+        DebuggerOverlay,
+        TextBundle {
+            visibility: Visibility { is_visible: false },
+            ..TextBundle::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load(UI_FONT),
+                    font_size: OVERLAY_FONT_SIZE,
+                    color: OVERLAY_COLOR,
+                },
+            )
+            .with_style(Style {
+                position_type: PositionType::Absolute,
+                position: UiRect {
+                    top: Val::Px(5.0),
+                    left: Val::Px(5.0),
+                    ..default()
+                },
+                ..default()
+            })
+        },
+    ));
+}
+
+/// Check if a breakpoint has been hit, or the address being run until has been reached
+/// Returns true, and sends the breakpoint that was hit, if so.
+pub fn check_breakpoints(
+    emu: &Emulator,
+    debugger: &mut DebuggerState,
+    hits: &mut EventWriter<BreakpointHit>,
+) -> bool {
+    let registers = emu.state.snapshot_registers();
+    let pc = registers.pc;
+
+    let hit = if debugger.run_until == Some(pc) {
+        debugger.run_until = None;
+        Some(Breakpoint::AtAddress(pc))
+    } else {
+        debugger
+            .breakpoints
+            .check_breakpoints(&registers, emu.state.total_cycles())
+            .cloned()
+    };
+
+    match hit {
+        Some(breakpoint) => {
+            hits.send(BreakpointHit(breakpoint));
+            true
+        }
+        None => false,
+    }
+}
+
+/// Pause the emulator when a breakpoint is hit
+pub fn breakpoint_hit(
+    mut hits: EventReader<BreakpointHit>,
+    mut debugger: ResMut<DebuggerState>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    let Some(BreakpointHit(breakpoint)) = hits.iter().last() else {
+        return;
+    };
+    info!("Breakpoint hit: {}", breakpoint);
+
+    debugger.single_step = true;
+    app_state
+        .set(AppState::Debugging)
+        .expect("Unable to pause the emulator!");
+}
+
+/// Pause the emulator if the last instruction accessed a watched address
+/// Returns true if the emulator was paused.
+pub fn check_watchpoints(
+    emu: &Emulator,
+    debugger: &mut DebuggerState,
+    app_state: &mut State<AppState>,
+) -> bool {
+    let Some(event) = emu.state.watchpoint_hit() else {
+        return false;
+    };
+    info!(
+        "Watchpoint hit: {} at {:#05x} by the instruction at {:#05x}",
+        event.mode_triggered, event.addr, event.pc
+    );
+
+    debugger.single_step = true;
+    app_state
+        .set(AppState::Debugging)
+        .expect("Unable to pause the emulator!");
+
+    true
+}
+
+/// Start keeping history for the debugger to step back through, once the emulator is paused
+/// Recording every instruction slows the emulator down, so it is only done while debugging.
+pub fn debugger_enter(mut emu: ResMut<Emulator>) {
+    emu.state.enable_history(DEFAULT_HISTORY_DEPTH);
+}
+
+/// Stop keeping history once the emulator leaves the debugger
+pub fn debugger_exit(mut emu: ResMut<Emulator>) {
+    emu.state.disable_history();
+}
+
+/// Step through instructions while paused
+/// F5 executes the next instruction, Shift+F5 undoes the last one stepped through, and F9
+/// continues running.
+pub fn debugger_step(
+    keys: Res<Input<KeyCode>>,
+    args: Res<RunArgs>,
+    mut emu: ResMut<Emulator>,
+    mut debugger: ResMut<DebuggerState>,
+    mut app_state: ResMut<State<AppState>>,
+    mut events: EventWriter<EmuEvent>,
+) {
+    let shift = keys.any_pressed([KeyCode::LShift, KeyCode::RShift]);
+
+    if keys.just_pressed(KeyCode::F5) && shift {
+        if let Err(e) = emu.state.step_back() {
+            warn!("Unable to step back: {}", e);
+        }
+    } else if keys.just_pressed(KeyCode::F5) {
+        run_instruction(&mut emu, &args, &mut app_state, &mut events);
+    } else if keys.just_pressed(KeyCode::F9) {
+        debugger.single_step = false;
+        app_state
+            .set(AppState::Running)
+            .expect("Unable to resume the emulator!");
+    }
+}
+
+/// Show the registers while paused
+pub fn debugger_overlay(
+    emu: Res<Emulator>,
+    app_state: Res<State<AppState>>,
+    mut overlays: Query<(&mut Text, &mut Visibility), With<DebuggerOverlay>>,
+) {
+    let debugging = *app_state.current() == AppState::Debugging;
+
+    for (mut text, mut visibility) in &mut overlays {
+        visibility.is_visible = debugging;
+        if !debugging {
+            continue;
+        }
+
+        let registers = emu.state.snapshot_registers();
+
+        // show the special registers first, then the general-purpose registers four to a line
+        let mut lines: Vec<String> = vec![
+            format!(
+                "PC: {:#05x}  I: {:#05x}  SP: {}",
+                registers.pc, registers.i, registers.sp
+            ),
+            format!("DT: {:#04x}  ST: {:#04x}", registers.dt, registers.st),
+        ];
+        for (row, values) in registers.v.chunks(4).enumerate() {
+            lines.push(
+                values
+                    .iter()
+                    .enumerate()
+                    .map(|(col, value)| format!("V{:X}: {:#04x}", row * 4 + col, value))
+                    .collect::<Vec<String>>()
+                    .join("  "),
+            );
+        }
+
+        // show where each subroutine that has yet to return will return to, innermost last
+        let stack = emu.state.peek_stack();
+        if stack.is_empty() {
+            lines.push("Stack: empty".to_string());
+        } else {
+            lines.push(format!("Stack ({} deep):", emu.state.stack_depth()));
+            for addrs in stack.chunks(4) {
+                lines.push(
+                    addrs
+                        .iter()
+                        .map(|addr| format!("{:#05x}", addr))
+                        .collect::<Vec<String>>()
+                        .join("  "),
+                );
+            }
+        }
+
+        if let Some(event) = emu.state.watchpoint_hit() {
+            lines.push(format!(
+                "Watch: {} {:#05x} ({:#04x} -> {:#04x})",
+                event.mode_triggered, event.addr, event.old_value, event.new_value
+            ));
+        }
+        lines.push("[I]:".to_string());
+
+        // show the memory at I, colored by the region each byte is in
+        let style = text.sections[0].style.clone();
+        let mut sections = vec![TextSection::new(lines.join("\n"), style.clone())];
+        for offset in 0..MEMORY_BYTES {
+            // addresses past 0xFFFF are shown as missing, like those past the end of RAM
+            let addr = registers.i.checked_add(offset);
+            let byte = addr.and_then(|addr| Some((addr, emu.state.peek_ram(addr)?)));
+            let (value, color) = match byte {
+                Some((addr, byte)) => (
+                    format!(" {:02X}", byte),
+                    get_region_color(emu.state.memory_map().region_at(addr)),
+                ),
+                None => (" --".to_string(), get_region_color(MemoryRegion::Unmapped)),
+            };
+            let byte_style = TextStyle {
+                color,
+                ..style.clone()
+            };
+            sections.push(TextSection::new(value, byte_style));
+        }
+        sections.push(TextSection::new("\nF5: step  Shift+F5: step back  F9: continue", style));
+
+        text.sections = sections;
+    }
+}
+
+/// Get the color memory contents are shown in for a region
+fn get_region_color(region: MemoryRegion) -> Color {
+    match region {
+        MemoryRegion::FontData => Color::YELLOW,
+        MemoryRegion::ProgramCode => OVERLAY_COLOR,
+        MemoryRegion::ProgramData => Color::CYAN,
+        MemoryRegion::Unmapped => Color::GRAY,
+    }
+}
@@ -0,0 +1,101 @@
+use crate::error::Chip8Error;
+use reqwest::header::CONTENT_TYPE;
+use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// file extensions of ROMs
+const ROM_EXTENSIONS: [&str; 5] = ["ch8", "c8", "sc8", "xo8", "bin"];
+
+// content types that ROMs are served as
+const ROM_CONTENT_TYPES: [&str; 3] = [
+    "application/octet-stream",
+    "application/x-chip8",
+    "binary/octet-stream",
+];
+
+/// Get the path that the ROM at a URL is cached to
+/// Downloaded ROMs are kept in ~/.chip8emu/cache/, named after the hash of the URL.
+fn get_cache_path(url: &str) -> PathBuf {
+    let home = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .unwrap_or_default();
+    let hash: String = Sha256::digest(url.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect();
+
+    Path::new(&home)
+        .join(".chip8emu")
+        .join("cache")
+        .join(format!("{}.ch8", hash))
+}
+
+/// Check whether a URL ends in the file extension of a ROM
+fn has_rom_extension(url: &str) -> bool {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    Path::new(path)
+        .extension()
+        .map(|ext| ROM_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+/// Download the ROM at a URL, or use the copy cached from an earlier download
+/// Returns the path to the cached ROM, which is then loaded like any other ROM file.
+pub fn download_rom(url: &str, timeout: Duration) -> Result<PathBuf, Chip8Error> {
+    let path = get_cache_path(url);
+    if path.exists() {
+        return Ok(path);
+    }
+
+    eprintln!("Downloading ROM from {}", url);
+    let client = reqwest::blocking::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| Chip8Error::NetworkError(e.to_string()))?;
+    let response = client.get(url).send().map_err(|e| {
+        if e.is_timeout() {
+            Chip8Error::NetworkError(format!("timed out after {}s", timeout.as_secs()))
+        } else {
+            Chip8Error::NetworkError(e.to_string())
+        }
+    })?;
+
+    match response.status() {
+        StatusCode::OK => {}
+        StatusCode::NOT_FOUND => {
+            return Err(Chip8Error::NetworkError(format!("{} was not found (404)", url)))
+        }
+        status => return Err(Chip8Error::NetworkError(format!("server returned {}", status))),
+    }
+
+    // a web page is a common mistake for a link to a ROM, so check what was sent back
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| value.split(';').next().unwrap_or_default().trim().to_lowercase());
+    let is_rom_type = content_type
+        .as_deref()
+        .map_or(true, |value| ROM_CONTENT_TYPES.contains(&value));
+    if !is_rom_type && !has_rom_extension(url) {
+        return Err(Chip8Error::NetworkError(format!(
+            "{} is not a ROM (content type is {})",
+            url,
+            content_type.unwrap_or_default()
+        )));
+    }
+
+    let bytes = response
+        .bytes()
+        .map_err(|e| Chip8Error::NetworkError(e.to_string()))?;
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| Chip8Error::NetworkError(e.to_string()))?;
+    }
+    fs::write(&path, &bytes).map_err(|e| Chip8Error::NetworkError(e.to_string()))?;
+
+    Ok(path)
+}
@@ -0,0 +1,95 @@
+use crate::chip8::MemoryRegion;
+use crate::emulator::debugger::AppState;
+use crate::emulator::{Emulator, UI_FONT};
+use bevy::prelude::*;
+
+// style of the error overlay
+const TITLE_FONT_SIZE: f32 = 28.0;
+const MESSAGE_FONT_SIZE: f32 = 18.0;
+const TEXT_COLOR: Color = Color::WHITE;
+const BACKGROUND_COLOR: Color = Color::rgba(0.0, 0.0, 0.0, 0.8);
+
+/// Marker for the overlay shown when the emulator is halted by an error
+#[derive(Component)]
+pub struct ErrorOverlay;
+
+/// Marker for the text describing the error
+#[derive(Component)]
+pub struct ErrorMessage;
+
+/// Make the error overlay, which covers the whole window
+pub fn error_overlay_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let font = asset_server.load(UI_FONT);
+
+    commands
+        .spawn((
+            ErrorOverlay,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: BACKGROUND_COLOR.into(),
+                visibility: Visibility { is_visible: false },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                "Emulator Error",
+                TextStyle {
+                    font: font.clone(),
+                    font_size: TITLE_FONT_SIZE,
+                    color: TEXT_COLOR,
+                },
+            ));
+            parent.spawn((
+                ErrorMessage,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font,
+                        font_size: MESSAGE_FONT_SIZE,
+                        color: TEXT_COLOR,
+                    },
+                ),
+            ));
+        });
+}
+
+/// Show the error overlay while the emulator is halted
+pub fn error_overlay(
+    emu: Res<Emulator>,
+    app_state: Res<State<AppState>>,
+    mut overlays: Query<&mut Visibility, With<ErrorOverlay>>,
+    mut messages: Query<&mut Text, With<ErrorMessage>>,
+) {
+    let halted = *app_state.current() == AppState::Halted;
+
+    for mut visibility in &mut overlays {
+        visibility.is_visible = halted;
+    }
+
+    if !halted {
+        return;
+    }
+
+    if let Some(error) = &emu.error {
+        let pc = emu.state.snapshot_registers().pc;
+        let mut message = format!("{} (PC: {:#05x})", error, pc);
+
+        // point out when the PC has left the program
+        let region = emu.state.memory_map().region_at(pc);
+        if region != MemoryRegion::ProgramCode {
+            message += &format!("\nPC jumped into {} at {:#05x}", region, pc);
+        }
+
+        for mut text in &mut messages {
+            text.sections[0].value = message.clone();
+        }
+    }
+}
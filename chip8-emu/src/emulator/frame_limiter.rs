@@ -0,0 +1,31 @@
+use bevy::prelude::*;
+use std::time::{Duration, Instant};
+
+/// The number of frames run each second when none is given
+pub const DEFAULT_FPS: u32 = 60;
+
+/// The number of frames to run each second, whatever the refresh rate of the monitor
+/// The timers count down once a frame, so rates below 60 run programs in slow motion.
+#[derive(Resource, Debug)]
+pub struct TargetFrameRate(pub u32);
+
+impl Default for TargetFrameRate {
+    fn default() -> Self {
+        Self(DEFAULT_FPS)
+    }
+}
+
+/// Wait out whatever is left of the last frame, so that frames start at the target rate
+/// `spin_sleep` spins for the last part of the wait, as sleeping alone can overshoot by a
+/// millisecond or more.
+pub fn frame_limiter(target: Res<TargetFrameRate>, mut last_frame: Local<Option<Instant>>) {
+    let frame_time = Duration::from_secs_f64(1.0 / target.0.max(1) as f64);
+
+    if let Some(elapsed) = last_frame.map(|last| last.elapsed()) {
+        if elapsed < frame_time {
+            spin_sleep::sleep(frame_time - elapsed);
+        }
+    }
+
+    *last_frame = Some(Instant::now());
+}
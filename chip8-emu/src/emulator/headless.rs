@@ -0,0 +1,161 @@
+use crate::chip8::{Chip8, InstructionCoverage, Vram, HIRES_HEIGHT, HIRES_WIDTH};
+use crate::disasm::format_trace;
+use crate::emulator::args::RunArgs;
+use crate::emulator::colors::get_palette;
+use crate::emulator::crash_dump::write_crash_dump;
+use crate::emulator::inspectors::RecordingInspector;
+use crate::emulator::recording::GifRecorder;
+use crate::emulator::rom_dump::write_rom_dump;
+use crate::emulator::screenshot::save_screenshot;
+use crate::emulator::startup_systems::make_chip8;
+use log::info;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// time between frames
+const FRAME_DURATION: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Run the emulator without a window
+/// Instructions and frames are run at the same rate as with a window, until the program exits,
+/// an error occurs, or the frame limit is reached.
+pub fn run_headless(args: &RunArgs) {
+    let mut state = match make_chip8(args) {
+        Ok(state) => state,
+        Err(e) => {
+            eprintln!("Unable to start emulator: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if args.coverage {
+        state.enable_coverage_tracking();
+    }
+
+    let palette = get_palette(args);
+    let mut gif = args.record.as_ref().and_then(|path| {
+        GifRecorder::create(Path::new(path), palette[0], palette[1], args.record_fps)
+            .map_err(|e| eprintln!("Unable to record to {}: {}", path, e))
+            .ok()
+    });
+
+    let mut next_frame = Instant::now();
+    let mut frame: Vram = [[false; HIRES_WIDTH]; HIRES_HEIGHT];
+    let mut failed = false;
+    let mut recorder = RecordingInspector::default();
+    'frames: for _ in 0..args.max_frames {
+        for _ in 0..state.cycles_per_frame() {
+            let pc = state.snapshot_registers().pc;
+            if let Err(e) = state.step_with_inspector(&mut recorder) {
+                eprintln!("Emulator error at {:#05x}: {}", pc, e);
+
+                // show what led up to the error
+                let trace = state.get_trace();
+                if !trace.is_empty() {
+                    eprintln!("{}", format_trace(trace));
+                }
+                if args.dump_on_crash {
+                    write_crash_dump(&state);
+                }
+
+                failed = true;
+                break 'frames;
+            }
+        }
+
+        frame = *state.do_frame_with_inspector(&mut recorder).vram;
+        if let Some(gif) = &mut gif {
+            gif.record_frame(state.display_size(), |x, y| frame[y][x]);
+        }
+        if state.has_exited() {
+            break;
+        }
+
+        // wait for the next frame
+        next_frame += FRAME_DURATION;
+        if let Some(wait) = next_frame.checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+    }
+
+    info!(
+        "Ran {} instructions over {} frames",
+        state.total_cycles(),
+        recorder.frames
+    );
+
+    if args.dump_display {
+        print_display(&state);
+    }
+
+    if let Some(profiler) = state.opcode_profiler() {
+        if let Err(e) = profiler.report_to_writer(&mut std::io::stdout()) {
+            eprintln!("Unable to print opcode report: {}", e);
+        }
+    }
+
+    if let Some(coverage) = state.coverage() {
+        report_coverage(coverage, state.export_rom().len(), args);
+    }
+
+    if let (Some(gif), Some(path)) = (gif, &args.record) {
+        if let Err(e) = gif.finish() {
+            eprintln!("Unable to save recording to {}: {}", path, e);
+        }
+    }
+
+    if let Some(path) = &args.dump_rom {
+        write_rom_dump(&state, path);
+    }
+
+    if args.screenshot_on_exit {
+        let path = Path::new(&args.screenshot_output);
+        if let Err(e) = save_screenshot(
+            path,
+            state.display_size(),
+            |x, y| frame[y][x],
+            palette[1],
+            palette[0],
+            args.screenshot_scale,
+        ) {
+            eprintln!("Unable to save screenshot to {}: {}", path.display(), e);
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+/// Print how much of the ROM was executed, and write the LCOV tracefile if one was asked for
+fn report_coverage(coverage: &InstructionCoverage, rom_size: usize, args: &RunArgs) {
+    eprintln!(
+        "Covered {:.1}% of the ROM, executing from {} addresses",
+        coverage.coverage_percent(rom_size),
+        coverage.covered_count()
+    );
+
+    if let Some(path) = &args.coverage_lcov {
+        let written = File::create(path).and_then(|file| {
+            let mut w = BufWriter::new(file);
+            coverage.save_lcov(&mut w, args.rom(), rom_size)?;
+            w.flush()
+        });
+        if let Err(e) = written {
+            eprintln!("Unable to save coverage to {}: {}", path, e);
+        }
+    }
+}
+
+/// Print the display as text, with a # for each pixel that is on
+fn print_display(state: &Chip8) {
+    let (width, height) = state.display_size();
+
+    for y in 0..height {
+        let row: String = (0..width)
+            .map(|x| if state.get_pixel_color(x, y) > 0 { '#' } else { ' ' })
+            .collect();
+        println!("{}", row);
+    }
+}
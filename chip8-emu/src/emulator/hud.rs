@@ -0,0 +1,132 @@
+use crate::emulator::{Emulator, UI_FONT};
+use bevy::diagnostic::{Diagnostics, FrameTimeDiagnosticsPlugin};
+use bevy::prelude::*;
+use std::time::Duration;
+
+// the key that shows and hides the HUD
+const HUD_KEY: KeyCode = KeyCode::F3;
+
+// text style of the HUD
+const HUD_FONT_SIZE: f32 = 16.0;
+const HUD_COLOR: Color = Color::WHITE;
+const HUD_BACKGROUND: Color = Color::rgba(0.0, 0.0, 0.0, 0.6);
+
+// frames longer than this skip updating the HUD, so the emulator can catch up
+const FRAME_BUDGET: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+/// Whether the HUD is shown
+#[derive(Resource, Debug, Default)]
+pub struct HudVisible(pub bool);
+
+/// Counts the instructions run, to work out how many are run each second
+#[derive(Resource, Debug, Default)]
+pub struct CycleCounter {
+    /// The instructions run since the count was last taken
+    cycles: u32,
+
+    /// The time since the count was last taken
+    elapsed: Duration,
+
+    /// The instructions run each second, as of the last count
+    cycles_per_second: u32,
+}
+
+impl CycleCounter {
+    /// Count instructions that were run
+    pub fn add(&mut self, cycles: u32) {
+        self.cycles += cycles;
+    }
+}
+
+/// Marker for the background of the HUD
+#[derive(Component)]
+pub struct Hud;
+
+/// Marker for the text of the HUD
+#[derive(Component)]
+pub struct HudText;
+
+/// Make the HUD, hidden until it is toggled on
+pub fn hud_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            Hud,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        top: Val::Px(5.0),
+                        left: Val::Px(5.0),
+                        ..default()
+                    },
+                    padding: UiRect::all(Val::Px(4.0)),
+                    ..default()
+                },
+                background_color: HUD_BACKGROUND.into(),
+                visibility: Visibility { is_visible: false },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                HudText,
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load(UI_FONT),
+                        font_size: HUD_FONT_SIZE,
+                        color: HUD_COLOR,
+                    },
+                ),
+            ));
+        });
+}
+
+/// Show or hide the HUD when the HUD key is pressed
+pub fn hud_toggle(keys: Res<Input<KeyCode>>, mut visible: ResMut<HudVisible>) {
+    if keys.just_pressed(HUD_KEY) {
+        visible.0 = !visible.0;
+    }
+}
+
+/// Take the count of instructions run once a second
+pub fn cycle_counter_update(time: Res<Time>, mut counter: ResMut<CycleCounter>) {
+    counter.elapsed += time.delta();
+    if counter.elapsed >= Duration::from_secs(1) {
+        counter.cycles_per_second = (counter.cycles as f32 / counter.elapsed.as_secs_f32()) as u32;
+        counter.cycles = 0;
+        counter.elapsed = Duration::ZERO;
+    }
+}
+
+/// Show the frame rate, instruction rate, and timers in the HUD
+/// Updates are skipped on slow frames rather than slowing the emulator down further.
+pub fn hud_update(
+    visible: Res<HudVisible>,
+    time: Res<Time>,
+    diagnostics: Res<Diagnostics>,
+    counter: Res<CycleCounter>,
+    emu: Res<Emulator>,
+    mut huds: Query<&mut Visibility, With<Hud>>,
+    mut texts: Query<&mut Text, With<HudText>>,
+) {
+    for mut visibility in &mut huds {
+        visibility.is_visible = visible.0;
+    }
+    if !visible.0 || time.delta() > FRAME_BUDGET {
+        return;
+    }
+
+    let fps = diagnostics
+        .get(FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or_default();
+    let registers = emu.state.snapshot_registers();
+
+    for mut text in &mut texts {
+        text.sections[0].value = format!(
+            "FPS: {:.0}  IPS: {}\nDT: {:#04x}  ST: {:#04x}\nPC: {:#05x}  SP: {}",
+            fps, counter.cycles_per_second, registers.dt, registers.st, registers.pc, registers.sp
+        );
+    }
+}
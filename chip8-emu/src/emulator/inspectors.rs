@@ -0,0 +1,32 @@
+use crate::chip8::{Chip8Inspector, Vram};
+use crate::error::EmulatorError;
+use log::trace;
+
+/// Logs what the emulator does at trace level
+/// Instructions are already traced by the core, so only errors and frames are logged here.
+pub struct LogInspector;
+
+impl Chip8Inspector for LogInspector {
+    fn on_error(&mut self, error: &EmulatorError) {
+        trace!("Instruction failed: {}", error);
+    }
+
+    fn on_frame(&mut self, vram: &Vram) {
+        let lit = vram.iter().flatten().filter(|&&pixel| pixel).count();
+        trace!("Frame drawn with {} pixels on", lit);
+    }
+}
+
+/// Counts the frames the emulator draws
+/// The instructions run are counted by the core, in `total_cycles`.
+#[derive(Debug, Default)]
+pub struct RecordingInspector {
+    /// The number of frames drawn
+    pub frames: usize,
+}
+
+impl Chip8Inspector for RecordingInspector {
+    fn on_frame(&mut self, _vram: &Vram) {
+        self.frames += 1;
+    }
+}
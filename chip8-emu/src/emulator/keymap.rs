@@ -0,0 +1,235 @@
+use bevy::prelude::KeyCode::*;
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+
+// the original CHIP-8 keypad, laid over the left side of a QWERTY keyboard
+const QWERTY: [(KeyCode, u8); 16] = [
+    (Key1, 0x1),
+    (Key2, 0x2),
+    (Key3, 0x3),
+    (Key4, 0xC),
+    (Q, 0x4),
+    (W, 0x5),
+    (E, 0x6),
+    (R, 0xD),
+    (A, 0x7),
+    (S, 0x8),
+    (D, 0x9),
+    (F, 0xE),
+    (Z, 0xA),
+    (X, 0x0),
+    (C, 0xB),
+    (V, 0xF),
+];
+
+// the digits on the number pad, with A to F on the keys around them
+const NUMPAD: [(KeyCode, u8); 16] = [
+    (Numpad0, 0x0),
+    (Numpad1, 0x1),
+    (Numpad2, 0x2),
+    (Numpad3, 0x3),
+    (Numpad4, 0x4),
+    (Numpad5, 0x5),
+    (Numpad6, 0x6),
+    (Numpad7, 0x7),
+    (Numpad8, 0x8),
+    (Numpad9, 0x9),
+    (NumpadDecimal, 0xA),
+    (NumpadEnter, 0xB),
+    (NumpadDivide, 0xC),
+    (NumpadMultiply, 0xD),
+    (NumpadSubtract, 0xE),
+    (NumpadAdd, 0xF),
+];
+
+// keys that can be named in a keymap file, other than letters, digits, and the number pad digits
+const NAMED_KEYS: [(&str, KeyCode); 30] = [
+    ("space", Space),
+    ("enter", Return),
+    ("tab", Tab),
+    ("up", Up),
+    ("down", Down),
+    ("left", Left),
+    ("right", Right),
+    ("lshift", LShift),
+    ("rshift", RShift),
+    ("lcontrol", LControl),
+    ("rcontrol", RControl),
+    ("lalt", LAlt),
+    ("ralt", RAlt),
+    ("comma", Comma),
+    ("period", Period),
+    ("slash", Slash),
+    ("backslash", Backslash),
+    ("semicolon", Semicolon),
+    ("apostrophe", Apostrophe),
+    ("lbracket", LBracket),
+    ("rbracket", RBracket),
+    ("grave", Grave),
+    ("minus", Minus),
+    ("equals", Equals),
+    ("numpaddecimal", NumpadDecimal),
+    ("numpadenter", NumpadEnter),
+    ("numpaddivide", NumpadDivide),
+    ("numpadmultiply", NumpadMultiply),
+    ("numpadsubtract", NumpadSubtract),
+    ("numpadadd", NumpadAdd),
+];
+
+/// Errors that can occur while reading a keymap file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeymapError {
+    /// The file could not be read
+    Unreadable(String),
+
+    /// The file is not valid TOML, or has no [keys] table
+    InvalidToml(String),
+
+    /// A keyboard key name is not known
+    UnknownKey(String),
+
+    /// A CHIP-8 key is not a hex digit
+    InvalidChip8Key(String),
+
+    /// A CHIP-8 key is given to more than one keyboard key
+    DuplicateChip8Key(u8),
+
+    /// A CHIP-8 key is not given to any keyboard key
+    MissingChip8Key(u8),
+}
+
+impl fmt::Display for KeymapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeymapError::Unreadable(e) => write!(f, "unable to read keymap: {}", e),
+            KeymapError::InvalidToml(e) => write!(f, "invalid keymap: {}", e),
+            KeymapError::UnknownKey(name) => write!(f, "unknown keyboard key '{}'", name),
+            KeymapError::InvalidChip8Key(key) => {
+                write!(f, "'{}' is not a CHIP-8 key from 0x0 to 0xF", key)
+            }
+            KeymapError::DuplicateChip8Key(key) => {
+                write!(f, "CHIP-8 key {:#x} is mapped more than once", key)
+            }
+            KeymapError::MissingChip8Key(key) => write!(f, "CHIP-8 key {:#x} is not mapped", key),
+        }
+    }
+}
+
+impl std::error::Error for KeymapError {}
+
+/// The layout of a keymap file
+#[derive(Deserialize)]
+struct KeymapFile {
+    /// The CHIP-8 key for each keyboard key
+    keys: BTreeMap<String, String>,
+}
+
+/// Mapping between keyboard keys and the chip8 keys
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct Keymap(pub [(KeyCode, u8); 16]);
+
+impl Default for Keymap {
+    /// The QWERTY layout
+    fn default() -> Self {
+        Keymap(QWERTY)
+    }
+}
+
+impl Keymap {
+    /// The number pad layout
+    pub fn numpad() -> Self {
+        Keymap(NUMPAD)
+    }
+
+    /// Get a built-in layout by name, or read a keymap file
+    pub fn load(name_or_path: &str) -> Result<Self, KeymapError> {
+        match name_or_path.to_ascii_lowercase().as_str() {
+            "qwerty" => Ok(Keymap::default()),
+            "numpad" => Ok(Keymap::numpad()),
+            _ => Keymap::from_toml(Path::new(name_or_path)),
+        }
+    }
+
+    /// Read a keymap file
+    /// The [keys] table gives the CHIP-8 key for each keyboard key, like `"1" = "0x1"`, and every
+    /// CHIP-8 key must be given exactly once.
+    pub fn from_toml(path: &Path) -> Result<Self, KeymapError> {
+        let toml =
+            std::fs::read_to_string(path).map_err(|e| KeymapError::Unreadable(e.to_string()))?;
+        let file: KeymapFile =
+            toml::from_str(&toml).map_err(|e| KeymapError::InvalidToml(e.to_string()))?;
+
+        // each CHIP-8 key is filled in once
+        let mut keys: [Option<KeyCode>; 16] = [None; 16];
+        for (name, chip8_key) in &file.keys {
+            let key_code =
+                parse_key_code(name).ok_or_else(|| KeymapError::UnknownKey(name.clone()))?;
+            let chip8_key = u8::from_str_radix(chip8_key.trim_start_matches("0x"), 16)
+                .ok()
+                .filter(|&key| key <= 0xF)
+                .ok_or_else(|| KeymapError::InvalidChip8Key(chip8_key.clone()))?;
+
+            if keys[chip8_key as usize].replace(key_code).is_some() {
+                return Err(KeymapError::DuplicateChip8Key(chip8_key));
+            }
+        }
+
+        let mut keymap = QWERTY;
+        for (chip8_key, (entry, key_code)) in keymap.iter_mut().zip(keys).enumerate() {
+            let key_code = key_code.ok_or(KeymapError::MissingChip8Key(chip8_key as u8))?;
+            *entry = (key_code, chip8_key as u8);
+        }
+
+        Ok(Keymap(keymap))
+    }
+
+    /// Check if a keyboard key is mapped to a CHIP-8 key
+    pub fn contains(&self, key_code: KeyCode) -> bool {
+        self.0.iter().any(|(kc, _)| *kc == key_code)
+    }
+}
+
+/// Get a keyboard key from its name in a keymap file
+/// Names are a letter, a digit, "numpad" and a digit, or one of the named keys.
+fn parse_key_code(name: &str) -> Option<KeyCode> {
+    const LETTERS: [KeyCode; 26] = [
+        A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+    ];
+    const DIGITS: [KeyCode; 10] = [Key0, Key1, Key2, Key3, Key4, Key5, Key6, Key7, Key8, Key9];
+    const NUMPAD_DIGITS: [KeyCode; 10] = [
+        Numpad0, Numpad1, Numpad2, Numpad3, Numpad4, Numpad5, Numpad6, Numpad7, Numpad8, Numpad9,
+    ];
+
+    let name = name.to_ascii_lowercase();
+    let single = |name: &str| {
+        let mut chars = name.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+
+    if let Some(c) = single(&name) {
+        if c.is_ascii_lowercase() {
+            return Some(LETTERS[(c as u8 - b'a') as usize]);
+        }
+        if let Some(digit) = c.to_digit(10) {
+            return Some(DIGITS[digit as usize]);
+        }
+    }
+    if let Some(digit) = name
+        .strip_prefix("numpad")
+        .and_then(single)
+        .and_then(|c| c.to_digit(10))
+    {
+        return Some(NUMPAD_DIGITS[digit as usize]);
+    }
+
+    NAMED_KEYS
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, key_code)| *key_code)
+}
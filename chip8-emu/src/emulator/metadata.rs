@@ -0,0 +1,75 @@
+use crate::chip8::WrapMode;
+use serde::Deserialize;
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+// extension added to the ROM path to get the path to its metadata
+const SIDECAR_EXTENSION: &str = ".chip8meta";
+
+/// Information about a ROM, read from a JSON file next to it
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RomMetadata {
+    /// The name of the program
+    pub title: Option<String>,
+
+    /// Who wrote the program
+    pub author: Option<String>,
+
+    /// What the program is and how to use it
+    pub description: Option<String>,
+
+    /// The year the program was released
+    pub year: Option<u16>,
+
+    /// The emulator options the program needs to run correctly
+    pub quirks: QuirksConfig,
+}
+
+/// Emulator options needed by a ROM
+/// Options that aren't given are left to the command line arguments.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct QuirksConfig {
+    /// Whether 8XY6 and 8XYE shift VX in place, ignoring VY
+    pub shift_quirk: Option<bool>,
+
+    /// Whether FX55 and FX65 leave I unchanged
+    pub load_store_quirk: Option<bool>,
+
+    /// Whether BNNN jumps to NNN plus VX, where X is the high nibble of NNN, instead of V0
+    pub jump0_quirk: Option<bool>,
+
+    /// Whether DXYN waits for the next frame before drawing
+    pub vblank_quirk: Option<bool>,
+
+    /// What happens to sprites drawn past the edges of the display
+    pub wrap_mode: Option<WrapMode>,
+
+    /// The number of instructions that should be run each frame
+    pub cycles_per_frame: Option<u32>,
+}
+
+impl RomMetadata {
+    /// Read the metadata for a ROM from `<rom_path>.chip8meta`, if there is any
+    /// A metadata file that can't be parsed is reported and ignored.
+    pub fn load_from_sidecar(rom_path: &Path) -> Option<RomMetadata> {
+        let path = sidecar_path(rom_path);
+        let json = std::fs::read_to_string(&path).ok()?;
+
+        match serde_json::from_str(&json) {
+            Ok(metadata) => Some(metadata),
+            Err(e) => {
+                eprintln!("Unable to read ROM metadata from {}: {}", path.display(), e);
+                None
+            }
+        }
+    }
+}
+
+/// Get the path to the metadata file for a ROM
+fn sidecar_path(rom_path: &Path) -> PathBuf {
+    let mut path: OsString = rom_path.as_os_str().to_owned();
+    path.push(SIDECAR_EXTENSION);
+    PathBuf::from(path)
+}
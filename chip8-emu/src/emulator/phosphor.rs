@@ -0,0 +1,458 @@
+use crate::chip8::{HIRES_HEIGHT, HIRES_WIDTH};
+use crate::emulator::args::RunArgs;
+use crate::emulator::systems::update_display;
+use crate::emulator::util::{get_grid_origin, get_pixel_size};
+use crate::emulator::Emulator;
+use bevy::prelude::*;
+use bevy::render::extract_resource::{ExtractResource, ExtractResourcePlugin};
+use bevy::render::main_graph::node::CAMERA_DRIVER;
+use bevy::render::render_asset::RenderAssets;
+use bevy::render::render_graph::{self, RenderGraph};
+use bevy::render::render_resource::*;
+use bevy::render::renderer::{RenderContext, RenderDevice, RenderQueue};
+use bevy::render::{RenderApp, RenderStage};
+use bevy::window::WindowResized;
+use std::borrow::Cow;
+
+// path of the shader, within the assets folder
+const SHADER_PATH: &str = "shaders/phosphor.wgsl";
+
+// texels drawn for each hires pixel
+// lores pixels are twice this size, as the texture always covers the whole display
+const TEXELS_PER_PIXEL: u32 = 8;
+const TEXTURE_WIDTH: u32 = HIRES_WIDTH as u32 * TEXELS_PER_PIXEL;
+const TEXTURE_HEIGHT: u32 = HIRES_HEIGHT as u32 * TEXELS_PER_PIXEL;
+
+// the display is packed eight pixels to a word, with four bits for the color of each
+const PIXELS_PER_WORD: usize = 8;
+const VRAM_WORDS: usize = HIRES_WIDTH * HIRES_HEIGHT / PIXELS_PER_WORD;
+
+// threads along each side of a compute workgroup, which must match the shader
+const WORKGROUP_SIZE: u32 = 8;
+
+// brightness that a pixel has faded to once the persistence frames have passed
+const FADED_BRIGHTNESS: f32 = 1.0 / 255.0;
+
+// name of the compute node in the render graph
+const PHOSPHOR_NODE: &str = "phosphor";
+
+/// Draws the display with a compute shader, in place of a sprite for each pixel
+/// Pixels fade out over the persistence frames, bloom onto their neighbours, and are crossed by
+/// scanlines.
+pub struct PhosphorPlugin;
+
+impl Plugin for PhosphorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(ExtractResourcePlugin::<PhosphorImages>::default())
+            .add_plugin(ExtractResourcePlugin::<ExtractedPixelState>::default())
+            .add_startup_system(phosphor_setup)
+            .add_system(phosphor_update.after(update_display))
+            .add_system(phosphor_resize);
+
+        let render_app = app.sub_app_mut(RenderApp);
+        render_app
+            .init_resource::<PhosphorPipeline>()
+            .init_resource::<PhosphorBuffers>()
+            .add_system_to_stage(RenderStage::Prepare, prepare_phosphor_buffers)
+            .add_system_to_stage(RenderStage::Queue, queue_phosphor_bind_group);
+
+        // the display has to be drawn before the camera shows it
+        let mut render_graph = render_app.world.resource_mut::<RenderGraph>();
+        render_graph.add_node(PHOSPHOR_NODE, PhosphorNode::default());
+        render_graph
+            .add_node_edge(PHOSPHOR_NODE, CAMERA_DRIVER)
+            .expect("Unable to add the phosphor node to the render graph!");
+    }
+}
+
+/// Marker for the quad the phosphor display is shown on
+#[derive(Component)]
+pub struct PhosphorScreen;
+
+/// The textures the shader draws into
+#[derive(Resource, Clone, ExtractResource)]
+pub struct PhosphorImages {
+    /// How brightly each texel is glowing, faded from earlier frames
+    glow: Handle<Image>,
+
+    /// The finished display, with bloom and scanlines
+    output: Handle<Image>,
+}
+
+/// The settings for the shader
+#[derive(Clone, ShaderType)]
+pub struct PhosphorParams {
+    /// The color for each combination of display planes, in linear RGBA
+    palette: [Vec4; 16],
+
+    /// The size of the display, in CHIP-8 pixels
+    resolution: UVec2,
+
+    /// The size of the textures, in texels
+    texture_size: UVec2,
+
+    /// The brightness kept by a pixel each frame after it turns off
+    decay: f32,
+}
+
+/// The display, bit-packed for the shader
+#[derive(Clone, ShaderType)]
+pub struct PackedVram {
+    /// The color of each pixel, eight to a word, one row of the hires display after another
+    pixels: [u32; VRAM_WORDS],
+}
+
+/// The display and settings, copied to the render world each frame
+#[derive(Resource, Clone, ExtractResource)]
+pub struct ExtractedPixelState {
+    params: PhosphorParams,
+    vram: PackedVram,
+}
+
+/// Make the textures, and the quad to show them on
+fn phosphor_setup(
+    mut commands: Commands,
+    mut images: ResMut<Assets<Image>>,
+    windows: Res<Windows>,
+    args: Res<RunArgs>,
+    emu: Res<Emulator>,
+) {
+    let mut make_image = || {
+        let mut image = Image::new_fill(
+            Extent3d {
+                width: TEXTURE_WIDTH,
+                height: TEXTURE_HEIGHT,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &[0, 0, 0, 255],
+            TextureFormat::Rgba8Unorm,
+        );
+        image.texture_descriptor.usage = TextureUsages::COPY_DST
+            | TextureUsages::STORAGE_BINDING
+            | TextureUsages::TEXTURE_BINDING;
+        images.add(image)
+    };
+    let glow = make_image();
+    let output = make_image();
+
+    let window = windows
+        .get_primary()
+        .expect("Unable to get primary window!");
+    let (size, translation) = get_screen_placement(
+        (window.width(), window.height()),
+        emu.resolution,
+        args.maintain_aspect,
+        emu.layer,
+    );
+
+    commands.spawn((
+        PhosphorScreen,
+        SpriteBundle {
+            sprite: Sprite {
+                custom_size: Some(size),
+                ..default()
+            },
+            texture: output.clone(),
+            transform: Transform {
+                translation,
+                ..default()
+            },
+            ..default()
+        },
+    ));
+
+    commands.insert_resource(PhosphorImages { glow, output });
+    commands.insert_resource(ExtractedPixelState {
+        params: get_params(&emu, args.persistence),
+        vram: PackedVram {
+            pixels: [0; VRAM_WORDS],
+        },
+    });
+}
+
+/// Pack the display for the shader
+fn phosphor_update(
+    args: Res<RunArgs>,
+    emu: Res<Emulator>,
+    mut state: ResMut<ExtractedPixelState>,
+) {
+    state.params = get_params(&emu, args.persistence);
+
+    let (width, height) = emu.state.display_size();
+    state.vram.pixels = [0; VRAM_WORDS];
+    for y in 0..height {
+        for x in 0..width {
+            let index = y * HIRES_WIDTH + x;
+            let color = emu.state.get_pixel_color(x, y) as u32 & 0xF;
+            let shift = (index % PIXELS_PER_WORD) * 4;
+            state.vram.pixels[index / PIXELS_PER_WORD] |= color << shift;
+        }
+    }
+}
+
+/// Fit the quad to the window, the same way the pixel grid would be laid out
+fn phosphor_resize(
+    mut events: EventReader<WindowResized>,
+    args: Res<RunArgs>,
+    emu: Res<Emulator>,
+    mut screens: Query<(&mut Sprite, &mut Transform), With<PhosphorScreen>>,
+) {
+    for event in events.iter() {
+        let (size, translation) = get_screen_placement(
+            (event.width, event.height),
+            emu.resolution,
+            args.maintain_aspect,
+            emu.layer,
+        );
+
+        for (mut sprite, mut transform) in &mut screens {
+            sprite.custom_size = Some(size);
+            transform.translation = translation;
+        }
+    }
+}
+
+/// Get the size and translation of the quad, so that it covers the pixel grid exactly
+fn get_screen_placement(
+    window_size: (f32, f32),
+    display_size: (usize, usize),
+    maintain_aspect: bool,
+    layer: f32,
+) -> (Vec2, Vec3) {
+    let pixel_size = get_pixel_size(window_size, display_size, maintain_aspect);
+    let origin = get_grid_origin(window_size, pixel_size, display_size);
+
+    // the camera is centered on the middle of the grid's first pixel, not its corner
+    let (width, height) = (display_size.0 as f32, display_size.1 as f32);
+    let size = Vec2::new(width * pixel_size.0, height * pixel_size.1);
+    let translation = Vec3::new(
+        origin.x + (width - 1.0) / 2.0 * pixel_size.0,
+        origin.y + (height + 1.0) / 2.0 * pixel_size.1,
+        layer,
+    );
+
+    (size, translation)
+}
+
+/// Get the settings for the shader from the emulator
+fn get_params(emu: &Emulator, persistence: u8) -> PhosphorParams {
+    // fade to almost nothing over the persistence frames, or straight away without persistence
+    let decay = if persistence == 0 {
+        0.0
+    } else {
+        FADED_BRIGHTNESS.powf(1.0 / persistence as f32)
+    };
+
+    let (width, height) = emu.state.display_size();
+    PhosphorParams {
+        palette: emu.palette.map(|color| Vec4::from(color.as_linear_rgba_f32())),
+        resolution: UVec2::new(width as u32, height as u32),
+        texture_size: UVec2::new(TEXTURE_WIDTH, TEXTURE_HEIGHT),
+        decay,
+    }
+}
+
+/// The layout of the shader's inputs, and the compute pipelines for each pass
+#[derive(Resource)]
+pub struct PhosphorPipeline {
+    layout: BindGroupLayout,
+    decay_pipeline: CachedComputePipelineId,
+    bloom_pipeline: CachedComputePipelineId,
+}
+
+impl FromWorld for PhosphorPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let buffer_entry = |binding: u32, ty: BufferBindingType| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::Buffer {
+                ty,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        };
+        let texture_entry = |binding: u32, access: StorageTextureAccess| BindGroupLayoutEntry {
+            binding,
+            visibility: ShaderStages::COMPUTE,
+            ty: BindingType::StorageTexture {
+                access,
+                format: TextureFormat::Rgba8Unorm,
+                view_dimension: TextureViewDimension::D2,
+            },
+            count: None,
+        };
+
+        let layout = world
+            .resource::<RenderDevice>()
+            .create_bind_group_layout(&BindGroupLayoutDescriptor {
+                label: Some("phosphor_bind_group_layout"),
+                entries: &[
+                    buffer_entry(0, BufferBindingType::Uniform),
+                    buffer_entry(1, BufferBindingType::Storage { read_only: true }),
+                    texture_entry(2, StorageTextureAccess::ReadWrite),
+                    texture_entry(3, StorageTextureAccess::WriteOnly),
+                ],
+            });
+
+        let shader = world.resource::<AssetServer>().load(SHADER_PATH);
+        let mut pipeline_cache = world.resource_mut::<PipelineCache>();
+        let mut queue_pass = |entry_point: &'static str| {
+            pipeline_cache.queue_compute_pipeline(ComputePipelineDescriptor {
+                label: Some(Cow::from(entry_point)),
+                layout: Some(vec![layout.clone()]),
+                shader: shader.clone(),
+                shader_defs: vec![],
+                entry_point: Cow::from(entry_point),
+            })
+        };
+        let decay_pipeline = queue_pass("decay");
+        let bloom_pipeline = queue_pass("bloom");
+
+        PhosphorPipeline {
+            layout,
+            decay_pipeline,
+            bloom_pipeline,
+        }
+    }
+}
+
+/// The buffers holding the shader's settings and the display
+#[derive(Resource)]
+pub struct PhosphorBuffers {
+    params: UniformBuffer<PhosphorParams>,
+    vram: StorageBuffer<PackedVram>,
+}
+
+impl Default for PhosphorBuffers {
+    fn default() -> Self {
+        Self {
+            params: UniformBuffer::from(PhosphorParams {
+                palette: [Vec4::ZERO; 16],
+                resolution: UVec2::ZERO,
+                texture_size: UVec2::ZERO,
+                decay: 0.0,
+            }),
+            vram: StorageBuffer::from(PackedVram {
+                pixels: [0; VRAM_WORDS],
+            }),
+        }
+    }
+}
+
+/// The shader's inputs, ready to be bound
+#[derive(Resource)]
+struct PhosphorBindGroup(BindGroup);
+
+/// Copy the extracted display and settings to the GPU
+fn prepare_phosphor_buffers(
+    state: Res<ExtractedPixelState>,
+    mut buffers: ResMut<PhosphorBuffers>,
+    device: Res<RenderDevice>,
+    queue: Res<RenderQueue>,
+) {
+    buffers.params.set(state.params.clone());
+    buffers.params.write_buffer(&device, &queue);
+    buffers.vram.set(state.vram.clone());
+    buffers.vram.write_buffer(&device, &queue);
+}
+
+/// Bind the buffers and textures for the shader
+fn queue_phosphor_bind_group(
+    mut commands: Commands,
+    pipeline: Res<PhosphorPipeline>,
+    buffers: Res<PhosphorBuffers>,
+    images: Res<PhosphorImages>,
+    gpu_images: Res<RenderAssets<Image>>,
+    device: Res<RenderDevice>,
+) {
+    // the textures aren't on the GPU until the frame after they are made
+    let glow = gpu_images.get(&images.glow);
+    let output = gpu_images.get(&images.output);
+    let (Some(glow), Some(output)) = (glow, output) else {
+        return;
+    };
+    let (Some(params), Some(vram)) = (buffers.params.binding(), buffers.vram.binding()) else {
+        return;
+    };
+
+    let bind_group = device.create_bind_group(&BindGroupDescriptor {
+        label: Some("phosphor_bind_group"),
+        layout: &pipeline.layout,
+        entries: &[
+            BindGroupEntry {
+                binding: 0,
+                resource: params,
+            },
+            BindGroupEntry {
+                binding: 1,
+                resource: vram,
+            },
+            BindGroupEntry {
+                binding: 2,
+                resource: BindingResource::TextureView(&glow.texture_view),
+            },
+            BindGroupEntry {
+                binding: 3,
+                resource: BindingResource::TextureView(&output.texture_view),
+            },
+        ],
+    });
+    commands.insert_resource(PhosphorBindGroup(bind_group));
+}
+
+/// Runs the decay and bloom passes each frame, once the shader has compiled
+#[derive(Default)]
+struct PhosphorNode {
+    ready: bool,
+}
+
+impl render_graph::Node for PhosphorNode {
+    fn update(&mut self, world: &mut World) {
+        let pipeline = world.resource::<PhosphorPipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+
+        self.ready = [pipeline.decay_pipeline, pipeline.bloom_pipeline]
+            .into_iter()
+            .all(|id| pipeline_cache.get_compute_pipeline(id).is_some());
+    }
+
+    fn run(
+        &self,
+        _graph: &mut render_graph::RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), render_graph::NodeRunError> {
+        let Some(bind_group) = world.get_resource::<PhosphorBindGroup>() else {
+            return Ok(());
+        };
+        if !self.ready {
+            return Ok(());
+        }
+
+        let pipeline = world.resource::<PhosphorPipeline>();
+        let pipeline_cache = world.resource::<PipelineCache>();
+        let mut pass = render_context
+            .command_encoder
+            .begin_compute_pass(&ComputePassDescriptor {
+                label: Some("phosphor_pass"),
+            });
+        pass.set_bind_group(0, &bind_group.0, &[]);
+
+        // the bloom pass reads the glow the decay pass has just written
+        for id in [pipeline.decay_pipeline, pipeline.bloom_pipeline] {
+            let compute_pipeline = pipeline_cache
+                .get_compute_pipeline(id)
+                .expect("Phosphor pipelines should be ready");
+            pass.set_pipeline(compute_pipeline);
+            pass.dispatch_workgroups(
+                TEXTURE_WIDTH / WORKGROUP_SIZE,
+                TEXTURE_HEIGHT / WORKGROUP_SIZE,
+                1,
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,219 @@
+use crate::chip8::{WrapMode, DEFAULT_CYCLES_PER_FRAME};
+use crate::emulator::args::RunArgs;
+use crate::emulator::audio::*;
+use crate::emulator::auto_speed::{auto_speed_update, SpeedController};
+use crate::emulator::colors::{get_palette, Chip8Color};
+use crate::emulator::compare::*;
+use crate::emulator::debug_server::*;
+use crate::emulator::debugger::*;
+use crate::emulator::error_overlay::*;
+use crate::emulator::hud::*;
+use crate::emulator::keymap::Keymap;
+use crate::emulator::phosphor::PhosphorPlugin;
+use crate::emulator::profiler::{opcode_report_on_exit, Chip8DiagnosticPlugin};
+use crate::emulator::recording::*;
+use crate::emulator::rewind::*;
+use crate::emulator::rom_dump::rom_dump_on_exit;
+use crate::emulator::save_states::*;
+use crate::emulator::screenshot::*;
+use crate::emulator::startup_systems::*;
+use crate::emulator::systems::*;
+use crate::emulator::{EmuEvent, TurboActive, OFF_COLOR, ON_COLOR};
+use crate::error::Chip8Error;
+use bevy::diagnostic::FrameTimeDiagnosticsPlugin;
+use bevy::prelude::*;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+/// The options most often changed when embedding the emulator in another app
+#[derive(Resource, Debug, Clone)]
+pub struct Chip8Config {
+    /// The number of instructions to run each frame
+    pub cycles_per_frame: u32,
+
+    /// The color of pixels that are on
+    pub on_color: Color,
+
+    /// The color of pixels that are off
+    pub off_color: Color,
+
+    /// What happens to sprites drawn past the edges of the display
+    pub wrap_mode: WrapMode,
+
+    /// Whether 8XY6 and 8XYE shift VX in place, ignoring VY
+    pub shift_quirk: bool,
+
+    /// The z coordinate the display is drawn at, to put it in front of or behind other sprites
+    pub layer: f32,
+}
+
+impl Default for Chip8Config {
+    fn default() -> Self {
+        Self {
+            cycles_per_frame: DEFAULT_CYCLES_PER_FRAME,
+            on_color: ON_COLOR,
+            off_color: OFF_COLOR,
+            wrap_mode: WrapMode::default(),
+            shift_quirk: false,
+            layer: 0.0,
+        }
+    }
+}
+
+impl Chip8Config {
+    /// Get the config from the command line arguments
+    pub fn from_args(args: &RunArgs) -> Self {
+        let palette = get_palette(args);
+        Self {
+            cycles_per_frame: args.cycles_per_frame,
+            on_color: palette[1],
+            off_color: palette[0],
+            wrap_mode: args.wrap_mode,
+            shift_quirk: args.shift_quirk,
+            layer: 0.0,
+        }
+    }
+}
+
+/// The ROM loaded by `emu_setup`
+#[derive(Resource, Debug, Clone)]
+pub struct Chip8Rom(pub Vec<u8>);
+
+/// Runs a ROM inside a bevy app, adding the emulator along with all of its systems
+/// The app needs a primary window, as the display is laid out to fill it.
+pub struct Chip8Plugin {
+    rom: Vec<u8>,
+    config: Chip8Config,
+
+    /// Everything else, which is left at the defaults unless made from the arguments
+    args: RunArgs,
+}
+
+impl Chip8Plugin {
+    /// Make the plugin for a ROM, with the default options for anything not in the config
+    pub fn new(rom: Vec<u8>, config: Chip8Config) -> Self {
+        Self {
+            rom,
+            config,
+            args: RunArgs::embedded(),
+        }
+    }
+
+    /// Make the plugin from the command line arguments, reading the ROM they give
+    pub fn from_args(args: RunArgs) -> Result<Self, Chip8Error> {
+        let rom = std::fs::read(args.rom()).map_err(|e| match e.kind() {
+            ErrorKind::NotFound => Chip8Error::RomNotFound(PathBuf::from(args.rom())),
+            _ => Chip8Error::RomReadError(e),
+        })?;
+
+        let config = Chip8Config::from_args(&args);
+        Ok(Self::new(rom, config).with_args(args))
+    }
+
+    /// Use the command line arguments for the options that aren't in the config
+    pub fn with_args(mut self, args: RunArgs) -> Self {
+        self.args = args;
+        self
+    }
+}
+
+impl Plugin for Chip8Plugin {
+    fn build(&self, app: &mut App) {
+        // the config takes priority over the arguments it overlaps with
+        let mut args = self.args.clone();
+        args.cycles_per_frame = self.config.cycles_per_frame;
+        args.on_color = Some(Chip8Color(self.config.on_color));
+        args.off_color = Some(Chip8Color(self.config.off_color));
+        args.invert = false;
+        args.wrap_mode = self.config.wrap_mode;
+        args.shift_quirk = self.config.shift_quirk;
+
+        // starting paused leaves the first instruction to be stepped through in the debugger
+        let initial_state = if args.pause_on_start {
+            AppState::Debugging
+        } else {
+            AppState::Running
+        };
+        let profile = args.profile;
+        let phosphor = args.phosphor;
+
+        // the HUD shows the frame rate
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugin(FrameTimeDiagnosticsPlugin);
+        }
+
+        app.add_plugin(Chip8DiagnosticPlugin { profile })
+            .insert_resource(args)
+            .insert_resource(self.config.clone())
+            .insert_resource(Chip8Rom(self.rom.clone()))
+            .init_resource::<Keymap>()
+            .add_state(initial_state)
+            .init_resource::<RewindBuffer>()
+            .init_resource::<RewindState>()
+            .init_resource::<HudVisible>()
+            .init_resource::<TurboActive>()
+            .init_resource::<CycleCounter>()
+            .init_resource::<GifRecording>()
+            .init_resource::<SpeedController>()
+            .add_event::<BreakpointHit>()
+            .add_event::<EmuEvent>()
+            .add_startup_system_to_stage(
+                StartupStage::PreStartup,
+                emu_setup.pipe(report_setup_error),
+            )
+            .add_startup_system_to_stage(
+                StartupStage::PreStartup,
+                compare_setup.pipe(report_setup_error),
+            )
+            .add_startup_system(camera_setup)
+            .add_startup_system(pixels_setup)
+            .add_startup_system(debugger_setup)
+            .add_startup_system(debug_server_setup)
+            .add_startup_system(save_state_setup)
+            .add_startup_system(audio_setup)
+            .add_startup_system(error_overlay_setup)
+            .add_startup_system(hud_setup)
+            .add_startup_system(recording_setup)
+            .add_system(get_input)
+            .add_system(get_gamepad_input)
+            .add_system(reset_input)
+            .add_system(compare_input.after(get_input).after(get_gamepad_input))
+            .add_system(rewind_update.before(do_next_instruction))
+            .add_system_set(
+                SystemSet::on_update(AppState::Running)
+                    .with_system(do_next_instruction)
+                    .with_system(save_state_input)
+                    .with_system(change_speed)
+                    .with_system(auto_speed_update.after(update_display))
+                    .with_system(compare_update.after(compare_input)),
+            )
+            .add_system_set(SystemSet::on_update(AppState::Debugging).with_system(debugger_step))
+            .add_system_set(SystemSet::on_enter(AppState::Debugging).with_system(debugger_enter))
+            .add_system_set(SystemSet::on_exit(AppState::Debugging).with_system(debugger_exit))
+            .add_system(breakpoint_hit.after(do_next_instruction))
+            .add_system(debugger_overlay)
+            .add_system(debug_server_sync.before(do_next_instruction))
+            .add_system(hud_toggle)
+            .add_system(cycle_counter_update)
+            .add_system(hud_update)
+            .add_system(error_overlay)
+            .add_system(state_message_timeout)
+            .add_system(update_tone)
+            .add_system(update_display)
+            .add_system(compare_display.after(update_display).after(compare_update))
+            .add_system(resolution_change)
+            .add_system(exit_on_request)
+            .add_system(screenshot_input)
+            .add_system_to_stage(CoreStage::Last, screenshot_on_exit)
+            .add_system_to_stage(CoreStage::Last, opcode_report_on_exit)
+            .add_system_to_stage(CoreStage::Last, recording_on_exit)
+            .add_system_to_stage(CoreStage::Last, rom_dump_on_exit)
+            .add_system(window_resize_pixel)
+            .add_system(window_resize_camera);
+
+        // the shader replaces the sprite for each pixel
+        if phosphor {
+            app.add_plugin(PhosphorPlugin);
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use crate::emulator::Emulator;
+use bevy::app::AppExit;
+use bevy::diagnostic::{Diagnostic, DiagnosticId, Diagnostics, LogDiagnosticsPlugin};
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use std::marker::PhantomData;
+use std::time::{Duration, Instant};
+
+/// Time spent running instructions each frame, in microseconds
+pub const INSTRUCTION_TIME: DiagnosticId =
+    DiagnosticId::from_u128(0x8f0b_5b52_4c1e_4d0a_9a7e_3c61_2f4b_0001);
+
+/// Time spent updating the display each frame, in microseconds
+pub const DISPLAY_UPDATE_TIME: DiagnosticId =
+    DiagnosticId::from_u128(0x8f0b_5b52_4c1e_4d0a_9a7e_3c61_2f4b_0002);
+
+/// Time spent reading the keyboard each frame, in microseconds
+pub const INPUT_TIME: DiagnosticId =
+    DiagnosticId::from_u128(0x8f0b_5b52_4c1e_4d0a_9a7e_3c61_2f4b_0003);
+
+// the name of each diagnostic
+const DIAGNOSTICS: [(DiagnosticId, &str); 3] = [
+    (INSTRUCTION_TIME, "instruction_time_us"),
+    (DISPLAY_UPDATE_TIME, "display_update_time_us"),
+    (INPUT_TIME, "input_time_us"),
+];
+
+// the number of measurements averaged for each diagnostic
+const MAX_HISTORY: usize = 60;
+
+// time between printing the diagnostics while profiling
+const LOG_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Registers the emulator's timing diagnostics
+/// With `profile`, the diagnostics are also logged while running, and summarized on exit.
+pub struct Chip8DiagnosticPlugin {
+    pub profile: bool,
+}
+
+impl Plugin for Chip8DiagnosticPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system(diagnostics_setup);
+
+        if self.profile {
+            app.add_plugin(LogDiagnosticsPlugin {
+                wait_duration: LOG_INTERVAL,
+                ..default()
+            })
+            .init_resource::<PerformanceSummary>()
+            .add_system_to_stage(CoreStage::Last, summary_on_exit);
+        }
+    }
+}
+
+/// Every measurement of each diagnostic, for summarizing on exit
+#[derive(Resource, Default)]
+pub struct PerformanceSummary {
+    /// The measurements for each diagnostic, in the same order as the diagnostics
+    samples: [Vec<f64>; DIAGNOSTICS.len()],
+}
+
+impl PerformanceSummary {
+    /// Add a measurement of a diagnostic
+    fn add(&mut self, id: DiagnosticId, value: f64) {
+        if let Some(index) = DIAGNOSTICS.iter().position(|(d, _)| *d == id) {
+            self.samples[index].push(value);
+        }
+    }
+}
+
+impl std::fmt::Display for PerformanceSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{:<24}{:>10}{:>10}{:>10}",
+            "diagnostic", "min", "max", "p99"
+        )?;
+        for ((_, name), samples) in DIAGNOSTICS.iter().zip(&self.samples) {
+            if samples.is_empty() {
+                writeln!(f, "{:<24}{:>10}", name, "no data")?;
+                continue;
+            }
+
+            let mut sorted = samples.clone();
+            sorted.sort_by(f64::total_cmp);
+            let p99 = sorted[(sorted.len() * 99).div_ceil(100) - 1];
+            writeln!(
+                f,
+                "{:<24}{:>10.1}{:>10.1}{:>10.1}",
+                name,
+                sorted[0],
+                sorted[sorted.len() - 1],
+                p99
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Records how long systems take
+#[derive(SystemParam)]
+pub struct Profiler<'w, 's> {
+    diagnostics: ResMut<'w, Diagnostics>,
+    summary: Option<ResMut<'w, PerformanceSummary>>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+impl Profiler<'_, '_> {
+    /// Record the time since `start` for a diagnostic
+    pub fn record(&mut self, id: DiagnosticId, start: Instant) {
+        let micros = start.elapsed().as_secs_f64() * 1_000_000.0;
+        self.diagnostics.add_measurement(id, || micros);
+        if let Some(summary) = &mut self.summary {
+            summary.add(id, micros);
+        }
+    }
+}
+
+/// Register the diagnostics
+fn diagnostics_setup(mut diagnostics: ResMut<Diagnostics>) {
+    for (id, name) in DIAGNOSTICS {
+        diagnostics.add(Diagnostic::new(id, name, MAX_HISTORY).with_suffix("us"));
+    }
+}
+
+/// Print the summary of the diagnostics when the app exits
+fn summary_on_exit(exits: EventReader<AppExit>, summary: Res<PerformanceSummary>) {
+    if exits.is_empty() {
+        return;
+    }
+    exits.clear();
+
+    println!("{}", *summary);
+}
+
+/// Print the most executed opcodes when the app exits, if they were counted
+pub fn opcode_report_on_exit(exits: EventReader<AppExit>, emu: Res<Emulator>) {
+    if exits.is_empty() {
+        return;
+    }
+    exits.clear();
+
+    if let Some(profiler) = emu.state.opcode_profiler() {
+        if let Err(e) = profiler.report_to_writer(&mut std::io::stdout()) {
+            eprintln!("Unable to print opcode report: {}", e);
+        }
+    }
+}
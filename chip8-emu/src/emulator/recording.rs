@@ -0,0 +1,202 @@
+use crate::chip8::{Chip8, HIRES_HEIGHT, HIRES_WIDTH};
+use crate::emulator::args::RunArgs;
+use crate::emulator::Emulator;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use gif::{Encoder, Frame, Repeat};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+
+// the number of frames the emulator runs each second
+const EMULATOR_FPS: u64 = 60;
+
+// the size of each high resolution pixel in the recording, in image pixels
+const RECORD_PIXEL_SIZE: usize = 4;
+
+// the size of the recording, which fits the display in either resolution
+const RECORD_WIDTH: usize = HIRES_WIDTH * RECORD_PIXEL_SIZE;
+const RECORD_HEIGHT: usize = HIRES_HEIGHT * RECORD_PIXEL_SIZE;
+
+/// A display captured on a frame, with the index of each image pixel in the palette
+struct CapturedFrame {
+    frame: u64,
+    pixels: Vec<u8>,
+}
+
+/// Records the display to an animated GIF, frame by frame
+/// Frames are encoded and written on another thread, so recording doesn't slow the emulator.
+pub struct GifRecorder {
+    sender: Sender<CapturedFrame>,
+    writer: JoinHandle<Result<(), String>>,
+
+    /// The number of frames recorded each second, out of the 60 the emulator runs
+    fps: u32,
+
+    /// The number of frames the emulator has run since recording started
+    frames: u64,
+}
+
+impl GifRecorder {
+    /// Start recording to a file, with a two color palette of the off and on colors
+    pub fn create(
+        path: &Path,
+        off_color: Color,
+        on_color: Color,
+        fps: u32,
+    ) -> Result<Self, String> {
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        let palette: Vec<u8> = [off_color, on_color]
+            .iter()
+            .flat_map(|color| color.as_rgba_f32()[..3].to_vec())
+            .map(|channel| (channel.clamp(0.0, 1.0) * 255.0).round() as u8)
+            .collect();
+        let mut encoder = Encoder::new(
+            BufWriter::new(file),
+            RECORD_WIDTH as u16,
+            RECORD_HEIGHT as u16,
+            &palette,
+        )
+        .map_err(|e| e.to_string())?;
+        encoder.set_repeat(Repeat::Infinite).map_err(|e| e.to_string())?;
+
+        let fps = fps.clamp(1, EMULATOR_FPS as u32);
+        let (sender, receiver) = mpsc::channel::<CapturedFrame>();
+        let writer = thread::spawn(move || {
+            // each frame is held until the next one arrives, as that's when its delay is known
+            let mut pending: Option<CapturedFrame> = None;
+            let mut last_delay = get_delay(0, EMULATOR_FPS / fps as u64);
+            for captured in receiver {
+                if let Some(previous) = pending.take() {
+                    last_delay = get_delay(previous.frame, captured.frame);
+                    write_frame(&mut encoder, &previous.pixels, last_delay)?;
+                }
+                pending = Some(captured);
+            }
+
+            // the last frame is shown for as long as the one before it
+            if let Some(last) = pending {
+                write_frame(&mut encoder, &last.pixels, last_delay)?;
+            }
+
+            // the encoder finishes the file when it is dropped
+            Ok(())
+        });
+
+        Ok(Self {
+            sender,
+            writer,
+            fps,
+            frames: 0,
+        })
+    }
+
+    /// Capture the display, if this frame is one of those being recorded
+    pub fn record_frame(
+        &mut self,
+        display_size: (usize, usize),
+        is_on: impl Fn(usize, usize) -> bool,
+    ) {
+        let frame = self.frames;
+        self.frames += 1;
+
+        // spread the recorded frames evenly over each second
+        if (frame * self.fps as u64) % EMULATOR_FPS >= self.fps as u64 {
+            return;
+        }
+
+        let (scale_x, scale_y) = (RECORD_WIDTH / display_size.0, RECORD_HEIGHT / display_size.1);
+        let mut pixels = Vec::with_capacity(RECORD_WIDTH * RECORD_HEIGHT);
+        for y in 0..RECORD_HEIGHT {
+            for x in 0..RECORD_WIDTH {
+                pixels.push(is_on(x / scale_x, y / scale_y) as u8);
+            }
+        }
+
+        // the writer only stops early if writing failed, which is reported when finishing
+        let _ = self.sender.send(CapturedFrame { frame, pixels });
+    }
+
+    /// Capture the display of the emulator, as shown
+    pub fn record_chip8(&mut self, state: &Chip8) {
+        self.record_frame(state.display_size(), |x, y| state.get_pixel_color(x, y) > 0);
+    }
+
+    /// Write out the frames still waiting, and finish the file
+    pub fn finish(self) -> Result<(), String> {
+        drop(self.sender);
+        self.writer
+            .join()
+            .unwrap_or_else(|_| Err("the recording thread panicked".to_string()))
+    }
+}
+
+/// Get the delay between two frames, in the hundredths of a second that GIFs count in
+/// A frame at 60 fps is 1.67 hundredths, so the delays are rounded in a way that adds up to
+/// the right time overall.
+fn get_delay(from_frame: u64, to_frame: u64) -> u16 {
+    let centiseconds = |frame: u64| frame * 100 / EMULATOR_FPS;
+    (centiseconds(to_frame) - centiseconds(from_frame)).clamp(1, u16::MAX as u64) as u16
+}
+
+/// Encode a frame of the recording
+fn write_frame(
+    encoder: &mut Encoder<BufWriter<File>>,
+    pixels: &[u8],
+    delay: u16,
+) -> Result<(), String> {
+    let mut frame = Frame::from_indexed_pixels(
+        RECORD_WIDTH as u16,
+        RECORD_HEIGHT as u16,
+        pixels,
+        None,
+    );
+    frame.delay = delay;
+    encoder.write_frame(&frame).map_err(|e| e.to_string())
+}
+
+/// The recording of the session, if one was asked for
+#[derive(Resource, Default)]
+pub struct GifRecording(pub Option<GifRecorder>);
+
+/// Start recording, if asked to
+pub fn recording_setup(
+    args: Res<RunArgs>,
+    emu: Res<Emulator>,
+    mut recording: ResMut<GifRecording>,
+) {
+    let Some(path) = &args.record else {
+        return;
+    };
+
+    let recorder =
+        GifRecorder::create(Path::new(path), emu.palette[0], emu.palette[1], args.record_fps);
+    match recorder {
+        Ok(recorder) => {
+            info!("Recording to {}", path);
+            recording.0 = Some(recorder);
+        }
+        Err(e) => error!("Unable to record to {}: {}", path, e),
+    }
+}
+
+/// Finish the recording when the app exits
+pub fn recording_on_exit(
+    exits: EventReader<AppExit>,
+    args: Res<RunArgs>,
+    mut recording: ResMut<GifRecording>,
+) {
+    if exits.is_empty() {
+        return;
+    }
+    exits.clear();
+
+    if let (Some(recorder), Some(path)) = (recording.0.take(), &args.record) {
+        match recorder.finish() {
+            Ok(_) => info!("Saved recording to {}", path),
+            Err(e) => error!("Unable to save recording to {}: {}", path, e),
+        }
+    }
+}
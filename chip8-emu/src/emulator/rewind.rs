@@ -0,0 +1,143 @@
+use crate::chip8::Chip8;
+use crate::emulator::args::RunArgs;
+use crate::emulator::debugger::AppState;
+use crate::emulator::startup_systems::configure_chip8;
+use crate::emulator::Emulator;
+use bevy::prelude::*;
+use std::collections::VecDeque;
+
+// the key held to rewind
+// R is already the CHIP-8 D key, so the rewind key is kept off the keypad
+const REWIND_KEY: KeyCode = KeyCode::Back;
+
+// the most frames that can be rewound
+const MAX_REWIND_FRAMES: usize = 300;
+
+// the most memory the rewind buffer can use, in bytes
+const MAX_REWIND_MEMORY: usize = 64 * 1024 * 1024;
+
+// the number of display frames between each rewound frame, for rewinding at 30 fps
+const FRAMES_PER_REWIND_STEP: u32 = 2;
+
+/// The changes needed to step back from one frame to the frame before it
+/// Each change is a two byte big-endian offset into the save state, followed by the old byte.
+struct RewindFrame {
+    changes: Box<[u8]>,
+}
+
+/// The last few frames of emulator state
+/// Only the latest state is kept whole. Every other frame is stored as the bytes that changed
+/// since it, so stepping back means undoing the changes one frame at a time.
+#[derive(Resource, Default)]
+pub struct RewindBuffer {
+    /// The save state of the latest frame
+    latest: Vec<u8>,
+
+    /// The changes for each frame before the latest, oldest first
+    frames: VecDeque<RewindFrame>,
+
+    /// The memory used by the saved states, in bytes
+    size: usize,
+}
+
+impl RewindBuffer {
+    /// Add the state for a new frame
+    fn push(&mut self, state: Vec<u8>) {
+        if self.latest.len() != state.len() || state.len() > u16::MAX as usize {
+            // the state can't be diffed against the last one, so start over from it
+            self.frames.clear();
+            self.size = state.len();
+            self.latest = state;
+            return;
+        }
+
+        // record the old value of every byte that changed
+        let mut changes: Vec<u8> = Vec::new();
+        for (offset, (old, new)) in self.latest.iter().zip(&state).enumerate() {
+            if old != new {
+                changes.extend_from_slice(&(offset as u16).to_be_bytes());
+                changes.push(*old);
+            }
+        }
+
+        self.size += changes.len();
+        self.frames.push_back(RewindFrame {
+            changes: changes.into_boxed_slice(),
+        });
+        self.latest = state;
+
+        // drop the oldest frames to stay within the limits
+        while self.frames.len() > MAX_REWIND_FRAMES || self.size > MAX_REWIND_MEMORY {
+            match self.frames.pop_front() {
+                Some(frame) => self.size -= frame.changes.len(),
+                None => break,
+            }
+        }
+    }
+
+    /// Step back to the frame before the latest one, returning its state
+    /// Returns None if there are no earlier frames left.
+    fn step_back(&mut self) -> Option<&[u8]> {
+        let frame = self.frames.pop_back()?;
+        self.size -= frame.changes.len();
+
+        for change in frame.changes.chunks(3) {
+            let offset = u16::from_be_bytes([change[0], change[1]]) as usize;
+            self.latest[offset] = change[2];
+        }
+
+        Some(&self.latest)
+    }
+}
+
+/// Whether the emulator is running forward or being rewound
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RewindState {
+    /// The emulator is running normally, and each frame is saved
+    #[default]
+    Forward,
+
+    /// The emulator is paused and stepping back through the saved frames
+    Rewinding {
+        /// The number of display frames until the next step back
+        frames_until_step: u32,
+    },
+}
+
+/// Save each frame, or step back through the saved frames while the rewind key is held
+/// Releasing the rewind key carries on running from the rewound state.
+pub fn rewind_update(
+    keys: Res<Input<KeyCode>>,
+    args: Res<RunArgs>,
+    app_state: Res<State<AppState>>,
+    mut emu: ResMut<Emulator>,
+    mut buffer: ResMut<RewindBuffer>,
+    mut rewind_state: ResMut<RewindState>,
+) {
+    if !keys.pressed(REWIND_KEY) {
+        *rewind_state = RewindState::Forward;
+
+        // only save frames where time has passed in the emulator
+        if *app_state.current() == AppState::Running {
+            buffer.push(emu.state.save_state());
+        }
+        return;
+    }
+
+    let frames_until_step = match *rewind_state {
+        RewindState::Forward => 0,
+        RewindState::Rewinding { frames_until_step } => frames_until_step,
+    };
+
+    if frames_until_step == 0 {
+        if let Some(Ok(state)) = buffer.step_back().map(Chip8::load_state) {
+            emu.replace_state(configure_chip8(state, &args));
+        }
+    }
+
+    *rewind_state = RewindState::Rewinding {
+        frames_until_step: frames_until_step
+            .checked_sub(1)
+            .unwrap_or(FRAMES_PER_REWIND_STEP - 1),
+    };
+}
@@ -0,0 +1,26 @@
+use crate::chip8::Chip8;
+use crate::emulator::args::RunArgs;
+use crate::emulator::Emulator;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+
+/// Write the program as it is in memory to a file
+/// Where the program was written, or why it couldn't be, is printed to stderr.
+pub fn write_rom_dump(state: &Chip8, path: &str) {
+    match std::fs::write(path, state.export_rom()) {
+        Ok(()) => eprintln!("Wrote the program to {}", path),
+        Err(e) => eprintln!("Unable to write the program to {}: {}", path, e),
+    }
+}
+
+/// Write the program out when the app exits, if asked to
+pub fn rom_dump_on_exit(exits: EventReader<AppExit>, args: Res<RunArgs>, emu: Res<Emulator>) {
+    if exits.is_empty() {
+        return;
+    }
+    exits.clear();
+
+    if let Some(path) = &args.dump_rom {
+        write_rom_dump(&emu.state, path);
+    }
+}
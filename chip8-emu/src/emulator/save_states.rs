@@ -0,0 +1,126 @@
+use crate::chip8::Chip8;
+use crate::emulator::args::RunArgs;
+use crate::emulator::startup_systems::configure_chip8;
+use crate::emulator::{Emulator, UI_FONT};
+use bevy::prelude::KeyCode::*;
+use bevy::prelude::*;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// the keys for each save slot
+const SLOT_KEYS: [KeyCode; 4] = [F5, F6, F7, F8];
+
+// how long save state messages are shown for
+const MESSAGE_FRAMES: u32 = 60;
+
+// text style of save state messages
+const MESSAGE_FONT_SIZE: f32 = 20.0;
+const MESSAGE_COLOR: Color = Color::WHITE;
+
+/// A message about the last save state operation, shown for a limited number of frames
+#[derive(Component)]
+pub struct StateMessage {
+    frames_left: u32,
+}
+
+/// Get the path to the save state for a ROM in a given slot
+/// Save states are kept in ~/.chip8emu/saves/, named after the ROM file.
+fn get_save_path(rom: &str, slot: usize) -> PathBuf {
+    let home = std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .unwrap_or_default();
+    let rom_name = Path::new(rom)
+        .file_stem()
+        .unwrap_or_default()
+        .to_string_lossy();
+
+    Path::new(&home)
+        .join(".chip8emu")
+        .join("saves")
+        .join(format!("{}.{}.sav", rom_name, slot))
+}
+
+/// Save the emulator state to a file
+fn save_to_file(state: &Chip8, path: &Path) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    fs::write(path, state.save_state()).map_err(|e| e.to_string())
+}
+
+/// Load the emulator state from a file
+fn load_from_file(path: &Path) -> Result<Chip8, String> {
+    let bytes = fs::read(path).map_err(|e| e.to_string())?;
+    Chip8::load_state(&bytes).map_err(|e| e.to_string())
+}
+
+/// Make the save state message text
+pub fn save_state_setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        StateMessage { frames_left: 0 },
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load(UI_FONT),
+                font_size: MESSAGE_FONT_SIZE,
+                color: MESSAGE_COLOR,
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            position: UiRect {
+                bottom: Val::Px(5.0),
+                left: Val::Px(5.0),
+                ..default()
+            },
+            ..default()
+        }),
+    ));
+}
+
+/// Save and load states
+/// F5-F8 save to slots 0-3, and holding shift loads from the slot instead.
+pub fn save_state_input(
+    keys: Res<Input<KeyCode>>,
+    args: Res<RunArgs>,
+    mut emu: ResMut<Emulator>,
+    mut messages: Query<(&mut Text, &mut StateMessage)>,
+) {
+    for (slot, kc) in SLOT_KEYS.into_iter().enumerate() {
+        if !keys.just_pressed(kc) {
+            continue;
+        }
+
+        emu.slot = slot;
+        let path = get_save_path(args.rom(), slot);
+
+        let message = if keys.any_pressed([LShift, RShift]) {
+            match load_from_file(&path) {
+                Ok(state) => {
+                    emu.replace_state(configure_chip8(state, &args));
+                    format!("State loaded (slot {})", slot)
+                }
+                Err(e) => format!("Unable to load state: {}", e),
+            }
+        } else {
+            match save_to_file(&emu.state, &path) {
+                Ok(_) => format!("State saved (slot {})", slot),
+                Err(e) => format!("Unable to save state: {}", e),
+            }
+        };
+
+        // show the message
+        for (mut text, mut state_message) in &mut messages {
+            text.sections[0].value = message.clone();
+            state_message.frames_left = MESSAGE_FRAMES;
+        }
+    }
+}
+
+/// Hide the save state message once it has been shown long enough
+pub fn state_message_timeout(mut messages: Query<(&mut StateMessage, &mut Visibility)>) {
+    for (mut state_message, mut visibility) in &mut messages {
+        visibility.is_visible = state_message.frames_left > 0;
+        state_message.frames_left = state_message.frames_left.saturating_sub(1);
+    }
+}
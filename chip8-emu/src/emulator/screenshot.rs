@@ -0,0 +1,95 @@
+use crate::emulator::args::RunArgs;
+use crate::emulator::Emulator;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// the key for taking a screenshot while running
+const SCREENSHOT_KEY: KeyCode = KeyCode::F12;
+
+/// Get the brightness of a color, from 0 to 255
+fn luminance(color: Color) -> u8 {
+    let [r, g, b, _] = color.as_rgba_f32();
+    ((0.299 * r + 0.587 * g + 0.114 * b).clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+/// Write the display to a grayscale PNG
+/// Each display pixel is drawn as a `scale` by `scale` square, using the brightness of the on
+/// or off color.
+pub fn save_screenshot(
+    path: &Path,
+    display_size: (usize, usize),
+    is_on: impl Fn(usize, usize) -> bool,
+    on_color: Color,
+    off_color: Color,
+    scale: u32,
+) -> Result<(), String> {
+    let scale = scale as usize;
+    let (width, height) = (display_size.0 * scale, display_size.1 * scale);
+    let (on, off) = (luminance(on_color), luminance(off_color));
+
+    // one byte per image pixel, row by row
+    let mut data: Vec<u8> = Vec::with_capacity(width * height);
+    for y in 0..height {
+        for x in 0..width {
+            data.push(if is_on(x / scale, y / scale) { on } else { off });
+        }
+    }
+
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    encoder
+        .write_header()
+        .and_then(|mut writer| writer.write_image_data(&data))
+        .map_err(|e| e.to_string())
+}
+
+/// Save a screenshot of the emulator display
+fn screenshot_emulator(emu: &Emulator, args: &RunArgs, path: &Path) {
+    let result = save_screenshot(
+        path,
+        emu.state.display_size(),
+        |x, y| emu.state.get_pixel_color(x, y) > 0,
+        emu.palette[1],
+        emu.palette[0],
+        args.screenshot_scale,
+    );
+
+    match result {
+        Ok(_) => info!("Saved screenshot to {}", path.display()),
+        Err(e) => error!("Unable to save screenshot to {}: {}", path.display(), e),
+    }
+}
+
+/// Save a screenshot with a timestamped name when the screenshot key is pressed
+pub fn screenshot_input(keys: Res<Input<KeyCode>>, args: Res<RunArgs>, emu: Res<Emulator>) {
+    if !keys.just_pressed(SCREENSHOT_KEY) {
+        return;
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    screenshot_emulator(&emu, &args, Path::new(&format!("screenshot-{}.png", timestamp)));
+}
+
+/// Save a screenshot of the final display when the app exits, if asked to
+pub fn screenshot_on_exit(
+    exits: EventReader<AppExit>,
+    args: Res<RunArgs>,
+    emu: Res<Emulator>,
+) {
+    if exits.is_empty() || !args.screenshot_on_exit {
+        return;
+    }
+    exits.clear();
+
+    screenshot_emulator(&emu, &args, Path::new(&args.screenshot_output));
+}
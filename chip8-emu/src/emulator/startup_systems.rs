@@ -0,0 +1,223 @@
+use crate::chip8::{Chip8, Chip8Builder, HIRES_HEIGHT, HIRES_WIDTH};
+use crate::emulator::args::RunArgs;
+use crate::emulator::colors::get_palette;
+use crate::emulator::compare::{get_display_origin, get_layout_size, EMULATOR_A, EMULATOR_B};
+use crate::emulator::plugin::{Chip8Config, Chip8Rom};
+use crate::emulator::{Coordinate, Emulator, EmulatorTag};
+use crate::error::Chip8Error;
+use bevy::core_pipeline::clear_color::ClearColorConfig;
+use bevy::prelude::*;
+use rfd::{MessageButtons, MessageDialog, MessageLevel};
+use crate::emulator::util::{
+    get_camera_translation, get_grid_origin, get_pixel_size, get_pixel_translation,
+    get_sprite_size,
+};
+
+/// Make the camera
+pub fn camera_setup(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    args: Res<RunArgs>,
+    emu: Res<Emulator>,
+) {
+    let window = windows
+        .get_primary()
+        .expect("Unable to get primary window!");
+
+    let window_size = (window.width(), window.height());
+    let layout_size = get_layout_size(emu.resolution, &args);
+    let pixel_size: (f32, f32) = get_pixel_size(window_size, layout_size, args.maintain_aspect);
+
+    // the background is the off color set in emu_setup, unless a gap color is given
+    let clear_color = match args.gap_color {
+        Some(color) => ClearColorConfig::Custom(color.0),
+        None => ClearColorConfig::Default,
+    };
+
+    commands.spawn(Camera2dBundle {
+        camera_2d: Camera2d { clear_color },
+        transform: Transform {
+            translation: get_camera_translation(window_size, pixel_size),
+            ..default()
+        },
+        ..default()
+    });
+}
+
+/// Make the emulator state from the arguments
+pub fn make_chip8(args: &RunArgs) -> Result<Chip8, Chip8Error> {
+    let builder = get_builder(args).rom_path(args.rom());
+    let mut state = configure_chip8(builder.build()?, args);
+    apply_patches(&mut state, args)?;
+    Ok(state)
+}
+
+/// Get a builder with everything but the ROM set from the arguments
+fn get_builder(args: &RunArgs) -> Chip8Builder {
+    let mut builder = Chip8Builder::default()
+        .mode(args.mode)
+        .cycles_per_frame(args.cycles_per_frame);
+    if let Some(seed) = args.rng_seed {
+        builder = builder.seed(seed);
+    }
+    if let Some(font) = &args.font {
+        builder = builder.font_path(font);
+    }
+    if let Some(font_set) = &args.font_set {
+        builder = builder.font_set(font_set.clone());
+    }
+    if let Some(entry) = args.entry_point {
+        builder = builder.entry_point(entry);
+    }
+
+    builder
+}
+
+/// Apply the options that aren't part of the program state, such as after loading a save state
+pub fn configure_chip8(mut state: Chip8, args: &RunArgs) -> Chip8 {
+    state = state
+        .with_wrap_mode(args.wrap_mode)
+        .with_shift_quirk(args.shift_quirk)
+        .with_load_store_quirk(args.load_store_quirk)
+        .with_load_store_by_x_quirk(args.load_store_by_x_quirk)
+        .with_jump0_quirk(args.jump0_quirk)
+        .with_vblank_quirk(args.vblank_quirk)
+        .with_add_index_overflow_quirk(args.add_index_overflow_quirk)
+        .with_write_protect_font(args.protect_font)
+        .with_double_buffer(args.double_buffer)
+        .with_timer_frequency(args.timer_hz)
+        .with_timer_decrement(args.timer_decrement());
+    if args.trace {
+        state = state.with_trace(args.trace_depth);
+    }
+    if args.profile_opcodes {
+        state = state.with_opcode_profiler();
+    }
+
+    for &(addr, mode) in &args.watch {
+        if let Err(e) = state.add_watchpoint(addr, mode) {
+            warn!("Unable to watch {:#05x}: {}", addr, e);
+        }
+    }
+
+    state
+}
+
+/// Write the patches given in the arguments into memory, once the ROM is loaded
+fn apply_patches(state: &mut Chip8, args: &RunArgs) -> Result<(), Chip8Error> {
+    for (addr, bytes) in &args.patch {
+        state.inject_ram(*addr, bytes)?;
+    }
+
+    Ok(())
+}
+
+/// Make the emulator
+pub fn emu_setup(
+    mut commands: Commands,
+    args: Res<RunArgs>,
+    config: Res<Chip8Config>,
+    rom: Res<Chip8Rom>,
+) -> Result<(), Chip8Error> {
+    let mut state = configure_chip8(get_builder(&args).rom_bytes(&rom.0).build()?, &args);
+    apply_patches(&mut state, &args)?;
+    let palette = get_palette(&args);
+
+    // fill any space around the display with the off color
+    commands.insert_resource(ClearColor(palette[0]));
+
+    commands.insert_resource(Emulator {
+        resolution: state.display_size(),
+        slot: 0,
+        cycles_per_frame: state.cycles_per_frame(),
+        palette,
+        error: None,
+        decay: [[0; HIRES_WIDTH]; HIRES_HEIGHT],
+        brightness: [[0.0; HIRES_WIDTH]; HIRES_HEIGHT],
+        redraw: true,
+        layer: config.layer,
+        state,
+    });
+
+    Ok(())
+}
+
+/// Show any error from making the emulator in a dialog, then exit
+/// The rest of the startup systems need the emulator, so the app can't continue without it.
+pub fn report_setup_error(In(result): In<Result<(), Chip8Error>>) {
+    if let Err(e) = result {
+        error!("Unable to start emulator: {}", e);
+
+        MessageDialog::new()
+            .set_level(MessageLevel::Error)
+            .set_title("CHIP-8")
+            .set_description(&format!("Unable to start emulator: {}", e))
+            .set_buttons(MessageButtons::Ok)
+            .show();
+
+        std::process::exit(1);
+    }
+}
+
+/// Make all the pixels
+/// Pixels are made for high resolution mode, but only the ones on the display are visible.
+pub fn pixels_setup(
+    mut commands: Commands,
+    windows: Res<Windows>,
+    args: Res<RunArgs>,
+    emu: Res<Emulator>,
+) {
+    // the phosphor shader draws the whole display on one quad instead
+    if args.phosphor {
+        return;
+    }
+
+    let window = windows
+        .get_primary()
+        .expect("Unable to get primary window!");
+
+    let window_size = (window.width(), window.height());
+    let layout_size = get_layout_size(emu.resolution, &args);
+    let pixel_size: (f32, f32) = get_pixel_size(window_size, layout_size, args.maintain_aspect);
+    let origin = get_grid_origin(window_size, pixel_size, layout_size);
+
+    // make the pixels, with a second set for the emulator being compared against
+    let tags = if args.compare.is_some() {
+        vec![EmulatorTag(EMULATOR_A), EmulatorTag(EMULATOR_B)]
+    } else {
+        vec![EmulatorTag(EMULATOR_A)]
+    };
+    for tag in tags {
+        let display_origin = get_display_origin(origin, pixel_size, emu.resolution, &tag);
+        for x in 0..HIRES_WIDTH {
+            for y in 0..HIRES_HEIGHT {
+                commands.spawn((
+                    Coordinate { x, y },
+                    tag,
+                    SpriteBundle {
+                        sprite: Sprite {
+                            color: emu.palette[0],
+                            custom_size: Some(get_sprite_size(pixel_size, args.pixel_gap)),
+                            ..default()
+                        },
+                        transform: Transform {
+                            translation: get_pixel_translation(
+                                x,
+                                y,
+                                pixel_size,
+                                emu.resolution,
+                                display_origin,
+                                emu.layer,
+                            ),
+                            ..default()
+                        },
+                        visibility: Visibility {
+                            is_visible: x < emu.resolution.0 && y < emu.resolution.1,
+                        },
+                        ..default()
+                    },
+                ));
+            }
+        }
+    }
+}
@@ -0,0 +1,489 @@
+use crate::chip8::{FrameEvents, MemoryRegion};
+use crate::disasm::format_trace;
+use crate::emulator::args::RunArgs;
+use crate::emulator::colors::blend;
+use crate::emulator::compare::{get_display_origin, get_layout_size, EMULATOR_A};
+use crate::emulator::crash_dump::write_crash_dump;
+use crate::emulator::debugger::{
+    check_breakpoints, check_watchpoints, AppState, BreakpointHit, DebuggerState,
+};
+use crate::emulator::hud::CycleCounter;
+use crate::emulator::inspectors::LogInspector;
+use crate::emulator::keymap::Keymap;
+use crate::emulator::profiler::{Profiler, DISPLAY_UPDATE_TIME, INPUT_TIME, INSTRUCTION_TIME};
+use crate::emulator::recording::GifRecording;
+use crate::emulator::rewind::RewindState;
+use crate::emulator::{
+    get_window_title, Coordinate, EmuEvent, Emulator, EmulatorTag, TurboActive, GAMEPAD_MAP,
+    MAX_CYCLES_PER_FRAME, MIN_CYCLES_PER_FRAME, TURBO_KEY,
+};
+use crate::error::EmulatorError;
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::window::WindowResized;
+use std::time::Instant;
+use crate::emulator::util::{
+    get_camera_translation, get_grid_origin, get_pixel_size, get_pixel_translation,
+    get_sprite_size,
+};
+use crate::input::Input::{Pressed, Unpressed};
+
+// how much brighter a pixel that is on gets each frame in LCD mode, out of 1.0
+const LCD_RISE: f32 = 0.5;
+
+// how much dimmer a pixel that is off gets each frame in LCD mode, out of 1.0
+const LCD_FALL: f32 = 0.33;
+
+/// Update the display based on the emulator state
+#[allow(clippy::too_many_arguments)]
+pub fn update_display(
+    mut pixels_query: Query<(&Coordinate, &EmulatorTag, &mut Sprite)>,
+    args: Res<RunArgs>,
+    mut emu: ResMut<Emulator>,
+    app_state: Res<State<AppState>>,
+    rewind_state: Res<RewindState>,
+    mut recording: ResMut<GifRecording>,
+    mut events: EventWriter<EmuEvent>,
+    mut profiler: Profiler,
+) {
+    let start = Instant::now();
+
+    // only let time pass in the emulator while it is running forward
+    // otherwise, the display can change without a frame passing, so it is shown straight away
+    let running =
+        *app_state.current() == AppState::Running && *rewind_state == RewindState::Forward;
+    let display_updated = if running {
+        let FrameEvents {
+            sound_timer_expired,
+            delay_timer_expired,
+            display_updated,
+            ..
+        } = emu.state.do_frame_with_inspector(&mut LogInspector);
+        if sound_timer_expired {
+            events.send(EmuEvent::SoundTimerExpired);
+        }
+        if delay_timer_expired {
+            events.send(EmuEvent::DelayTimerExpired);
+        }
+        update_decay(&mut emu, args.persistence);
+        if args.lcd_mode {
+            emu.redraw |= update_brightness(&mut emu, LCD_RISE, LCD_FALL);
+        }
+        if let Some(recorder) = &mut recording.0 {
+            recorder.record_chip8(&emu.state);
+        }
+        display_updated
+    } else {
+        // when stepping, each pixel is shown as it is straight away
+        let display_updated = emu.state.present_display();
+        if args.lcd_mode && display_updated {
+            update_brightness(&mut emu, 1.0, 1.0);
+        }
+        display_updated
+    };
+
+    if display_updated {
+        events.send(EmuEvent::VramUpdated);
+    }
+
+    // the pixels only need recoloring if the display changed, or some are still fading out
+    if !display_updated && args.persistence == 0 && !emu.redraw {
+        profiler.record(DISPLAY_UPDATE_TIME, start);
+        return;
+    }
+    emu.redraw = false;
+
+    // update the pixels with the state, leaving any being compared against to compare_display
+    for (coord, _, mut pixel) in pixels_query
+        .iter_mut()
+        .filter(|(_, tag, _)| tag.0 == EMULATOR_A)
+    {
+        let color = emu.state.get_pixel_color(coord.x, coord.y) as usize;
+        let decay = emu.decay[coord.y][coord.x];
+
+        // pixels that were turned off fade out from the on color
+        pixel.color = if args.lcd_mode {
+            let brightness = emu.brightness[coord.y][coord.x];
+            blend(emu.palette[0], emu.palette[color.max(1)], brightness)
+        } else if color == 0 && decay > 0 {
+            blend(
+                emu.palette[0],
+                emu.palette[1],
+                decay as f32 / args.persistence as f32,
+            )
+        } else {
+            emu.palette[color]
+        };
+    }
+
+    profiler.record(DISPLAY_UPDATE_TIME, start);
+}
+
+/// Count down the frames left for each pixel to fade out
+/// Pixels that are on start over from the full number of frames.
+fn update_decay(emu: &mut Emulator, persistence: u8) {
+    if persistence == 0 {
+        return;
+    }
+
+    let (width, height) = emu.state.display_size();
+    for y in 0..height {
+        for x in 0..width {
+            emu.decay[y][x] = if emu.state.get_pixel_color(x, y) > 0 {
+                persistence
+            } else {
+                emu.decay[y][x].saturating_sub(1)
+            };
+        }
+    }
+}
+
+/// Fade each pixel towards on or off by the given amounts, returning whether any changed
+fn update_brightness(emu: &mut Emulator, rise: f32, fall: f32) -> bool {
+    let mut changed = false;
+
+    let (width, height) = emu.state.display_size();
+    for y in 0..height {
+        for x in 0..width {
+            let brightness = emu.brightness[y][x];
+            emu.brightness[y][x] = if emu.state.get_pixel_color(x, y) > 0 {
+                (brightness + rise).min(1.0)
+            } else {
+                (brightness - fall).max(0.0)
+            };
+            changed |= emu.brightness[y][x] != brightness;
+        }
+    }
+
+    changed
+}
+
+/// Do the next instruction
+#[allow(clippy::too_many_arguments)]
+pub fn do_next_instruction(
+    args: Res<RunArgs>,
+    turbo: Res<TurboActive>,
+    mut emu: ResMut<Emulator>,
+    mut debugger: ResMut<DebuggerState>,
+    mut app_state: ResMut<State<AppState>>,
+    mut counter: ResMut<CycleCounter>,
+    mut hits: EventWriter<BreakpointHit>,
+    mut events: EventWriter<EmuEvent>,
+    rewind_state: Res<RewindState>,
+    mut profiler: Profiler,
+) {
+    // the rewound state is shown without running it until rewinding stops
+    if *rewind_state != RewindState::Forward {
+        return;
+    }
+    let start = Instant::now();
+
+    // turbo runs more instructions in each frame, leaving the frame rate alone
+    // fast-forwarding to the --run-until address does the same
+    let cycles = if turbo.0 || debugger.run_until.is_some() {
+        emu.cycles_per_frame.saturating_mul(args.turbo_multiplier)
+    } else {
+        emu.cycles_per_frame
+    };
+
+    // with nothing to check between instructions, they can be run as one quicker batch
+    let checked = debugger.run_until.is_some()
+        || !debugger.breakpoints.breakpoints().is_empty()
+        || emu.state.has_watchpoints();
+    if !checked {
+        let result = emu.state.bulk_do_instructions(cycles);
+        counter.add(result.executed);
+        if let Some(e) = result.errors.into_iter().next() {
+            halt_on_error(&mut emu, &args, &mut app_state, &mut events, e);
+        }
+
+        profiler.record(INSTRUCTION_TIME, start);
+        return;
+    }
+
+    for _ in 0..cycles {
+        counter.add(1);
+
+        // stop running instructions if the emulator was halted
+        if run_instruction(&mut emu, &args, &mut app_state, &mut events) {
+            break;
+        }
+
+        // stop running instructions if a watched address was accessed
+        if check_watchpoints(&emu, &mut debugger, &mut app_state) {
+            break;
+        }
+
+        // stop running instructions if a breakpoint was hit
+        if check_breakpoints(&emu, &mut debugger, &mut hits) {
+            break;
+        }
+    }
+
+    profiler.record(INSTRUCTION_TIME, start);
+}
+
+/// Run a single instruction on the emulator, sending an event for it
+/// Returns true if the instruction failed and the emulator was halted.
+pub fn run_instruction(
+    emu: &mut Emulator,
+    args: &RunArgs,
+    app_state: &mut State<AppState>,
+    events: &mut EventWriter<EmuEvent>,
+) -> bool {
+    match emu.state.step_with_inspector(&mut LogInspector) {
+        Ok(opcode) => {
+            events.send(EmuEvent::OpcodeExecuted(opcode));
+            false
+        }
+        Err(e) => {
+            halt_on_error(emu, args, app_state, events, e);
+            true
+        }
+    }
+}
+
+/// Report an error from the emulator and halt it
+fn halt_on_error(
+    emu: &mut Emulator,
+    args: &RunArgs,
+    app_state: &mut State<AppState>,
+    events: &mut EventWriter<EmuEvent>,
+    e: EmulatorError,
+) {
+    if let EmulatorError::UnknownOpcode { opcode, pc } = &e {
+        events.send(EmuEvent::UnknownOpcode(*opcode, *pc));
+    }
+
+    let pc = emu.state.snapshot_registers().pc;
+    error!("Emulator error at {:#05x}: {}", pc, e);
+
+    // point out when the PC has left the program, which is often the real problem
+    let region = emu.state.memory_map().region_at(pc);
+    if region != MemoryRegion::ProgramCode {
+        error!("PC jumped into {} at {:#05x}", region, pc);
+    }
+
+    // show what led up to the error
+    let trace = emu.state.get_trace();
+    if !trace.is_empty() {
+        eprintln!("{}", format_trace(trace));
+    }
+    if args.dump_on_crash {
+        write_crash_dump(&emu.state);
+    }
+
+    emu.error = Some(e);
+    app_state
+        .set(AppState::Halted)
+        .expect("Unable to halt the emulator!");
+}
+
+/// Speed up or slow down the emulator
+/// + multiplies the number of instructions run each frame by 1.5, and - halves it.
+pub fn change_speed(
+    keys: Res<Input<KeyCode>>,
+    keymap: Res<Keymap>,
+    args: Res<RunArgs>,
+    mut emu: ResMut<Emulator>,
+    mut windows: ResMut<Windows>,
+) {
+    // keys used by the keymap are left to the program
+    let pressed = |key_code: &KeyCode| keys.just_pressed(*key_code) && !keymap.contains(*key_code);
+    let speed_up = [KeyCode::Plus, KeyCode::Equals, KeyCode::NumpadAdd]
+        .iter()
+        .any(pressed);
+    let slow_down = [KeyCode::Minus, KeyCode::NumpadSubtract].iter().any(pressed);
+
+    let cycles_per_frame = if speed_up {
+        ((emu.cycles_per_frame as f32 * 1.5).round() as u32).min(MAX_CYCLES_PER_FRAME)
+    } else if slow_down {
+        (emu.cycles_per_frame / 2).max(MIN_CYCLES_PER_FRAME)
+    } else {
+        return;
+    };
+    emu.cycles_per_frame = cycles_per_frame;
+
+    // show the new speed in the window title
+    windows
+        .get_primary_mut()
+        .expect("Unable to get primary window!")
+        .set_title(get_window_title(&args, cycles_per_frame));
+}
+
+/// Restart the program when F1 is pressed
+/// This also clears any error, so a halted program can be run again.
+pub fn reset_input(
+    keys: Res<Input<KeyCode>>,
+    mut emu: ResMut<Emulator>,
+    mut app_state: ResMut<State<AppState>>,
+) {
+    if !keys.just_pressed(KeyCode::F1) {
+        return;
+    }
+
+    emu.state.reset();
+    emu.error = None;
+
+    if *app_state.current() == AppState::Halted {
+        app_state
+            .set(AppState::Running)
+            .expect("Unable to resume the emulator!");
+    }
+}
+
+/// Lay out the pixels again if the emulator has changed resolution
+pub fn resolution_change(
+    windows: Res<Windows>,
+    mut emu: ResMut<Emulator>,
+    mut events: EventWriter<WindowResized>,
+) {
+    let resolution = emu.state.display_size();
+    if resolution == emu.resolution {
+        return;
+    }
+    emu.resolution = resolution;
+
+    let window = windows
+        .get_primary()
+        .expect("Unable to get primary window!");
+
+    // reuse the window resizing systems to resize the pixel grid
+    events.send(WindowResized {
+        id: window.id(),
+        width: window.width(),
+        height: window.height(),
+    });
+}
+
+/// Close the emulator if the program has asked to exit
+pub fn exit_on_request(emu: Res<Emulator>, mut exit: EventWriter<AppExit>) {
+    if emu.state.has_exited() {
+        exit.send(AppExit);
+    }
+}
+
+/// Manage pixels upon window resizing
+pub fn window_resize_pixel(
+    mut events: EventReader<WindowResized>,
+    args: Res<RunArgs>,
+    emu: Res<Emulator>,
+    mut pixels: Query<(
+        &Coordinate,
+        &EmulatorTag,
+        &mut Sprite,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+) {
+    for event in events.iter() {
+
+        // get the size of a pixel, and where the grid starts so that it is centered
+        let window_size = (event.width, event.height);
+        let layout_size = get_layout_size(emu.resolution, &args);
+        let pixel_size: (f32, f32) =
+            get_pixel_size(window_size, layout_size, args.maintain_aspect);
+        let origin = get_grid_origin(window_size, pixel_size, layout_size);
+
+        // change the size and translation of each pixel
+        for (coord, tag, mut pixel, mut transform, mut visibility) in &mut pixels {
+            // change the location of the pixel
+            transform.translation = get_pixel_translation(
+                coord.x,
+                coord.y,
+                pixel_size,
+                emu.resolution,
+                get_display_origin(origin, pixel_size, emu.resolution, tag),
+                emu.layer,
+            );
+
+            // change the size of the pixel
+            pixel.custom_size = Some(get_sprite_size(pixel_size, args.pixel_gap));
+
+            // hide the pixel if it is not on the display in this resolution
+            visibility.is_visible = coord.x < emu.resolution.0 && coord.y < emu.resolution.1;
+        }
+    }
+}
+/// Manage camera upon window resizing
+pub fn window_resize_camera(
+    mut events: EventReader<WindowResized>,
+    args: Res<RunArgs>,
+    emu: Res<Emulator>,
+    mut cameras: Query<&mut Transform, With<Camera>>,
+) {
+    for event in events.iter() {
+
+        // get the size of a pixel
+        let window_size = (event.width, event.height);
+        let layout_size = get_layout_size(emu.resolution, &args);
+        let pixel_size: (f32, f32) =
+            get_pixel_size(window_size, layout_size, args.maintain_aspect);
+
+        // change the camera translation
+        for mut camera in &mut cameras {
+            camera.translation = get_camera_translation(window_size, pixel_size);
+        }
+
+    }
+}
+
+/// Manage user input
+pub fn get_input(
+    inputs: Res<Input<KeyCode>>,
+    keymap: Res<Keymap>,
+    mut emu: ResMut<Emulator>,
+    mut turbo: ResMut<TurboActive>,
+    mut events: EventWriter<EmuEvent>,
+    mut profiler: Profiler,
+) {
+    let start = Instant::now();
+    turbo.0 = inputs.pressed(TURBO_KEY);
+
+    // process each keycode in the keymap
+    for (kc, input) in keymap.0 {
+        if inputs.just_pressed(kc) {
+            emu.state
+                .change_input(Pressed(input))
+                .expect("Unable to change input!");
+            events.send(EmuEvent::InputChanged(input, true));
+        } else if inputs.just_released(kc) {
+            emu.state
+                .change_input(Unpressed(input))
+                .expect("Unable to change input!");
+            events.send(EmuEvent::InputChanged(input, false));
+        }
+    }
+
+    profiler.record(INPUT_TIME, start);
+}
+
+/// Get input from the chosen gamepad, if it is connected
+pub fn get_gamepad_input(
+    gamepads: Res<Gamepads>,
+    inputs: Res<Input<GamepadButton>>,
+    args: Res<RunArgs>,
+    mut emu: ResMut<Emulator>,
+    mut events: EventWriter<EmuEvent>,
+) {
+    let gamepad = Gamepad::new(args.gamepad_index);
+    if args.no_gamepad || !gamepads.contains(gamepad) {
+        return;
+    }
+
+    // process each button in the gamepad map
+    for (button_type, input) in GAMEPAD_MAP {
+        let button = GamepadButton::new(gamepad, button_type);
+        if inputs.just_pressed(button) {
+            emu.state
+                .change_input(Pressed(input))
+                .expect("Unable to change input!");
+            events.send(EmuEvent::InputChanged(input, true));
+        } else if inputs.just_released(button) {
+            emu.state
+                .change_input(Unpressed(input))
+                .expect("Unable to change input!");
+            events.send(EmuEvent::InputChanged(input, false));
+        }
+    }
+}
@@ -0,0 +1,206 @@
+use crate::chip8::Chip8;
+use crate::disasm::format_trace;
+use crate::emulator::args::RunArgs;
+use crate::emulator::crash_dump::write_crash_dump;
+use crate::emulator::startup_systems::make_chip8;
+use crate::input::Input;
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::style::Print;
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use std::io::{self, Stdout, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// time between frames
+const FRAME_DURATION: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+// terminals only report keypresses, not releases, so keys are let go after this many frames
+// this is a little longer than the usual delay before a held key starts repeating
+const KEY_HOLD_FRAMES: u8 = 30;
+
+// the original CHIP-8 keypad, laid over the left side of a QWERTY keyboard
+const QWERTY: [(char, u8); 16] = [
+    ('1', 0x1),
+    ('2', 0x2),
+    ('3', 0x3),
+    ('4', 0xC),
+    ('q', 0x4),
+    ('w', 0x5),
+    ('e', 0x6),
+    ('r', 0xD),
+    ('a', 0x7),
+    ('s', 0x8),
+    ('d', 0x9),
+    ('f', 0xE),
+    ('z', 0xA),
+    ('x', 0x0),
+    ('c', 0xB),
+    ('v', 0xF),
+];
+
+/// Puts the terminal back as it was when dropped, even if the emulator stops early
+struct TerminalGuard {
+    stdout: Stdout,
+}
+
+impl TerminalGuard {
+    /// Switch the terminal to raw mode and a blank screen
+    fn new() -> io::Result<Self> {
+        let mut stdout = io::stdout();
+        terminal::enable_raw_mode()?;
+        execute!(stdout, EnterAlternateScreen, Hide, Clear(ClearType::All))?;
+
+        Ok(Self { stdout })
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        // there's nothing to be done if the terminal can't be restored
+        let _ = execute!(self.stdout, Show, LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Run the emulator in the terminal, drawing the display with block characters
+/// Instructions and frames are run at the same rate as in a window, until the program exits,
+/// an error occurs, or escape is pressed.
+pub fn run_emulator_terminal(args: &RunArgs) {
+    let mut state = match make_chip8(args) {
+        Ok(state) => state,
+        Err(e) => {
+            eprintln!("Unable to start emulator: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    // the terminal has to be restored before any error is printed
+    let result = TerminalGuard::new().and_then(|mut guard| run_frames(&mut state, &mut guard));
+    match result {
+        Ok(None) => {}
+        Ok(Some(message)) => {
+            eprintln!("{}", message);
+            if args.dump_on_crash {
+                write_crash_dump(&state);
+            }
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("Terminal error: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Run frames until the program stops, returning a message if it stopped because of an error
+fn run_frames(state: &mut Chip8, guard: &mut TerminalGuard) -> io::Result<Option<String>> {
+    let mut held: [u8; 16] = [0; 16];
+    let mut resolution = state.display_size();
+    let mut next_frame = Instant::now();
+
+    loop {
+        if !read_input(state, &mut held)? {
+            return Ok(None);
+        }
+
+        for _ in 0..state.cycles_per_frame() {
+            let pc = state.snapshot_registers().pc;
+            if let Err(e) = state.do_next_instruction() {
+                let mut message = format!("Emulator error at {:#05x}: {}", pc, e);
+
+                // show what led up to the error
+                let trace = state.get_trace();
+                if !trace.is_empty() {
+                    message = format!("{}\n{}", message, format_trace(trace));
+                }
+
+                return Ok(Some(message));
+            }
+        }
+
+        state.do_frame();
+
+        // clear what was drawn at the old resolution, as the rows are now a different length
+        if state.display_size() != resolution {
+            resolution = state.display_size();
+            queue!(guard.stdout, Clear(ClearType::All))?;
+        }
+        draw_display(state, &mut guard.stdout)?;
+        if state.has_exited() {
+            return Ok(None);
+        }
+
+        // wait for the next frame
+        next_frame += FRAME_DURATION;
+        if let Some(wait) = next_frame.checked_duration_since(Instant::now()) {
+            thread::sleep(wait);
+        }
+    }
+}
+
+/// Pass on any keys pressed since the last frame, and let go of keys that have been held long
+/// enough. Returns false if the user asked to quit.
+fn read_input(state: &mut Chip8, held: &mut [u8; 16]) -> io::Result<bool> {
+    // count down the keys that are held, releasing them when they run out
+    for (key, frames) in held.iter_mut().enumerate() {
+        if *frames > 0 {
+            *frames -= 1;
+            if *frames == 0 {
+                state
+                    .change_input(Input::Unpressed(key as u8))
+                    .expect("Unable to change input!");
+            }
+        }
+    }
+
+    while event::poll(Duration::ZERO)? {
+        let Event::Key(event) = event::read()? else {
+            continue;
+        };
+
+        // raw mode swallows ctrl+c, so it has to be handled here along with escape
+        let ctrl_c = event.code == KeyCode::Char('c')
+            && event.modifiers.contains(KeyModifiers::CONTROL);
+        if event.code == KeyCode::Esc || ctrl_c {
+            return Ok(false);
+        }
+
+        let KeyCode::Char(c) = event.code else {
+            continue;
+        };
+        let Some(&(_, key)) = QWERTY.iter().find(|(k, _)| *k == c.to_ascii_lowercase()) else {
+            continue;
+        };
+
+        // some terminals do report releases, which can be used directly
+        if event.kind == KeyEventKind::Release {
+            held[key as usize] = 0;
+            state
+                .change_input(Input::Unpressed(key))
+                .expect("Unable to change input!");
+        } else {
+            held[key as usize] = KEY_HOLD_FRAMES;
+            state
+                .change_input(Input::Pressed(key))
+                .expect("Unable to change input!");
+        }
+    }
+
+    Ok(true)
+}
+
+/// Draw the display, with a full block for each pixel that is on
+fn draw_display(state: &Chip8, stdout: &mut Stdout) -> io::Result<()> {
+    let (width, height) = state.display_size();
+
+    for y in 0..height {
+        let row: String = (0..width)
+            .map(|x| if state.get_pixel_color(x, y) > 0 { '█' } else { ' ' })
+            .collect();
+        queue!(stdout, MoveTo(0, y as u16), Print(row))?;
+    }
+
+    stdout.flush()
+}
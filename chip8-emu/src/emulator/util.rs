@@ -0,0 +1,77 @@
+use bevy::math::{Vec2, Vec3};
+
+// the z of the camera, just in front of the far end of the default 2D projection
+const CAMERA_Z: f32 = 999.9;
+
+/// Get the size of a pixel for a given window size and display size
+/// With `maintain_aspect`, pixels are kept square and a whole number of window pixels in size,
+/// so the display is letterboxed if the window is a different shape. Otherwise, the pixels are
+/// stretched to fill the window.
+pub fn get_pixel_size(
+    window_size: (f32, f32),
+    display_size: (usize, usize),
+    maintain_aspect: bool,
+) -> (f32, f32) {
+    let stretched = (
+        window_size.0 / display_size.0 as f32,
+        window_size.1 / display_size.1 as f32,
+    );
+    if !maintain_aspect {
+        return stretched;
+    }
+
+    // pixels can't be smaller than one window pixel
+    let size = f32::min(stretched.0, stretched.1).floor().max(1.0);
+    (size, size)
+}
+
+/// Get the size to draw a pixel at, leaving a gap between it and the next pixel
+pub fn get_sprite_size(pixel_size: (f32, f32), gap: f32) -> Vec2 {
+    Vec2::new(
+        (pixel_size.0 - gap).max(0.0),
+        (pixel_size.1 - gap).max(0.0),
+    )
+}
+
+/// Get the offset of the pixel grid that centers it in the window
+pub fn get_grid_origin(
+    window_size: (f32, f32),
+    pixel_size: (f32, f32),
+    display_size: (usize, usize),
+) -> Vec2 {
+    let grid_size = (
+        display_size.0 as f32 * pixel_size.0,
+        display_size.1 as f32 * pixel_size.1,
+    );
+    Vec2::new(
+        ((window_size.0 - grid_size.0) / 2.0).round(),
+        ((window_size.1 - grid_size.1) / 2.0).round(),
+    )
+}
+
+/// Get the camera translation
+/// The camera sits where bevy puts 2D cameras by default, so everything from a z of 0 up to
+/// just below `CAMERA_Z` is in view.
+pub fn get_camera_translation(window_size: (f32, f32), pixel_size: (f32, f32)) -> Vec3 {
+    Vec3::new(
+        (window_size.0 / 2.0) - (pixel_size.0 / 2.0),
+        (window_size.1 / 2.0) + (pixel_size.1 / 2.0),
+        CAMERA_Z,
+    )
+}
+
+/// Get the translation for a certain pixel, drawn at the given z
+pub fn get_pixel_translation(
+    coord_x: usize,
+    coord_y: usize,
+    pixel_size: (f32, f32),
+    display_size: (usize, usize),
+    origin: Vec2,
+    layer: f32,
+) -> Vec3 {
+    Vec3::new(
+        origin.x + coord_x as f32 * pixel_size.0,
+        origin.y + (display_size.1 as f32 * pixel_size.1) - (coord_y as f32 * pixel_size.1),
+        layer,
+    )
+}
@@ -0,0 +1,36 @@
+extern crate core;
+
+use crate::cli::{Cli, Commands};
+use crate::emulator::run;
+use chip8_core::{analysis, chip8, disasm, error, input, instructions, validator};
+use clap::{CommandFactory, FromArgMatches};
+
+mod assembler;
+mod cli;
+mod commands;
+mod emulator;
+
+fn main() {
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+
+    // the window has its own logger, which is set up by bevy
+    if !matches!(cli.command, Commands::Run(_)) {
+        env_logger::init();
+    }
+
+    match cli.command {
+        Commands::Run(mut args) => {
+            if let Some(("run", run_matches)) = matches.subcommand() {
+                args.load_metadata(run_matches);
+            }
+            run(args);
+        }
+        Commands::Disasm(args) => commands::disasm::run(&args),
+        Commands::Analyze(args) => commands::analyze::run(&args),
+        Commands::Asm(args) => commands::asm::run(&args),
+        Commands::Test(args) => commands::test::run(&args),
+        #[cfg(feature = "debug")]
+        Commands::Repl(args) => commands::repl::run(&args),
+    }
+}
@@ -0,0 +1,118 @@
+//! JavaScript bindings for the CHIP-8 virtual machine
+//!
+//! Build with `wasm-pack build --target web chip8-wasm`, then serve the crate directory and open
+//! `www/index.html` for a small demo.
+
+#![warn(missing_docs)]
+
+use chip8_core::chip8::{Chip8, Chip8Builder, HIRES_HEIGHT, HIRES_WIDTH};
+use chip8_core::input::Input;
+use js_sys::{Math, Uint8Array, WebAssembly};
+use wasm_bindgen::prelude::*;
+use wasm_bindgen::JsCast;
+
+/// The font loaded into every machine
+const FONT: &[u8] = include_bytes!("../../fonts/default_font.ch8");
+
+/// A CHIP-8 virtual machine that can be driven from JavaScript
+#[wasm_bindgen]
+pub struct Chip8Wasm {
+    /// The machine being driven
+    chip8: Chip8,
+
+    /// The display as of the last frame, one byte per pixel
+    /// This is kept between frames so that JavaScript can view it without a copy being made.
+    frame: Box<[u8]>,
+}
+
+#[wasm_bindgen]
+impl Chip8Wasm {
+    /// Make a new machine running the given ROM
+    #[wasm_bindgen(constructor)]
+    pub fn new(rom_bytes: &[u8]) -> Result<Chip8Wasm, JsValue> {
+        // there is no OS to seed the random numbers from, so ask the browser instead
+        let seed = (Math::random() * u64::MAX as f64) as u64;
+
+        let chip8 = Chip8Builder::default()
+            .font_bytes(FONT)
+            .rom_bytes(rom_bytes)
+            .seed(seed)
+            .build()
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+
+        Ok(Chip8Wasm {
+            chip8,
+            frame: vec![0; HIRES_WIDTH * HIRES_HEIGHT].into_boxed_slice(),
+        })
+    }
+
+    /// Run the next n instructions
+    /// Stops early if the program exits.
+    pub fn step(&mut self, n: u32) -> Result<(), JsValue> {
+        for _ in 0..n {
+            if self.chip8.has_exited() {
+                break;
+            }
+
+            self.chip8
+                .do_next_instruction()
+                .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the number of instructions that should be run each frame
+    pub fn cycles_per_frame(&self) -> u32 {
+        self.chip8.cycles_per_frame()
+    }
+
+    /// Count down the timers and get the display
+    /// The display is returned as a view into the machine's memory, 128x64 with one byte per
+    /// pixel, which is only valid until the next call. Outside of high resolution mode, only the
+    /// top left corner is used.
+    pub fn do_frame(&mut self) -> Uint8Array {
+        let vram = self.chip8.do_frame().vram;
+        for (y, row) in vram.iter().enumerate() {
+            for (x, &pixel) in row.iter().enumerate() {
+                self.frame[y * HIRES_WIDTH + x] = pixel as u8;
+            }
+        }
+
+        let memory: WebAssembly::Memory = wasm_bindgen::memory().unchecked_into();
+        Uint8Array::new_with_byte_offset_and_length(
+            &memory.buffer(),
+            self.frame.as_ptr() as u32,
+            self.frame.len() as u32,
+        )
+    }
+
+    /// Get the width of the display in the current resolution mode
+    pub fn display_width(&self) -> usize {
+        self.chip8.display_size().0
+    }
+
+    /// Get the height of the display in the current resolution mode
+    pub fn display_height(&self) -> usize {
+        self.chip8.display_size().1
+    }
+
+    /// Press a key on the keypad, failing if it isn't one of the 16 keys
+    pub fn change_input_pressed(&mut self, key: u8) -> Result<(), JsValue> {
+        self.chip8
+            .change_input(Input::Pressed(key))
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Release a key on the keypad, failing if it isn't one of the 16 keys
+    pub fn change_input_released(&mut self, key: u8) -> Result<(), JsValue> {
+        self.chip8
+            .change_input(Input::Unpressed(key))
+            .map_err(|e| JsValue::from_str(&e.to_string()))
+    }
+
+    /// Check if a tone is playing
+    pub fn get_tone(&self) -> bool {
+        self.chip8.get_tone()
+    }
+}
@@ -1,4 +0,0 @@
-pub enum Input {
-    Pressed(u8),
-    Unpressed(u8),
-}